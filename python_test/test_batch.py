@@ -0,0 +1,39 @@
+import os
+import tempfile
+
+import blitz_parse
+
+print("--- Testing batch extraction errors ---")
+
+with tempfile.TemporaryDirectory() as tmp:
+    broken = os.path.join(tmp, "broken.docx")
+    with open(broken, "wb") as f:
+        f.write(b"not a zip file")
+    unknown = os.path.join(tmp, "data.unknownext")
+    with open(unknown, "wb") as f:
+        f.write(b"\x00\x01\x02")
+
+    paths = ["tests/assets/sample.docx", broken, unknown]
+    results = blitz_parse.extract_many_py(paths, threads=2)
+    assert [r["path"] for r in results] == paths
+
+    # 1. A file that extracts has text and no error
+    assert results[0]["text"] and results[0]["exception"] is None
+    print("✅ sample.docx extracted.")
+
+    # 2. A failed file keeps the exception class and its context
+    exception = results[1]["exception"]
+    assert isinstance(exception, blitz_parse.CorruptDocumentError), exception
+    assert exception.path == broken and exception.format == "docx"
+    assert results[1]["error"] == str(exception)
+    print(f"✅ broken.docx: {type(exception).__name__}: {exception}")
+
+    exception = results[2]["exception"]
+    assert isinstance(exception, blitz_parse.UnsupportedFormatError), exception
+    print(f"✅ data.unknownext: {type(exception).__name__}: {exception}")
+
+    # 3. The exception can be raised as it would be for a single file
+    try:
+        raise results[1]["exception"]
+    except blitz_parse.BlitzParseError as e:
+        print(f"✅ Re-raised: {e.category}")
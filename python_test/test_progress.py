@@ -0,0 +1,39 @@
+import blitz_parse
+
+pdf_path = "tests/assets/sample.pdf"
+
+print("--- Testing progress callbacks ---")
+
+
+class StopExtraction(Exception):
+    pass
+
+
+# 1. A callback that only records the updates lets the extraction finish
+updates = []
+options = blitz_parse.ExtractOptions(progress=updates.append)
+text = blitz_parse.extract_text_py(pdf_path, options)
+assert text, "expected text from the PDF"
+assert updates, "expected progress updates"
+assert updates[-1]["stage"] == "pdf" and updates[-1]["unit"] == "pages"
+print(f"✅ Received {len(updates)} progress updates.")
+
+
+# 2. A callback that raises stops the extraction with its own exception
+def stop(update):
+    raise StopExtraction(f"stopped at {update['done']} {update['unit']}")
+
+
+options = blitz_parse.ExtractOptions(progress=stop)
+try:
+    blitz_parse.extract_text_py(pdf_path, options)
+    raise AssertionError("expected the callback's exception")
+except StopExtraction as e:
+    print(f"✅ Extraction stopped by the callback: {e}")
+
+# 3. The same options can run another extraction once the error is raised
+try:
+    blitz_parse.extract_text_py(pdf_path, options)
+    raise AssertionError("expected the callback's exception")
+except StopExtraction:
+    print("✅ The callback stops the next extraction too.")
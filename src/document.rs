@@ -0,0 +1,181 @@
+use crate::errors::{BlitzParseError, ErrorContext};
+use crate::graph_builder::models::Section;
+use crate::normalize::{normalize, NormalizeOptions};
+use crate::provenance::{normalize_ranges, Provenance};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A parse that may have skipped parts: the document and what failed.
+pub(crate) type Partial = (ParsedDocument, Vec<BlitzParseError>);
+
+/// The format-independent result of parsing one file.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedDocument {
+    /// Plain-text content, as returned by `extract_text`.
+    pub text: String,
+    /// Document-level metadata such as `title` or `creator`, in the same flat
+    /// form `GraphBuilder` takes.
+    pub metadata: HashMap<String, String>,
+    /// The document outline, if the format has one. When non-empty, the graph
+    /// is built from sections rather than from `text`.
+    pub sections: Vec<Section>,
+    /// Problems that were recovered from, such as an unreadable page.
+    pub warnings: Vec<Warning>,
+    pub strategy: Strategy,
+    /// Where ranges of `text` came from, in text order without overlaps.
+    /// Empty for formats that don't record it.
+    pub provenance: Vec<Provenance>,
+}
+
+impl ParsedDocument {
+    pub fn from_text(text: String) -> Self {
+        ParsedDocument {
+            text,
+            ..Default::default()
+        }
+    }
+
+    /// Apply the normalization steps in `options` to the text and sections.
+    /// With provenance, each range is normalized on its own so its offsets
+    /// stay exact; words hyphenated across two ranges are left split.
+    pub fn normalize(&mut self, options: &NormalizeOptions) {
+        if self.provenance.is_empty() {
+            self.text = normalize(&self.text, options);
+        } else {
+            (self.text, self.provenance) = normalize_ranges(&self.text, &self.provenance, options);
+        }
+        for section in &mut self.sections {
+            section.content = normalize(&section.content, options);
+        }
+    }
+
+    /// Record parts that failed to extract as `Skipped` warnings.
+    pub fn with_failures(mut self, failures: &[BlitzParseError]) -> Self {
+        self.warnings.extend(
+            failures
+                .iter()
+                .map(|e| Warning::from_error(WarningKind::Skipped, e)),
+        );
+        self
+    }
+
+    /// The text of a partly extracted document, for the APIs that return
+    /// only text: any failures are reported in a `PartialSuccess` error.
+    pub(crate) fn into_text(
+        self,
+        failures: Vec<BlitzParseError>,
+    ) -> Result<String, BlitzParseError> {
+        if failures.is_empty() {
+            Ok(self.text)
+        } else {
+            Err(BlitzParseError::PartialSuccess {
+                text: self.text,
+                failures,
+                context: ErrorContext::default(),
+            })
+        }
+    }
+
+    /// Accept a partial result from a parser that reports one as an error: a
+    /// `PartialSuccess` becomes a document of the text that was extracted,
+    /// with a warning for each failure. The built-in parsers return partial
+    /// documents directly, keeping their provenance and metadata.
+    pub fn recover(
+        result: Result<ParsedDocument, BlitzParseError>,
+    ) -> Result<ParsedDocument, BlitzParseError> {
+        match result {
+            Err(BlitzParseError::PartialSuccess { text, failures, .. }) => Ok(ParsedDocument {
+                text,
+                warnings: failures
+                    .iter()
+                    .map(|e| Warning::from_error(WarningKind::Skipped, e))
+                    .collect(),
+                ..Default::default()
+            }),
+            result => result,
+        }
+    }
+}
+
+/// Text extracted from a file, with any problems that were worked around.
+#[derive(Debug, Clone, Default)]
+pub struct ExtractResult {
+    pub content: String,
+    pub warnings: Vec<Warning>,
+    /// Which of the format's extraction paths produced `content`; the reason
+    /// for a fallback is among the `warnings`.
+    pub strategy: Strategy,
+    /// Where ranges of `content` came from; see `ParsedDocument::provenance`.
+    pub provenance: Vec<Provenance>,
+}
+
+impl From<ParsedDocument> for ExtractResult {
+    fn from(document: ParsedDocument) -> Self {
+        ExtractResult {
+            content: document.text,
+            warnings: document.warnings,
+            strategy: document.strategy,
+            provenance: document.provenance,
+        }
+    }
+}
+
+/// How a document's text was extracted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// The format's usual parser.
+    #[default]
+    FastPath,
+    /// A slower, more forgiving parser that may keep less of the document,
+    /// e.g. DOCX without tables.
+    Fallback,
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Strategy::FastPath => "fast_path",
+            Strategy::Fallback => "fallback",
+        })
+    }
+}
+
+/// A problem extraction recovered from instead of failing the document.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    /// The error that was recovered from.
+    pub message: String,
+    /// Where it happened, as far as is known.
+    pub context: ErrorContext,
+}
+
+impl Warning {
+    pub fn from_error(kind: WarningKind, error: &BlitzParseError) -> Self {
+        Warning {
+            kind,
+            message: error.to_string(),
+            context: error.context().cloned().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    /// A page, part or chapter, or the rest of one, could not be read and is
+    /// missing from the text.
+    Skipped,
+    /// The fast path failed, so the document was read with the fallback.
+    Fallback,
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WarningKind::Skipped => "skipped",
+            WarningKind::Fallback => "fallback",
+        })
+    }
+}
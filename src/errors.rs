@@ -1,13 +1,117 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
 use std::fmt;
 
+/// The underlying error behind a structured `BlitzParseError`.
+pub type Cause = Box<dyn Error + Send + Sync + 'static>;
+
+/// Where in a file an error was found, as far as it is known.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ErrorContext {
+    /// The file being extracted; filled in by the top-level entry points.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// The part within the file, e.g. `word/document.xml` or `page 3`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part: Option<String>,
+    /// Byte offset into the part, or into the file when there is no part.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+}
+
+impl ErrorContext {
+    pub fn part(part: impl Into<String>) -> Self {
+        ErrorContext {
+            part: Some(part.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn at(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, " in {}", path)?;
+        }
+        if let Some(part) = &self.part {
+            write!(f, " at {}", part)?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " (byte {})", offset)?;
+        }
+        Ok(())
+    }
+}
+
 // A custom error enum for our library. This gives us precise,
 // informative errors for different failure scenarios.
 #[derive(Debug)]
 pub enum BlitzParseError {
     Io(std::io::Error),
-    Pdf(String),
-    Docx(String),
-    UnsupportedFileType(String),
+    /// The extraction's `CancellationToken` was cancelled.
+    Cancelled,
+    /// A bound set in `ExtractOptions` was reached before the document was done.
+    LimitExceeded {
+        kind: LimitKind,
+        context: ErrorContext,
+    },
+    /// The file, or its container (ZIP, PDF object structure, XML part), is
+    /// damaged or not valid for its format.
+    CorruptContainer {
+        format: &'static str,
+        context: ErrorContext,
+        source: Option<Cause>,
+    },
+    /// A part the format requires, named in `context.part`, is absent.
+    MissingPart {
+        format: &'static str,
+        context: ErrorContext,
+    },
+    /// The document is password-protected and could not be opened.
+    Encrypted {
+        format: &'static str,
+        context: ErrorContext,
+        source: Option<Cause>,
+    },
+    /// Text could not be decoded from the detected encoding.
+    Encoding {
+        encoding: &'static str,
+        context: ErrorContext,
+    },
+    /// Some parts were extracted into `text`, but the others failed.
+    PartialSuccess {
+        text: String,
+        failures: Vec<BlitzParseError>,
+        context: ErrorContext,
+    },
+    /// The progress callback failed; `source` is its error.
+    Callback {
+        context: ErrorContext,
+        source: Cause,
+    },
+    /// A parser panicked; the message is the panic payload.
+    Panicked {
+        message: String,
+        context: ErrorContext,
+    },
+    /// A parser registered from Python raised an exception.
+    Plugin {
+        name: String,
+        context: ErrorContext,
+        source: Option<Cause>,
+    },
+    /// No parser handles `format`: an extension, or a description such as
+    /// "No extension".
+    UnsupportedFileType {
+        format: String,
+        context: ErrorContext,
+    },
 }
 
 // This allows our error type to be displayed nicely.
@@ -15,17 +119,252 @@ impl fmt::Display for BlitzParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             BlitzParseError::Io(e) => write!(f, "I/O Error: {}", e),
-            BlitzParseError::Pdf(e) => write!(f, "PDF Parsing Error: {}", e),
-            BlitzParseError::Docx(e) => write!(f, "DOCX Parsing Error: {}", e),
-            BlitzParseError::UnsupportedFileType(ext) => {
-                write!(f, "Unsupported file type: {}", ext)
+            BlitzParseError::Cancelled => write!(f, "Extraction cancelled"),
+            BlitzParseError::LimitExceeded { kind, context } => {
+                write!(f, "Limit exceeded: {}{}", kind, context)
+            }
+            BlitzParseError::CorruptContainer {
+                format,
+                context,
+                source,
+            } => {
+                write!(f, "Corrupt {} container{}", format, context)?;
+                match source {
+                    Some(source) => write!(f, ": {}", source),
+                    None => Ok(()),
+                }
+            }
+            BlitzParseError::MissingPart { format, context } => {
+                write!(f, "Missing {} part{}", format, context)
+            }
+            BlitzParseError::Encrypted {
+                format, context, ..
+            } => write!(f, "Encrypted {} document{}", format, context),
+            BlitzParseError::Encoding { encoding, context } => {
+                write!(
+                    f,
+                    "Text Decoding Error: input is not valid {}{}",
+                    encoding, context
+                )
+            }
+            BlitzParseError::PartialSuccess {
+                failures, context, ..
+            } => {
+                write!(
+                    f,
+                    "Partially extracted{}: {} part(s) failed",
+                    context,
+                    failures.len()
+                )?;
+                match failures.first() {
+                    Some(first) => write!(f, ", first: {}", first),
+                    None => Ok(()),
+                }
+            }
+            BlitzParseError::Callback { context, source } => {
+                write!(f, "Progress callback failed{}: {}", context, source)
+            }
+            BlitzParseError::Panicked { message, context } => {
+                write!(f, "Parser panicked{}: {}", context, message)
+            }
+            BlitzParseError::Plugin {
+                name,
+                context,
+                source,
+            } => {
+                write!(f, "Parser plugin {} failed{}", name, context)?;
+                match source {
+                    Some(source) => write!(f, ": {}", source),
+                    None => Ok(()),
+                }
+            }
+            BlitzParseError::UnsupportedFileType { format, context } => {
+                write!(f, "Unsupported file type: {}{}", format, context)
             }
         }
     }
 }
 
+/// The `ExtractOptions` bound behind a `LimitExceeded` error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    InputBytes,
+    EntryBytes,
+    XmlDepth,
+    XmlElements,
+    Pages,
+    Deadline,
+    /// `ArchiveLimits::max_entries`, counted across nested containers.
+    Entries,
+    /// `ArchiveLimits::max_total_bytes`, counted across nested containers.
+    TotalBytes,
+    /// `ArchiveLimits::max_depth`, for archives nested in archives.
+    ArchiveDepth,
+}
+
+impl fmt::Display for LimitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LimitKind::InputBytes => "input size",
+            LimitKind::EntryBytes => "decompressed entry size",
+            LimitKind::XmlDepth => "XML nesting depth",
+            LimitKind::XmlElements => "XML element count",
+            LimitKind::Pages => "page count",
+            LimitKind::Deadline => "deadline",
+            LimitKind::Entries => "archive entry count",
+            LimitKind::TotalBytes => "total decompressed size",
+            LimitKind::ArchiveDepth => "archive nesting depth",
+        };
+        f.write_str(name)
+    }
+}
+
+impl BlitzParseError {
+    pub fn limit_exceeded(kind: LimitKind) -> Self {
+        BlitzParseError::LimitExceeded {
+            kind,
+            context: ErrorContext::default(),
+        }
+    }
+
+    /// A damaged or invalid file, with `cause` saying what is wrong with it.
+    pub(crate) fn corrupt(
+        format: &'static str,
+        context: ErrorContext,
+        cause: impl Into<Cause>,
+    ) -> Self {
+        BlitzParseError::CorruptContainer {
+            format,
+            context,
+            source: Some(cause.into()),
+        }
+    }
+
+    pub(crate) fn unsupported(format: impl Into<String>) -> Self {
+        BlitzParseError::UnsupportedFileType {
+            format: format.into(),
+            context: ErrorContext::default(),
+        }
+    }
+
+    /// A stable name for the kind of failure, for grouping errors in logs
+    /// and dashboards.
+    pub fn category(&self) -> &'static str {
+        match self {
+            BlitzParseError::Io(_) => "io",
+            BlitzParseError::Cancelled => "cancelled",
+            BlitzParseError::LimitExceeded { .. } => "limit_exceeded",
+            BlitzParseError::CorruptContainer { .. } => "corrupt_container",
+            BlitzParseError::MissingPart { .. } => "missing_part",
+            BlitzParseError::Encrypted { .. } => "encrypted",
+            BlitzParseError::Encoding { .. } => "encoding",
+            BlitzParseError::PartialSuccess { .. } => "partial_success",
+            BlitzParseError::UnsupportedFileType { .. } => "unsupported_format",
+            BlitzParseError::Callback { .. } => "callback",
+            BlitzParseError::Panicked { .. } => "panic",
+            BlitzParseError::Plugin { .. } => "plugin",
+        }
+    }
+
+    /// The document format the error concerns, where the error says.
+    pub fn format(&self) -> Option<&str> {
+        match self {
+            BlitzParseError::CorruptContainer { format, .. }
+            | BlitzParseError::MissingPart { format, .. }
+            | BlitzParseError::Encrypted { format, .. } => Some(format),
+            BlitzParseError::PartialSuccess { failures, .. } => {
+                failures.first().and_then(|e| e.format())
+            }
+            BlitzParseError::UnsupportedFileType { format, .. } => Some(format),
+            _ => None,
+        }
+    }
+
+    /// Where the error was found. Every error but an I/O failure or a
+    /// cancellation records it.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            BlitzParseError::LimitExceeded { context, .. }
+            | BlitzParseError::CorruptContainer { context, .. }
+            | BlitzParseError::MissingPart { context, .. }
+            | BlitzParseError::Encrypted { context, .. }
+            | BlitzParseError::Encoding { context, .. }
+            | BlitzParseError::PartialSuccess { context, .. }
+            | BlitzParseError::Callback { context, .. }
+            | BlitzParseError::Panicked { context, .. }
+            | BlitzParseError::Plugin { context, .. }
+            | BlitzParseError::UnsupportedFileType { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Record the file the error came from, unless a nested extraction (an
+    /// archive member, an attachment) already did. The path of an I/O
+    /// failure is the one the caller opened.
+    pub fn with_path(mut self, path: &str) -> Self {
+        if let BlitzParseError::LimitExceeded { context, .. }
+        | BlitzParseError::CorruptContainer { context, .. }
+        | BlitzParseError::MissingPart { context, .. }
+        | BlitzParseError::Encrypted { context, .. }
+        | BlitzParseError::Encoding { context, .. }
+        | BlitzParseError::PartialSuccess { context, .. }
+        | BlitzParseError::Callback { context, .. }
+        | BlitzParseError::Panicked { context, .. }
+        | BlitzParseError::Plugin { context, .. }
+        | BlitzParseError::UnsupportedFileType { context, .. } = &mut self
+        {
+            context.path.get_or_insert_with(|| path.to_string());
+        }
+        self
+    }
+}
+
 // This is crucial for allowing our error to work with Rust's `?` operator.
-impl std::error::Error for BlitzParseError {}
+impl Error for BlitzParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BlitzParseError::Io(e) => Some(e),
+            BlitzParseError::CorruptContainer { source, .. }
+            | BlitzParseError::Encrypted { source, .. }
+            | BlitzParseError::Plugin { source, .. } => {
+                source.as_deref().map(|e| e as &(dyn Error + 'static))
+            }
+            BlitzParseError::PartialSuccess { failures, .. } => {
+                failures.first().map(|e| e as &(dyn Error + 'static))
+            }
+            BlitzParseError::Callback { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Classify a failure to open a ZIP-based container, or `part` within it.
+pub(crate) fn zip_error(
+    format: &'static str,
+    part: Option<&str>,
+    err: zip::result::ZipError,
+) -> BlitzParseError {
+    use zip::result::ZipError;
+    let context = ErrorContext {
+        part: part.map(str::to_string),
+        ..Default::default()
+    };
+    match err {
+        ZipError::FileNotFound => BlitzParseError::MissingPart { format, context },
+        ZipError::UnsupportedArchive(reason) if reason == ZipError::PASSWORD_REQUIRED => {
+            BlitzParseError::Encrypted {
+                format,
+                context,
+                source: Some(Box::new(err)),
+            }
+        }
+        err => BlitzParseError::CorruptContainer {
+            format,
+            context,
+            source: Some(Box::new(err)),
+        },
+    }
+}
 
 // Helper implementations to convert underlying library errors into our custom error type.
 impl From<std::io::Error> for BlitzParseError {
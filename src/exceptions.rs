@@ -0,0 +1,120 @@
+use crate::errors::BlitzParseError as ParseError;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use std::path::Path;
+
+// `BlitzParseError` subclasses `ValueError`, which every failure used to be
+// raised as, so existing `except ValueError` handlers keep working.
+create_exception!(
+    blitz_parse,
+    BlitzParseError,
+    PyValueError,
+    "Base class for documents BlitzParse could not extract."
+);
+create_exception!(
+    blitz_parse,
+    UnsupportedFormatError,
+    BlitzParseError,
+    "No parser handles the file's format."
+);
+create_exception!(
+    blitz_parse,
+    CorruptDocumentError,
+    BlitzParseError,
+    "The document is damaged, incomplete or not valid for its format."
+);
+create_exception!(
+    blitz_parse,
+    EncryptedDocumentError,
+    BlitzParseError,
+    "The document is password-protected."
+);
+create_exception!(
+    blitz_parse,
+    LimitExceededError,
+    BlitzParseError,
+    "A limit set in `ExtractOptions` was reached."
+);
+
+/// Register the exception classes on the module.
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("BlitzParseError", py.get_type::<BlitzParseError>())?;
+    m.add(
+        "UnsupportedFormatError",
+        py.get_type::<UnsupportedFormatError>(),
+    )?;
+    m.add(
+        "CorruptDocumentError",
+        py.get_type::<CorruptDocumentError>(),
+    )?;
+    m.add(
+        "EncryptedDocumentError",
+        py.get_type::<EncryptedDocumentError>(),
+    )?;
+    m.add("LimitExceededError", py.get_type::<LimitExceededError>())?;
+    Ok(())
+}
+
+/// Convert an error into the matching Python exception.
+///
+/// I/O failures become the `OSError` subclass for their errno, such as
+/// `FileNotFoundError`, with `filename` set. Everything else is a
+/// `BlitzParseError` subclass with `path`, `format`, `part`, `offset` and
+/// `category` attributes, each `None` when unknown.
+pub fn to_py_err(error: ParseError, path: Option<&str>) -> PyErr {
+    let error = match (error, path) {
+        (ParseError::Io(e), _) => {
+            return match (e.raw_os_error(), path) {
+                // OSError(errno, strerror, filename) picks the subclass for errno.
+                (Some(errno), Some(path)) => {
+                    PyOSError::new_err((errno, e.to_string(), path.to_string()))
+                }
+                _ => PyErr::from(e),
+            };
+        }
+        // The callback's own exception, raised again as is.
+        (ParseError::Callback { source, context }, _) => match source.downcast::<PyErr>() {
+            Ok(e) => return *e,
+            Err(source) => ParseError::Callback { context, source },
+        },
+        (error, Some(path)) => error.with_path(path),
+        (error, None) => error,
+    };
+
+    let message = error.to_string();
+    let err = match &error {
+        ParseError::UnsupportedFileType { .. } => UnsupportedFormatError::new_err(message),
+        ParseError::Encrypted { .. } => EncryptedDocumentError::new_err(message),
+        ParseError::LimitExceeded { .. } => LimitExceededError::new_err(message),
+        ParseError::Cancelled | ParseError::Panicked { .. } | ParseError::Plugin { .. } => {
+            BlitzParseError::new_err(message)
+        }
+        _ => CorruptDocumentError::new_err(message),
+    };
+
+    let context = error.context();
+    let path = context
+        .and_then(|c| c.path.as_deref())
+        .or(path)
+        .map(str::to_string);
+    let format = error.format().map(str::to_string).or_else(|| {
+        let path = path.as_deref()?;
+        let ext = Path::new(path).extension()?.to_str()?;
+        Some(ext.to_lowercase())
+    });
+    Python::attach(|py| {
+        let value = err.value(py);
+        let attributes = value
+            .setattr("path", path)
+            .and_then(|_| value.setattr("format", format))
+            .and_then(|_| value.setattr("part", context.and_then(|c| c.part.clone())))
+            .and_then(|_| value.setattr("offset", context.and_then(|c| c.offset)))
+            .and_then(|_| value.setattr("category", error.category()));
+        match attributes {
+            Ok(()) => err,
+            Err(e) => e,
+        }
+    })
+}
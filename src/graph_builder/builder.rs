@@ -1,22 +1,99 @@
-use super::chunker::{chunk_text, ChunkingStrategy};
-use super::models::{Edge, KnowledgeGraph, Node};
+use super::chunker::{chunk_ranges, ChunkingStrategy};
+use super::models::{Edge, KnowledgeGraph, Node, Section};
+use crate::options::ExtractOptions;
+use crate::parsers::code::CodeFile;
+use crate::parsers::csv::format_record;
+use crate::parsers::email::{EmailMessage, Mailbox};
+use crate::parsers::notebook::Notebook;
+use crate::parsers::subtitle::{merge_cues, Cue};
+use crate::provenance::{locate, Offsets, Provenance};
 use std::collections::HashMap;
 
+/// Chunk properties holding its character offsets into the document's text.
+const DOCUMENT_OFFSETS: [&str; 2] = ["start", "end"];
+/// Chunk properties holding its character offsets into its parent's content,
+/// where that is only part of the document: a section, cell, message or code item.
+const PARENT_OFFSETS: [&str; 2] = ["parent_start", "parent_end"];
+
 pub struct GraphBuilder {
     graph: KnowledgeGraph,
     next_id: u64,
     // Map of: (Node Label, Node Name) -> Node ID
     // e.g., ("Author", "John Doe") -> "blitz-5"
     canonical_nodes: HashMap<(String, String), String>,
+    // Folder that Documents added from now on are placed `IN_FOLDER`.
+    current_folder: Option<String>,
+    // Cancellation and progress reporting for chunking.
+    options: ExtractOptions,
+    chunks: u64,
+}
+
+impl Default for GraphBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GraphBuilder {
     pub fn new() -> Self {
+        Self::with_options(ExtractOptions::default())
+    }
+
+    /// A builder that reports the chunks it creates to `options.progress`
+    /// and stops chunking once `options.cancel` is cancelled.
+    pub fn with_options(options: ExtractOptions) -> Self {
         GraphBuilder {
             graph: KnowledgeGraph::new(),
             next_id: 0,
             canonical_nodes: HashMap::new(),
+            current_folder: None,
+            options,
+            chunks: 0,
+        }
+    }
+
+    /// Place subsequently added Documents in the folder at `path`, a
+    /// `/`-separated path such as `repo/docs/api`. Each folder on the path
+    /// becomes a `Folder` node, linked to its parent by `IN_FOLDER`, and
+    /// Documents are linked to their folder the same way.
+    pub fn with_folder(&mut self, path: &str) -> &mut Self {
+        let mut parent: Option<String> = None;
+        let mut prefix = String::new();
+        for name in path.split('/').filter(|part| !part.is_empty()) {
+            if !prefix.is_empty() {
+                prefix.push('/');
+            }
+            prefix.push_str(name);
+
+            let key = ("Folder".to_string(), prefix.clone());
+            let folder_id = match self.canonical_nodes.get(&key) {
+                Some(id) => id.clone(),
+                None => {
+                    let id = self.generate_id();
+                    let mut properties = HashMap::new();
+                    properties.insert("name".to_string(), name.to_string());
+                    properties.insert("path".to_string(), prefix.clone());
+                    self.add_node(Node {
+                        id: id.clone(),
+                        label: "Folder".to_string(),
+                        properties,
+                    });
+                    if let Some(parent_id) = &parent {
+                        self.add_edge(Edge {
+                            source: id.clone(),
+                            target: parent_id.clone(),
+                            label: "IN_FOLDER".to_string(),
+                            properties: HashMap::new(),
+                        });
+                    }
+                    self.canonical_nodes.insert(key, id.clone());
+                    id
+                }
+            };
+            parent = Some(folder_id);
         }
+        self.current_folder = parent;
+        self
     }
 
     pub fn with_document(
@@ -25,8 +102,355 @@ impl GraphBuilder {
         content: &str,
         strategy: &ChunkingStrategy,
     ) -> &mut Self {
+        let doc_id = self.add_document(metadata);
+        self.add_located_chunks(
+            &doc_id,
+            content,
+            strategy,
+            &HashMap::new(),
+            &[],
+            DOCUMENT_OFFSETS,
+        );
+        self
+    }
+
+    /// Like `with_document`, also storing where each chunk came from in the
+    /// source file: the locators of the `provenance` ranges it overlaps, as
+    /// a JSON list in the chunk's `source` property.
+    pub fn with_located_document(
+        &mut self,
+        metadata: &HashMap<String, String>,
+        content: &str,
+        provenance: &[Provenance],
+        strategy: &ChunkingStrategy,
+    ) -> &mut Self {
+        let doc_id = self.add_document(metadata);
+        self.add_located_chunks(
+            &doc_id,
+            content,
+            strategy,
+            &HashMap::new(),
+            provenance,
+            DOCUMENT_OFFSETS,
+        );
+        self
+    }
+
+    /// Like `with_document`, but mirrors the document's outline as `Section`
+    /// nodes. Top-level sections hang off the Document via `HAS_SECTION`,
+    /// nested ones off their parent via `HAS_SUBSECTION`, and each section's
+    /// content is chunked beneath it.
+    pub fn with_sections(
+        &mut self,
+        metadata: &HashMap<String, String>,
+        sections: &[Section],
+        strategy: &ChunkingStrategy,
+    ) -> &mut Self {
+        let doc_id = self.add_document(metadata);
+
+        // Stack of (level, node ID) for the current path through the outline.
+        let mut parents: Vec<(usize, String)> = Vec::new();
+        for section in sections {
+            while parents
+                .last()
+                .is_some_and(|(level, _)| *level >= section.level)
+            {
+                parents.pop();
+            }
+
+            let section_id = self.generate_id();
+            let mut properties = HashMap::new();
+            properties.insert("title".to_string(), section.title.clone());
+            properties.insert("level".to_string(), section.level.to_string());
+            self.add_node(Node {
+                id: section_id.clone(),
+                label: "Section".to_string(),
+                properties,
+            });
+
+            let (parent_id, edge_label) = match parents.last() {
+                Some((_, id)) => (id.clone(), "HAS_SUBSECTION"),
+                None => (doc_id.clone(), "HAS_SECTION"),
+            };
+            self.add_edge(Edge {
+                source: parent_id,
+                target: section_id.clone(),
+                label: edge_label.to_string(),
+                properties: HashMap::new(),
+            });
+
+            self.add_chunks(&section_id, &section.content, strategy);
+            parents.push((section.level, section_id));
+        }
+        self
+    }
+
+    /// Add a Document for tabular data where each row becomes a `Record` node
+    /// linked by `HAS_RECORD`. Columns are stored as properties, and the row's
+    /// `header: value` rendering as `text`, so records also serve as chunks.
+    /// A column named `text`, or a repeated header, is stored as `text_2`,
+    /// `name_2` and so on.
+    pub fn with_records(
+        &mut self,
+        metadata: &HashMap<String, String>,
+        records: &[Vec<(String, String)>],
+    ) -> &mut Self {
+        let doc_id = self.add_document(metadata);
+        for record in records {
+            if record.is_empty() {
+                continue;
+            }
+            let record_id = self.generate_id();
+            let mut properties = HashMap::from([("text".to_string(), format_record(record))]);
+            for (key, value) in record {
+                let mut unique = key.clone();
+                for n in 2.. {
+                    if !properties.contains_key(&unique) {
+                        break;
+                    }
+                    unique = format!("{}_{}", key, n);
+                }
+                properties.insert(unique, value.clone());
+            }
+            self.add_node(Node {
+                id: record_id.clone(),
+                label: "Record".to_string(),
+                properties,
+            });
+            self.add_edge(Edge {
+                source: doc_id.clone(),
+                target: record_id,
+                label: "HAS_RECORD".to_string(),
+                properties: HashMap::new(),
+            });
+        }
+        self
+    }
+
+    /// Add one Document per email. Participants become `Person` nodes keyed by
+    /// address, and replies are linked to their parent message by `REPLY_TO`
+    /// when the parent is part of the same batch.
+    pub fn with_emails(
+        &mut self,
+        metadata: &HashMap<String, String>,
+        messages: &[EmailMessage],
+        strategy: &ChunkingStrategy,
+    ) -> &mut Self {
+        let mut by_message_id: HashMap<String, String> = HashMap::new();
+        let mut doc_ids = Vec::with_capacity(messages.len());
+
+        for message in messages {
+            let doc_id = self.add_document(metadata);
+            self.extend_properties(&doc_id, message.to_map());
+
+            let participants = [
+                (&message.from, "SENT_BY"),
+                (&message.to, "SENT_TO"),
+                (&message.cc, "CC_TO"),
+            ];
+            for (mailboxes, edge_label) in participants {
+                for mailbox in mailboxes {
+                    let person_id = self.get_or_create_person(mailbox);
+                    self.add_edge(Edge {
+                        source: doc_id.clone(),
+                        target: person_id,
+                        label: edge_label.to_string(),
+                        properties: HashMap::new(),
+                    });
+                }
+            }
+
+            self.add_chunks(&doc_id, &message.text(), strategy);
+            if let Some(message_id) = &message.message_id {
+                by_message_id.insert(message_id.clone(), doc_id.clone());
+            }
+            doc_ids.push(doc_id);
+        }
+
+        for (message, doc_id) in messages.iter().zip(&doc_ids) {
+            // Fall back to the last References entry when In-Reply-To is missing.
+            let parent = message.in_reply_to.as_ref().or(message.references.last());
+            if let Some(parent_id) = parent.and_then(|id| by_message_id.get(id)) {
+                self.add_edge(Edge {
+                    source: doc_id.clone(),
+                    target: parent_id.clone(),
+                    label: "REPLY_TO".to_string(),
+                    properties: HashMap::new(),
+                });
+            }
+        }
+        self
+    }
+
+    /// Add a Document for a source file. Each named top-level item becomes a
+    /// `Symbol` node linked by `DEFINES`, with its code chunked beneath it;
+    /// unnamed items such as imports are chunked under the Document.
+    pub fn with_code(
+        &mut self,
+        metadata: &HashMap<String, String>,
+        file: &CodeFile,
+        strategy: &ChunkingStrategy,
+    ) -> &mut Self {
+        let doc_id = self.add_document(metadata);
+        let mut properties =
+            HashMap::from([("language".to_string(), file.language.name().to_string())]);
+        if let Some(doc) = &file.doc {
+            properties.insert("doc".to_string(), doc.clone());
+        }
+        properties.retain(|key, _| !metadata.contains_key(key));
+        self.extend_properties(&doc_id, properties);
+
+        for item in &file.items {
+            let Some(name) = &item.name else {
+                self.add_chunks(&doc_id, &item.code, strategy);
+                continue;
+            };
+            let symbol_id = self.generate_id();
+            let mut properties = HashMap::new();
+            properties.insert("name".to_string(), name.clone());
+            properties.insert("kind".to_string(), item.kind.to_string());
+            properties.insert("language".to_string(), file.language.name().to_string());
+            properties.insert("start_line".to_string(), item.start_line.to_string());
+            properties.insert("end_line".to_string(), item.end_line.to_string());
+            if let Some(doc) = &item.doc {
+                properties.insert("doc".to_string(), doc.clone());
+            }
+            self.add_node(Node {
+                id: symbol_id.clone(),
+                label: "Symbol".to_string(),
+                properties,
+            });
+            self.add_edge(Edge {
+                source: doc_id.clone(),
+                target: symbol_id.clone(),
+                label: "DEFINES".to_string(),
+                properties: HashMap::new(),
+            });
+            self.add_chunks(&symbol_id, &item.code, strategy);
+        }
+        self
+    }
+
+    /// Add a Document for a Jupyter notebook with one `Cell` node per cell,
+    /// linked in order by `HAS_CELL`. Chunks carry the cell's `cell_type`
+    /// (`output` for code cell outputs) so they can be filtered directly.
+    pub fn with_notebook(
+        &mut self,
+        metadata: &HashMap<String, String>,
+        notebook: &Notebook,
+        include_outputs: bool,
+        strategy: &ChunkingStrategy,
+    ) -> &mut Self {
+        let doc_id = self.add_document(metadata);
+        let meta = &notebook.metadata;
+        let mut properties = HashMap::new();
+        let kernel = [
+            ("kernel", &meta.kernel_name),
+            ("kernel_display_name", &meta.kernel_display_name),
+            ("language_version", &meta.language_version),
+        ];
+        for (key, value) in kernel {
+            if let Some(value) = value {
+                properties.insert(key.to_string(), value.clone());
+            }
+        }
+        properties.insert("nbformat".to_string(), meta.nbformat.clone());
+        properties.retain(|key, _| !metadata.contains_key(key));
+        self.extend_properties(&doc_id, properties);
+
+        for (index, cell) in notebook.cells.iter().enumerate() {
+            let cell_id = self.generate_id();
+            let mut properties = HashMap::new();
+            properties.insert("cell_type".to_string(), cell.cell_type.clone());
+            properties.insert("index".to_string(), index.to_string());
+            if let Some(count) = cell.execution_count {
+                properties.insert("execution_count".to_string(), count.to_string());
+            }
+            self.add_node(Node {
+                id: cell_id.clone(),
+                label: "Cell".to_string(),
+                properties,
+            });
+            self.add_edge(Edge {
+                source: doc_id.clone(),
+                target: cell_id.clone(),
+                label: "HAS_CELL".to_string(),
+                properties: HashMap::new(),
+            });
+
+            let cell_type = HashMap::from([("cell_type".to_string(), cell.cell_type.clone())]);
+            self.add_chunks_with(&cell_id, &cell.source, strategy, &cell_type);
+            if include_outputs {
+                let output = HashMap::from([("cell_type".to_string(), "output".to_string())]);
+                for text in &cell.outputs {
+                    self.add_chunks_with(&cell_id, text, strategy, &output);
+                }
+            }
+        }
+        self
+    }
+
+    /// Add a Document for a subtitle or transcript file. Cues become
+    /// time-ranged chunks with `start_ms`/`end_ms` properties, and each voice
+    /// a `Speaker` node with `SPOKE` edges to the chunks it said. With
+    /// `RecursiveCharacter`, consecutive cues by the same speaker are merged
+    /// up to `chunk_size` characters.
+    pub fn with_transcript(
+        &mut self,
+        metadata: &HashMap<String, String>,
+        cues: &[Cue],
+        strategy: &ChunkingStrategy,
+    ) -> &mut Self {
+        let doc_id = self.add_document(metadata);
+        let cues = match strategy {
+            ChunkingStrategy::ByLine() => cues.to_vec(),
+            ChunkingStrategy::RecursiveCharacter { chunk_size, .. } => {
+                merge_cues(cues, *chunk_size)
+            }
+        };
+
+        for cue in cues {
+            let chunk_id = self.generate_id();
+            let mut properties = HashMap::new();
+            properties.insert("text".to_string(), cue.text.clone());
+            properties.insert("start_ms".to_string(), cue.start_ms.to_string());
+            properties.insert("end_ms".to_string(), cue.end_ms.to_string());
+            if let Some(speaker) = &cue.speaker {
+                properties.insert("speaker".to_string(), speaker.clone());
+            }
+            self.add_node(Node {
+                id: chunk_id.clone(),
+                label: "Chunk".to_string(),
+                properties,
+            });
+            self.add_edge(Edge {
+                source: doc_id.clone(),
+                target: chunk_id.clone(),
+                label: "CONTAINS".to_string(),
+                properties: HashMap::new(),
+            });
+
+            if let Some(speaker) = &cue.speaker {
+                let speaker_id = self.get_or_create_canonical_node("Speaker", speaker);
+                self.add_edge(Edge {
+                    source: speaker_id,
+                    target: chunk_id,
+                    label: "SPOKE".to_string(),
+                    properties: HashMap::new(),
+                });
+            }
+        }
+        self
+    }
+
+    // ... (The rest of the helper methods remain unchanged) ...
+    pub fn build(self) -> KnowledgeGraph {
+        self.graph
+    }
+
+    fn add_document(&mut self, metadata: &HashMap<String, String>) -> String {
         // Define metadata keys that should remain properties, not become nodes.
-        let keys_to_skip: Vec<&str> = vec!["title", "path"];
+        let keys_to_skip: Vec<&str> = vec!["title", "path", "identifier"];
 
         // 1. Create the main Document node with all metadata properties.
         let doc_id = self.generate_id();
@@ -35,6 +459,14 @@ impl GraphBuilder {
             label: "Document".to_string(),
             properties: metadata.clone(),
         });
+        if let Some(folder_id) = self.current_folder.clone() {
+            self.add_edge(Edge {
+                source: doc_id.clone(),
+                target: folder_id,
+                label: "IN_FOLDER".to_string(),
+                properties: HashMap::new(),
+            });
+        }
 
         // 2. Dynamically create nodes and edges from metadata.
         for (key, value_str) in metadata {
@@ -65,30 +497,76 @@ impl GraphBuilder {
                 });
             }
         }
+        doc_id
+    }
 
-        // 3. Process the document content into Chunk nodes.
-        for chunk_text in chunk_text(content, strategy) {
+    fn add_chunks(&mut self, parent_id: &str, content: &str, strategy: &ChunkingStrategy) {
+        self.add_chunks_with(parent_id, content, strategy, &HashMap::new());
+    }
+
+    /// Like `add_chunks`, copying `properties` onto every chunk.
+    fn add_chunks_with(
+        &mut self,
+        parent_id: &str,
+        content: &str,
+        strategy: &ChunkingStrategy,
+        properties: &HashMap<String, String>,
+    ) {
+        self.add_located_chunks(
+            parent_id,
+            content,
+            strategy,
+            properties,
+            &[],
+            PARENT_OFFSETS,
+        );
+    }
+
+    /// Chunk `content` beneath `parent_id`. Every chunk records its start
+    /// and end character offsets into `content` under the `offsets` property
+    /// names, and with `provenance`, the `source` it came from.
+    fn add_located_chunks(
+        &mut self,
+        parent_id: &str,
+        content: &str,
+        strategy: &ChunkingStrategy,
+        properties: &HashMap<String, String>,
+        provenance: &[Provenance],
+        offsets: [&str; 2],
+    ) {
+        // Chunk starts and ends each ascend, so each is counted in one pass.
+        let (mut starts, mut ends) = (Offsets::new(content), Offsets::new(content));
+        // 3. Process the content into Chunk nodes.
+        for range in chunk_ranges(content, strategy) {
+            if self.options.checkpoint().is_err() {
+                break;
+            }
             let chunk_id = self.generate_id();
-            let mut chunk_properties = HashMap::new();
-            chunk_properties.insert("text".to_string(), chunk_text.to_string());
+            let (start, end) = (starts.char(range.start), ends.char(range.end));
+            let mut chunk_properties = properties.clone();
+            chunk_properties.insert("text".to_string(), content[range].to_string());
+            chunk_properties.insert(offsets[0].to_string(), start.to_string());
+            chunk_properties.insert(offsets[1].to_string(), end.to_string());
+            let locators = locate(provenance, start, end);
+            if !locators.is_empty() {
+                if let Ok(source) = serde_json::to_string(&locators) {
+                    chunk_properties.insert("source".to_string(), source);
+                }
+            }
             self.add_node(Node {
                 id: chunk_id.clone(),
                 label: "Chunk".to_string(),
                 properties: chunk_properties,
             });
             self.add_edge(Edge {
-                source: doc_id.clone(),
+                source: parent_id.to_string(),
                 target: chunk_id,
                 label: "CONTAINS".to_string(),
                 properties: HashMap::new(),
             });
+            self.chunks += 1;
         }
-        self
-    }
-
-    // ... (The rest of the helper methods remain unchanged) ...
-    pub fn build(self) -> KnowledgeGraph {
-        self.graph
+        self.options.report("graph", "chunks", self.chunks, None);
     }
 
     fn add_node(&mut self, node: Node) {
@@ -118,6 +596,33 @@ impl GraphBuilder {
         new_id
     }
 
+    fn get_or_create_person(&mut self, mailbox: &Mailbox) -> String {
+        let key = ("Person".to_string(), mailbox.address.to_lowercase());
+        if let Some(id) = self.canonical_nodes.get(&key) {
+            return id.clone();
+        }
+
+        let new_id = self.generate_id();
+        let mut properties = HashMap::new();
+        let name = mailbox.name.as_deref().unwrap_or(&mailbox.address);
+        properties.insert("name".to_string(), name.to_string());
+        properties.insert("email".to_string(), mailbox.address.clone());
+        self.add_node(Node {
+            id: new_id.clone(),
+            label: "Person".to_string(),
+            properties,
+        });
+
+        self.canonical_nodes.insert(key, new_id.clone());
+        new_id
+    }
+
+    fn extend_properties(&mut self, id: &str, properties: HashMap<String, String>) {
+        if let Some(node) = self.graph.nodes.iter_mut().rev().find(|n| n.id == id) {
+            node.properties.extend(properties);
+        }
+    }
+
     fn generate_id(&mut self) -> String {
         self.next_id += 1;
         format!("blitz-{}", self.next_id)
@@ -176,4 +681,285 @@ mod tests {
         assert_eq!(subject_edges.len(), 2);
         assert!(subject_edges.iter().all(|e| e.source == doc_node.id));
     }
+
+    #[test]
+    fn test_sections_follow_outline_hierarchy() {
+        let mut builder = GraphBuilder::new();
+        let sections = vec![
+            Section {
+                title: "Part One".to_string(),
+                level: 0,
+                content: "Intro.".to_string(),
+            },
+            Section {
+                title: "Chapter 1".to_string(),
+                level: 1,
+                content: "Body one.\nBody two.".to_string(),
+            },
+            Section {
+                title: "Part Two".to_string(),
+                level: 0,
+                content: String::new(),
+            },
+        ];
+
+        builder.with_sections(&HashMap::new(), &sections, &ChunkingStrategy::ByLine());
+        let graph = builder.build();
+
+        let section_id = |title: &str| {
+            graph
+                .nodes
+                .iter()
+                .find(|n| n.label == "Section" && n.properties["title"] == title)
+                .unwrap()
+                .id
+                .clone()
+        };
+        let doc_id = graph
+            .nodes
+            .iter()
+            .find(|n| n.label == "Document")
+            .unwrap()
+            .id
+            .clone();
+
+        let top_level: Vec<_> = graph
+            .edges
+            .iter()
+            .filter(|e| e.label == "HAS_SECTION")
+            .map(|e| (e.source.clone(), e.target.clone()))
+            .collect();
+        assert_eq!(
+            top_level,
+            vec![
+                (doc_id.clone(), section_id("Part One")),
+                (doc_id, section_id("Part Two"))
+            ]
+        );
+
+        let sub = graph
+            .edges
+            .iter()
+            .find(|e| e.label == "HAS_SUBSECTION")
+            .unwrap();
+        assert_eq!(sub.source, section_id("Part One"));
+        assert_eq!(sub.target, section_id("Chapter 1"));
+
+        let chapter_chunks = graph
+            .edges
+            .iter()
+            .filter(|e| e.label == "CONTAINS" && e.source == section_id("Chapter 1"))
+            .count();
+        assert_eq!(chapter_chunks, 2);
+
+        // Section chunks are located within their section, not the document.
+        let second = graph
+            .nodes
+            .iter()
+            .find(|n| n.label == "Chunk" && n.properties["text"] == "Body two.")
+            .unwrap();
+        assert_eq!(second.properties["parent_start"], "10");
+        assert!(!second.properties.contains_key("start"));
+    }
+
+    #[test]
+    fn test_emails_create_people_and_reply_threads() {
+        let alice = Mailbox {
+            name: Some("Alice".to_string()),
+            address: "alice@example.com".to_string(),
+        };
+        let bob = Mailbox {
+            name: None,
+            address: "bob@example.com".to_string(),
+        };
+        let original = EmailMessage {
+            from: vec![alice.clone()],
+            to: vec![bob.clone()],
+            subject: Some("Plan".to_string()),
+            message_id: Some("1@example.com".to_string()),
+            body: "Shall we?".to_string(),
+            ..Default::default()
+        };
+        let reply = EmailMessage {
+            from: vec![bob],
+            to: vec![Mailbox {
+                name: None,
+                address: "ALICE@example.com".to_string(),
+            }],
+            subject: Some("Re: Plan".to_string()),
+            message_id: Some("2@example.com".to_string()),
+            in_reply_to: Some("1@example.com".to_string()),
+            body: "Yes.".to_string(),
+            ..Default::default()
+        };
+
+        let mut builder = GraphBuilder::new();
+        builder.with_emails(
+            &HashMap::new(),
+            &[original, reply],
+            &ChunkingStrategy::ByLine(),
+        );
+        let graph = builder.build();
+
+        let people: Vec<_> = graph.nodes.iter().filter(|n| n.label == "Person").collect();
+        assert_eq!(people.len(), 2, "addresses are matched case-insensitively");
+        assert_eq!(people[0].properties["name"], "Alice");
+
+        let doc_for = |subject: &str| {
+            graph
+                .nodes
+                .iter()
+                .find(|n| n.label == "Document" && n.properties["subject"] == subject)
+                .unwrap()
+                .id
+                .clone()
+        };
+        let reply_edge = graph.edges.iter().find(|e| e.label == "REPLY_TO").unwrap();
+        assert_eq!(reply_edge.source, doc_for("Re: Plan"));
+        assert_eq!(reply_edge.target, doc_for("Plan"));
+        assert_eq!(
+            graph.edges.iter().filter(|e| e.label == "SENT_BY").count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_code_items_become_symbols() {
+        use crate::parsers::code::{parse_source, Language};
+
+        let file = parse_source(
+            "use std::io;\n\n/// Entry point.\nfn main() {\n    run();\n}\n",
+            Language::Rust,
+        );
+        let mut builder = GraphBuilder::new();
+        builder.with_code(&HashMap::new(), &file, &ChunkingStrategy::ByLine());
+        let graph = builder.build();
+
+        let doc = graph.nodes.iter().find(|n| n.label == "Document").unwrap();
+        assert_eq!(doc.properties["language"], "rust");
+        let symbols: Vec<_> = graph.nodes.iter().filter(|n| n.label == "Symbol").collect();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].properties["name"], "main");
+        assert_eq!(symbols[0].properties["kind"], "function");
+        assert_eq!(symbols[0].properties["doc"], "Entry point.");
+
+        let defines = graph.edges.iter().find(|e| e.label == "DEFINES").unwrap();
+        assert_eq!(
+            (&defines.source, &defines.target),
+            (&doc.id, &symbols[0].id)
+        );
+        assert!(graph
+            .edges
+            .iter()
+            .any(|e| e.label == "CONTAINS" && e.source == doc.id));
+    }
+
+    #[test]
+    fn test_transcript_chunks_are_time_ranged_with_speakers() {
+        let cue = |start_ms, speaker: &str, text: &str| Cue {
+            start_ms,
+            end_ms: start_ms + 1000,
+            speaker: Some(speaker.to_string()),
+            text: text.to_string(),
+        };
+        let cues = vec![
+            cue(0, "Alice", "Hello."),
+            cue(1000, "Alice", "Shall we start?"),
+            cue(2000, "Bob", "Yes."),
+        ];
+
+        let mut builder = GraphBuilder::new();
+        builder.with_transcript(
+            &HashMap::new(),
+            &cues,
+            &ChunkingStrategy::RecursiveCharacter {
+                chunk_size: 100,
+                overlap: 0,
+            },
+        );
+        let graph = builder.build();
+
+        let chunks: Vec<_> = graph.nodes.iter().filter(|n| n.label == "Chunk").collect();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].properties["text"], "Hello. Shall we start?");
+        assert_eq!(chunks[0].properties["start_ms"], "0");
+        assert_eq!(chunks[0].properties["end_ms"], "2000");
+
+        let alice = graph
+            .nodes
+            .iter()
+            .find(|n| n.label == "Speaker" && n.properties["name"] == "Alice")
+            .unwrap();
+        let spoke: Vec<_> = graph.edges.iter().filter(|e| e.label == "SPOKE").collect();
+        assert_eq!(spoke.len(), 2);
+        assert_eq!(
+            (&spoke[0].source, &spoke[0].target),
+            (&alice.id, &chunks[0].id)
+        );
+    }
+
+    #[test]
+    fn test_record_columns_keep_text_and_repeated_headers() {
+        let pair = |k: &str, v: &str| (k.to_string(), v.to_string());
+        let records = vec![vec![
+            pair("name", "Ada"),
+            pair("text", "Notes"),
+            pair("name", "Lovelace"),
+        ]];
+
+        let mut builder = GraphBuilder::new();
+        builder.with_records(&HashMap::new(), &records);
+        let graph = builder.build();
+
+        let record = graph.nodes.iter().find(|n| n.label == "Record").unwrap();
+        assert_eq!(
+            record.properties["text"],
+            "name: Ada; text: Notes; name: Lovelace"
+        );
+        assert_eq!(record.properties["text_2"], "Notes");
+        assert_eq!(record.properties["name"], "Ada");
+        assert_eq!(record.properties["name_2"], "Lovelace");
+    }
+
+    #[test]
+    fn test_folders_nest_and_hold_documents() {
+        let mut builder = GraphBuilder::new();
+        let strategy = ChunkingStrategy::ByLine();
+        let meta = HashMap::from([("title".to_string(), "A".to_string())]);
+        builder
+            .with_folder("repo/docs")
+            .with_document(&meta, "a", &strategy);
+        builder
+            .with_folder("repo")
+            .with_document(&meta, "b", &strategy);
+        builder
+            .with_folder("repo/docs")
+            .with_document(&meta, "c", &strategy);
+        let graph = builder.build();
+
+        let folders: Vec<_> = graph.nodes.iter().filter(|n| n.label == "Folder").collect();
+        assert_eq!(folders.len(), 2);
+        let id_of = |path: &str| {
+            &folders
+                .iter()
+                .find(|n| n.properties["path"] == path)
+                .unwrap()
+                .id
+        };
+        let in_folder: Vec<_> = graph
+            .edges
+            .iter()
+            .filter(|e| e.label == "IN_FOLDER")
+            .map(|e| e.target.as_str())
+            .collect();
+        assert_eq!(
+            in_folder,
+            vec![
+                id_of("repo"),
+                id_of("repo/docs"),
+                id_of("repo"),
+                id_of("repo/docs")
+            ]
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 use unicode_segmentation::UnicodeSegmentation;
 
 use pyo3::prelude::*;
@@ -11,12 +12,29 @@ pub enum ChunkingStrategy {
 }
 
 pub fn chunk_text(text: &str, strategy: &ChunkingStrategy) -> Vec<String> {
+    chunk_ranges(text, strategy)
+        .into_iter()
+        .map(|range| text[range].to_string())
+        .collect()
+}
+
+/// The byte range in `text` of each chunk `chunk_text` returns.
+pub fn chunk_ranges(text: &str, strategy: &ChunkingStrategy) -> Vec<Range<usize>> {
     match strategy {
-        ChunkingStrategy::ByLine() => text
-            .lines()
-            .filter(|line| !line.trim().is_empty())
-            .map(String::from)
-            .collect(),
+        ChunkingStrategy::ByLine() => {
+            // Lines as `str::lines` splits them, without `\n` or `\r\n`.
+            let mut ranges = Vec::new();
+            let mut start = 0;
+            for line in text.split_inclusive('\n') {
+                let content = line.strip_suffix('\n').unwrap_or(line);
+                let content = content.strip_suffix('\r').unwrap_or(content);
+                if !content.trim().is_empty() {
+                    ranges.push(start..start + content.len());
+                }
+                start += line.len();
+            }
+            ranges
+        }
         ChunkingStrategy::RecursiveCharacter {
             chunk_size,
             overlap,
@@ -24,30 +42,27 @@ pub fn chunk_text(text: &str, strategy: &ChunkingStrategy) -> Vec<String> {
     }
 }
 
-fn recursive_character_split(text: &str, chunk_size: usize, overlap: usize) -> Vec<String> {
+fn recursive_character_split(text: &str, chunk_size: usize, overlap: usize) -> Vec<Range<usize>> {
     if chunk_size == 0 {
         return Vec::new();
     }
 
-    let step = if overlap >= chunk_size {
-        1
-    } else {
-        chunk_size - overlap
-    };
+    let step = window_step(chunk_size, overlap);
 
     let mut chunks = Vec::new();
-    let graphemes: Vec<&str> = text.graphemes(true).collect();
-    if graphemes.len() <= chunk_size {
-        chunks.push(text.to_string());
+    let mut bounds: Vec<usize> = text.grapheme_indices(true).map(|(i, _)| i).collect();
+    let len = bounds.len();
+    if len <= chunk_size {
+        chunks.push(0..text.len());
         return chunks;
     }
+    bounds.push(text.len());
 
     let mut start = 0;
-    while start < graphemes.len() {
-        let end = std::cmp::min(start + chunk_size, graphemes.len());
-        let chunk_str: String = graphemes[start..end].concat();
-        chunks.push(chunk_str);
-        if end == graphemes.len() {
+    while start < len {
+        let end = std::cmp::min(start + chunk_size, len);
+        chunks.push(bounds[start]..bounds[end]);
+        if end == len {
             break;
         }
         start += step;
@@ -55,6 +70,115 @@ fn recursive_character_split(text: &str, chunk_size: usize, overlap: usize) -> V
     chunks
 }
 
+/// Incremental `chunk_text`: text is pushed in pieces and chunks are emitted
+/// as soon as they are complete, so only the current partial line or window
+/// is held in memory. Feeding a text in any number of pieces yields the same
+/// chunks as `chunk_text` over the whole of it.
+pub struct StreamingChunker {
+    strategy: ChunkingStrategy,
+    // Text not yet emitted: a partial line, or the graphemes from the start of
+    // the next window onwards.
+    buffer: String,
+    emitted: bool,
+}
+
+impl StreamingChunker {
+    pub fn new(strategy: ChunkingStrategy) -> Self {
+        StreamingChunker {
+            strategy,
+            buffer: String::new(),
+            emitted: false,
+        }
+    }
+
+    /// Add the next piece of text, returning any chunks it completes.
+    pub fn push(&mut self, text: &str) -> Vec<String> {
+        self.buffer.push_str(text);
+        let mut chunks = Vec::new();
+        match self.strategy {
+            ChunkingStrategy::ByLine() => {
+                let mut consumed = 0;
+                while let Some(end) = self.buffer[consumed..].find('\n') {
+                    let line = &self.buffer[consumed..consumed + end];
+                    let line = line.strip_suffix('\r').unwrap_or(line);
+                    if !line.trim().is_empty() {
+                        chunks.push(line.to_string());
+                    }
+                    consumed += end + 1;
+                }
+                self.buffer.drain(..consumed);
+            }
+            ChunkingStrategy::RecursiveCharacter {
+                chunk_size,
+                overlap,
+            } => {
+                if chunk_size == 0 {
+                    self.buffer.clear();
+                    return chunks;
+                }
+                let step = window_step(chunk_size, overlap);
+                let bounds: Vec<usize> =
+                    self.buffer.grapheme_indices(true).map(|(i, _)| i).collect();
+                // The last grapheme may still be extended by the next piece, and a
+                // window is only final once text beyond its end is known to exist.
+                let available = bounds.len().saturating_sub(1);
+                let mut start = 0;
+                while start + chunk_size < available {
+                    chunks.push(self.buffer[bounds[start]..bounds[start + chunk_size]].to_string());
+                    start += step;
+                }
+                if start > 0 {
+                    self.buffer.drain(..bounds[start]);
+                }
+            }
+        }
+        self.emitted |= !chunks.is_empty();
+        chunks
+    }
+
+    /// Flush the chunks left once the input is exhausted.
+    pub fn finish(self) -> Vec<String> {
+        match self.strategy {
+            ChunkingStrategy::ByLine() => chunk_text(&self.buffer, &self.strategy),
+            // Before anything is emitted this is exactly `chunk_text`; afterwards
+            // the buffer starts at the next window and holds the tail.
+            ChunkingStrategy::RecursiveCharacter { .. } if !self.emitted => {
+                chunk_text(&self.buffer, &self.strategy)
+            }
+            ChunkingStrategy::RecursiveCharacter {
+                chunk_size,
+                overlap,
+            } => {
+                let step = window_step(chunk_size, overlap);
+                let mut bounds: Vec<usize> =
+                    self.buffer.grapheme_indices(true).map(|(i, _)| i).collect();
+                let len = bounds.len();
+                bounds.push(self.buffer.len());
+
+                let mut chunks = Vec::new();
+                let mut start = 0;
+                while start < len {
+                    let end = std::cmp::min(start + chunk_size, len);
+                    chunks.push(self.buffer[bounds[start]..bounds[end]].to_string());
+                    if end == len {
+                        break;
+                    }
+                    start += step;
+                }
+                chunks
+            }
+        }
+    }
+}
+
+fn window_step(chunk_size: usize, overlap: usize) -> usize {
+    if overlap >= chunk_size {
+        1
+    } else {
+        chunk_size - overlap
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -62,7 +186,13 @@ mod tests {
     #[test]
     fn test_recursive_split() {
         let text = "This is a sentence. This is another sentence.";
-        let chunks = recursive_character_split(text, 20, 5);
+        let chunks = chunk_text(
+            text,
+            &ChunkingStrategy::RecursiveCharacter {
+                chunk_size: 20,
+                overlap: 5,
+            },
+        );
 
         assert_eq!(chunks.len(), 3);
         assert_eq!(chunks[0], "This is a sentence. ");
@@ -73,8 +203,53 @@ mod tests {
     #[test]
     fn test_recursive_split_with_large_overlap() {
         let text = "abcdef";
-        let chunks = recursive_character_split(text, 3, 5);
+        let chunks = chunk_text(
+            text,
+            &ChunkingStrategy::RecursiveCharacter {
+                chunk_size: 3,
+                overlap: 5,
+            },
+        );
 
         assert_eq!(chunks, vec!["abc", "bcd", "cde", "def"]);
     }
+
+    #[test]
+    fn test_streaming_chunker_matches_chunk_text() {
+        let text = "First line\r\n\nSecond line with more words\nThird caf\u{e9} line\ne\u{301}nd";
+        let strategies = [
+            ChunkingStrategy::ByLine(),
+            ChunkingStrategy::RecursiveCharacter {
+                chunk_size: 7,
+                overlap: 2,
+            },
+            ChunkingStrategy::RecursiveCharacter {
+                chunk_size: 3,
+                overlap: 5,
+            },
+            ChunkingStrategy::RecursiveCharacter {
+                chunk_size: 500,
+                overlap: 0,
+            },
+        ];
+        for strategy in strategies {
+            let expected = chunk_text(text, &strategy);
+            // Split into pieces of every size, including mid-grapheme (after "e").
+            for piece in 1..text.len() {
+                let mut chunker = StreamingChunker::new(strategy);
+                let mut chunks = Vec::new();
+                let mut rest = text;
+                while !rest.is_empty() {
+                    let mut at = piece.min(rest.len());
+                    while !rest.is_char_boundary(at) {
+                        at += 1;
+                    }
+                    chunks.extend(chunker.push(&rest[..at]));
+                    rest = &rest[at..];
+                }
+                chunks.extend(chunker.finish());
+                assert_eq!(chunks, expected, "{:?} in pieces of {}", strategy, piece);
+            }
+        }
+    }
 }
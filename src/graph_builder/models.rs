@@ -18,6 +18,15 @@ pub struct Edge {
     pub properties: HashMap<String, String>,
 }
 
+/// A titled part of a document's outline, such as a chapter or heading.
+/// `level` is the nesting depth, 0 for top-level sections.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Section {
+    pub title: String,
+    pub level: usize,
+    pub content: String,
+}
+
 /// The main container for the knowledge graph.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct KnowledgeGraph {
@@ -34,3 +43,9 @@ impl KnowledgeGraph {
         }
     }
 }
+
+impl Default for KnowledgeGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,283 @@
+use crate::tree::{Block, BlockKind, Footnote, Span};
+
+/// Render blocks and footnotes as an HTML fragment of semantic elements
+/// only: headings, paragraphs, lists, tables, `pre`/`code`, `strong`, `em`,
+/// links and footnotes. All text is escaped, and links other than http(s),
+/// mailto and relative ones are dropped, keeping their text, so the result
+/// can be inserted into a page as is.
+pub fn render(blocks: &[Block], footnotes: &[Footnote]) -> String {
+    let mut out = String::new();
+    // Whether each open list is ordered; each has an open `li`.
+    let mut lists: Vec<bool> = Vec::new();
+    for block in blocks {
+        if let BlockKind::ListItem {
+            depth,
+            ordered,
+            spans,
+        } = &block.kind
+        {
+            while lists.len() > depth + 1 {
+                close_list(&mut out, &mut lists);
+            }
+            if lists.len() == depth + 1 {
+                if lists.last() == Some(ordered) {
+                    out.push_str("</li>\n");
+                } else {
+                    close_list(&mut out, &mut lists);
+                }
+            }
+            // Skipped levels get an empty item to hold the deeper list.
+            while lists.len() <= *depth {
+                out.push_str(if *ordered { "<ol>\n" } else { "<ul>\n" });
+                lists.push(*ordered);
+                if lists.len() <= *depth {
+                    out.push_str("<li>");
+                }
+            }
+            out.push_str("<li>");
+            out.push_str(&render_inline(spans));
+            continue;
+        }
+        while !lists.is_empty() {
+            close_list(&mut out, &mut lists);
+        }
+
+        match &block.kind {
+            BlockKind::Heading { level, spans } => {
+                let level = (*level).clamp(1, 6);
+                out.push_str(&format!(
+                    "<h{}>{}</h{}>\n",
+                    level,
+                    render_inline(spans),
+                    level
+                ));
+            }
+            BlockKind::Paragraph { spans } => {
+                out.push_str(&format!("<p>{}</p>\n", render_inline(spans)));
+            }
+            BlockKind::Table { rows } => render_table(&mut out, rows),
+            BlockKind::Code { language, text } => {
+                let class = language
+                    .as_deref()
+                    .filter(|l| {
+                        l.chars()
+                            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '+'))
+                    })
+                    .map(|l| format!(" class=\"language-{}\"", l))
+                    .unwrap_or_default();
+                out.push_str(&format!(
+                    "<pre><code{}>{}</code></pre>\n",
+                    class,
+                    escape(text.trim_end_matches('\n'))
+                ));
+            }
+            BlockKind::ListItem { .. } => unreachable!(),
+        }
+    }
+    while !lists.is_empty() {
+        close_list(&mut out, &mut lists);
+    }
+
+    if !footnotes.is_empty() {
+        out.push_str("<section class=\"footnotes\">\n");
+        for Footnote { label, spans } in footnotes {
+            let label = escape(label);
+            out.push_str(&format!(
+                "<p id=\"fn-{}\"><sup>{}</sup> {}</p>\n",
+                label,
+                label,
+                render_inline(spans)
+            ));
+        }
+        out.push_str("</section>\n");
+    }
+    out
+}
+
+fn close_list(out: &mut String, lists: &mut Vec<bool>) {
+    if let Some(ordered) = lists.pop() {
+        out.push_str(if ordered {
+            "</li>\n</ol>\n"
+        } else {
+            "</li>\n</ul>\n"
+        });
+    }
+}
+
+/// A table whose first row is its header, padded to the widest row.
+fn render_table(out: &mut String, rows: &[Vec<Vec<Span>>]) {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return;
+    }
+    out.push_str("<table>\n");
+    for (i, row) in rows.iter().enumerate() {
+        let tag = if i == 0 { "th" } else { "td" };
+        match i {
+            0 => out.push_str("<thead>\n"),
+            1 => out.push_str("<tbody>\n"),
+            _ => {}
+        }
+        out.push_str("<tr>");
+        for column in 0..columns {
+            let cell = row
+                .get(column)
+                .map(|c| render_inline(c))
+                .unwrap_or_default();
+            out.push_str(&format!("<{}>{}</{}>", tag, cell, tag));
+        }
+        out.push_str("</tr>\n");
+        if i == 0 {
+            out.push_str("</thead>\n");
+        }
+    }
+    if rows.len() > 1 {
+        out.push_str("</tbody>\n");
+    }
+    out.push_str("</table>\n");
+}
+
+/// Render spans as inline HTML, linking runs of spans that share a link.
+pub fn render_inline(spans: &[Span]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < spans.len() {
+        let link = &spans[i].link;
+        let end = (i..spans.len())
+            .find(|&j| spans[j].link != *link)
+            .unwrap_or(spans.len());
+        let mut content = String::new();
+        for span in &spans[i..end] {
+            content.push_str(&format_span(span));
+        }
+        match link.as_deref().filter(|url| is_safe_url(url)) {
+            Some(url) => out.push_str(&format!("<a href=\"{}\">{}</a>", escape(url), content)),
+            None => out.push_str(&content),
+        }
+        i = end;
+    }
+    out
+}
+
+fn format_span(span: &Span) -> String {
+    if let Some(label) = &span.footnote {
+        let label = escape(label);
+        return format!("<sup><a href=\"#fn-{}\">{}</a></sup>", label, label);
+    }
+    let mut html = escape(&span.text).replace('\n', "<br>");
+    if span.code {
+        html = format!("<code>{}</code>", html);
+    }
+    if span.italic {
+        html = format!("<em>{}</em>", html);
+    }
+    if span.bold {
+        html = format!("<strong>{}</strong>", html);
+    }
+    html
+}
+
+/// Whether a link target is http(s), mailto or relative. Anything else, such
+/// as `javascript:` or `data:`, could run script or smuggle content.
+fn is_safe_url(url: &str) -> bool {
+    let url = url.trim();
+    match url.find(':') {
+        // A colon after a path, query or fragment starts isn't a scheme.
+        Some(colon) if !url[..colon].contains(['/', '?', '#']) => {
+            let scheme = url[..colon].to_ascii_lowercase();
+            matches!(scheme.as_str(), "http" | "https" | "mailto")
+        }
+        _ => true,
+    }
+}
+
+/// Escape text for use in element content and quoted attribute values.
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renders_sanitized_semantic_html() {
+        let link = |text: &str, url: &str| Span {
+            text: text.to_string(),
+            link: Some(url.to_string()),
+            ..Default::default()
+        };
+        let item = |depth, ordered, text: &str| BlockKind::ListItem {
+            depth,
+            ordered,
+            spans: vec![Span::plain(text)],
+        };
+        let blocks: Vec<Block> = vec![
+            BlockKind::Heading {
+                level: 1,
+                spans: vec![Span::plain("<script>alert(1)</script>")],
+            },
+            BlockKind::Paragraph {
+                spans: vec![
+                    Span {
+                        text: "bold".to_string(),
+                        bold: true,
+                        ..Default::default()
+                    },
+                    Span::plain(" & "),
+                    link("safe", "https://example.com/?a=1&b=\"2\""),
+                    Span::plain(" "),
+                    link("unsafe", " JavaScript:alert(1)"),
+                    Span {
+                        footnote: Some("1".to_string()),
+                        ..Default::default()
+                    },
+                ],
+            },
+            item(0, false, "a"),
+            item(1, true, "b"),
+            item(0, false, "c"),
+            BlockKind::Table {
+                rows: vec![
+                    vec![vec![Span::plain("K")], vec![Span::plain("V")]],
+                    vec![vec![Span::plain("x\ny")]],
+                ],
+            },
+            BlockKind::Code {
+                language: Some("rust\" onclick=\"x".to_string()),
+                text: "a < b\n".to_string(),
+            },
+        ]
+        .into_iter()
+        .map(Block::from)
+        .collect();
+        let footnotes = vec![Footnote {
+            label: "1".to_string(),
+            spans: vec![Span::plain("Note.")],
+        }];
+
+        assert_eq!(
+            render(&blocks, &footnotes),
+            "<h1>&lt;script&gt;alert(1)&lt;/script&gt;</h1>\n\
+             <p><strong>bold</strong> &amp; \
+             <a href=\"https://example.com/?a=1&amp;b=&quot;2&quot;\">safe</a> unsafe\
+             <sup><a href=\"#fn-1\">1</a></sup></p>\n\
+             <ul>\n<li>a<ol>\n<li>b</li>\n</ol>\n</li>\n<li>c</li>\n</ul>\n\
+             <table>\n<thead>\n<tr><th>K</th><th>V</th></tr>\n</thead>\n\
+             <tbody>\n<tr><td>x<br>y</td><td></td></tr>\n</tbody>\n</table>\n\
+             <pre><code>a &lt; b</code></pre>\n\
+             <section class=\"footnotes\">\n<p id=\"fn-1\"><sup>1</sup> Note.</p>\n</section>\n"
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use crate::errors::BlitzParseError;
+use crate::graph_builder::builder::GraphBuilder;
+use crate::graph_builder::chunker::ChunkingStrategy;
+use crate::graph_builder::models::KnowledgeGraph;
+use crate::options::ExtractOptions;
+use crate::parsers;
+use crate::registry::ParserRegistry;
+use encoding_rs::{UTF_16BE, UTF_16LE};
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// How many leading bytes are inspected to tell text from binary files.
+const SNIFF_LEN: usize = 8192;
+
+/// Which files `ingest_directory` picks up. Globs use `.gitignore` syntax and
+/// are matched against paths relative to the root; when `include` is empty
+/// every file is a candidate.
+#[derive(Debug, Clone, Default)]
+pub struct IngestOptions {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub follow_links: bool,
+    /// Limits, cancellation and normalization for each file; `timeout`
+    /// applies to each file separately.
+    pub extract: ExtractOptions,
+}
+
+/// The graph for a directory tree, plus the files that could not be extracted.
+#[derive(Debug)]
+pub struct IngestedDirectory {
+    pub graph: KnowledgeGraph,
+    pub errors: Vec<(PathBuf, BlitzParseError)>,
+}
+
+/// Walk `root` recursively and build one graph for every file in it.
+///
+/// Files ignored by `.gitignore`, `.ignore` or git's exclude files are
+/// skipped, as are hidden files and binary files with no parser. Each
+/// directory becomes a `Folder` node and each file's Document is linked to
+/// its folder by `IN_FOLDER`. A file that fails to extract is recorded in
+/// `errors` and does not stop the walk.
+///
+/// `metadata` is added to every Document, except that each Document's `path`
+/// is always its path relative to `root`.
+pub fn ingest_directory(
+    root: &str,
+    options: &IngestOptions,
+    metadata: &HashMap<String, String>,
+    strategy: &ChunkingStrategy,
+) -> Result<IngestedDirectory, BlitzParseError> {
+    let root_path = Path::new(root);
+    if !root_path.is_dir() {
+        return Err(BlitzParseError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Not a directory: {}", root),
+        )));
+    }
+
+    let mut overrides = OverrideBuilder::new(root_path);
+    let globs = options.include.iter().cloned().chain(
+        options
+            .exclude
+            .iter()
+            .map(|glob| format!("!{}", glob.trim_start_matches('!'))),
+    );
+    for glob in globs {
+        overrides
+            .add(&glob)
+            .map_err(|e| BlitzParseError::Io(std::io::Error::other(e)))?;
+    }
+    let overrides = overrides
+        .build()
+        .map_err(|e| BlitzParseError::Io(std::io::Error::other(e)))?;
+
+    let walker = WalkBuilder::new(root_path)
+        .follow_links(options.follow_links)
+        .require_git(false)
+        .overrides(overrides)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    // Folder paths in the graph start at the root directory's own name.
+    let root_name = root_path
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| ".".to_string());
+
+    let mut builder = GraphBuilder::with_options(options.extract.clone());
+    let mut errors = Vec::new();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                let path = error_path(&e).unwrap_or(root_path).to_path_buf();
+                errors.push((path, BlitzParseError::Io(std::io::Error::other(e))));
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+
+        let path = entry.path();
+        let relative = path.strip_prefix(root_path).unwrap_or(path);
+        let folder = relative
+            .parent()
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        let relative = relative.to_string_lossy().replace('\\', "/");
+
+        // The file's own path wins over a `path` in the caller's metadata.
+        let mut file_metadata = metadata.clone();
+        file_metadata.insert("path".to_string(), relative);
+
+        builder.with_folder(&format!("{}/{}", root_name, folder));
+        match add_file(
+            &mut builder,
+            path,
+            &file_metadata,
+            strategy,
+            &options.extract,
+        ) {
+            Ok(()) => {}
+            // Cancelling stops the walk rather than failing every remaining file.
+            Err(BlitzParseError::Cancelled) => return Err(BlitzParseError::Cancelled),
+            Err(e) => errors.push((path.to_path_buf(), e)),
+        }
+    }
+
+    Ok(IngestedDirectory {
+        graph: builder.build(),
+        errors,
+    })
+}
+
+/// The path a walk error concerns, if it names one.
+fn error_path(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            error_path(err)
+        }
+        ignore::Error::Partial(errs) => errs.iter().find_map(error_path),
+        _ => None,
+    }
+}
+
+/// Add a file through its registered parser. Files no parser claims are
+/// indexed as plain text, unless they look binary.
+fn add_file(
+    builder: &mut GraphBuilder,
+    path: &Path,
+    metadata: &HashMap<String, String>,
+    strategy: &ChunkingStrategy,
+    options: &ExtractOptions,
+) -> Result<(), BlitzParseError> {
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| BlitzParseError::Io(std::io::Error::other("Path is not valid UTF-8")))?;
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+
+    let has_parser = match extension.as_deref() {
+        Some(ext) => ParserRegistry::global()
+            .read()
+            .unwrap()
+            .for_extension(ext)
+            .is_some(),
+        None => false,
+    };
+    if has_parser {
+        return crate::add_to_graph(builder, path_str, metadata, strategy, options);
+    }
+
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    std::fs::File::open(path)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)?;
+    if is_binary(&head) {
+        return Ok(());
+    }
+    if extension.is_none()
+        && parsers::code::Language::from_shebang(&String::from_utf8_lossy(&head)).is_some()
+    {
+        return crate::add_to_graph(builder, path_str, metadata, strategy, options);
+    }
+
+    let options = options.started();
+    options.checkpoint()?;
+    options.check_input(std::fs::metadata(path)?.len())?;
+    let bytes = std::fs::read(path)?;
+    let mut text = parsers::txt::decode_text(&bytes, true)?.text;
+    options.normalize_text(&mut text);
+    builder.with_document(metadata, &text, strategy);
+    Ok(())
+}
+
+/// Text files never contain NUL bytes outside UTF-16, which encoding
+/// detection recognises.
+fn is_binary(head: &[u8]) -> bool {
+    if !head.contains(&0) {
+        return false;
+    }
+    let encoding = parsers::txt::detect_encoding(head).encoding;
+    encoding != UTF_16LE && encoding != UTF_16BE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_ingest_walks_tree_with_filters() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("docs/api")).unwrap();
+        std::fs::create_dir_all(root.join("build")).unwrap();
+        std::fs::write(root.join(".gitignore"), "build/\n").unwrap();
+        std::fs::write(root.join("readme.txt"), "Top level").unwrap();
+        std::fs::write(root.join("docs/guide.txt"), "Guide").unwrap();
+        std::fs::write(root.join("docs/api/ref.txt"), "Reference").unwrap();
+        std::fs::write(root.join("docs/notes.md"), "Notes").unwrap();
+        std::fs::write(root.join("docs/.hidden.txt"), "Hidden").unwrap();
+        std::fs::write(root.join("docs/blob.bin"), (0u8..=255).collect::<Vec<_>>()).unwrap();
+        std::fs::write(root.join("build/out.txt"), "Ignored").unwrap();
+        std::fs::write(root.join("docs/api/draft.txt"), "Draft").unwrap();
+
+        let options = IngestOptions {
+            exclude: vec!["draft.txt".to_string()],
+            ..Default::default()
+        };
+        let strategy = ChunkingStrategy::ByLine();
+        let metadata = HashMap::from([
+            ("path".to_string(), "shared".to_string()),
+            ("team".to_string(), "docs".to_string()),
+        ]);
+        let ingested =
+            ingest_directory(root.to_str().unwrap(), &options, &metadata, &strategy).unwrap();
+        assert!(ingested.errors.is_empty());
+        let graph = ingested.graph;
+
+        let mut paths: Vec<_> = graph
+            .nodes
+            .iter()
+            .filter(|n| n.label == "Document")
+            .inspect(|n| assert_eq!(n.properties["team"], "docs"))
+            .map(|n| n.properties["path"].as_str())
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "docs/api/ref.txt",
+                "docs/guide.txt",
+                "docs/notes.md",
+                "readme.txt"
+            ]
+        );
+
+        let folders: Vec<_> = graph.nodes.iter().filter(|n| n.label == "Folder").collect();
+        assert_eq!(folders.len(), 3);
+        let api = folders
+            .iter()
+            .find(|n| n.properties["name"] == "api")
+            .unwrap();
+        let docs = folders
+            .iter()
+            .find(|n| n.properties["name"] == "docs")
+            .unwrap();
+        assert!(graph
+            .edges
+            .iter()
+            .any(|e| e.label == "IN_FOLDER" && e.source == api.id && e.target == docs.id));
+
+        let included = IngestOptions {
+            include: vec!["*.md".to_string()],
+            ..Default::default()
+        };
+        let ingested = ingest_directory(
+            root.to_str().unwrap(),
+            &included,
+            &HashMap::new(),
+            &strategy,
+        )
+        .unwrap();
+        let documents = ingested
+            .graph
+            .nodes
+            .iter()
+            .filter(|n| n.label == "Document")
+            .count();
+        assert_eq!(documents, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_errors_name_their_own_path() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("a")).unwrap();
+        std::os::unix::fs::symlink(root.join("a"), root.join("a/loop")).unwrap();
+
+        let options = IngestOptions {
+            follow_links: true,
+            ..Default::default()
+        };
+        let ingested = ingest_directory(
+            root.to_str().unwrap(),
+            &options,
+            &HashMap::new(),
+            &ChunkingStrategy::ByLine(),
+        )
+        .unwrap();
+        assert_eq!(ingested.errors.len(), 1);
+        assert_eq!(ingested.errors[0].0, root.join("a/loop"));
+    }
+}
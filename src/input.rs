@@ -0,0 +1,182 @@
+use crate::errors::BlitzParseError;
+use crate::options::ExtractOptions;
+use memmap2::Mmap;
+use std::fs::File;
+use std::ops::Deref;
+use std::time::SystemTime;
+
+/// With `ExtractOptions::mmap`, files at least this large are memory-mapped
+/// instead of read into memory.
+///
+/// A mapped file must not be truncated while it is parsed: reading a page past
+/// the new end raises SIGBUS and kills the process. Writes that keep the size
+/// are visible to the parse and are caught by `FileBytes::changed`. Files are
+/// therefore only mapped when the caller opts in for files it controls.
+pub const MMAP_THRESHOLD: u64 = 16 << 20;
+
+/// The contents of a file, either read into memory or memory-mapped.
+pub enum FileBytes {
+    Owned(Vec<u8>),
+    Mapped {
+        map: Mmap,
+        // Size and modification time when mapped, to notice later writes.
+        len: u64,
+        modified: Option<SystemTime>,
+    },
+}
+
+impl FileBytes {
+    /// Read `path` into memory.
+    pub fn open(path: &str) -> Result<FileBytes, BlitzParseError> {
+        FileBytes::open_with(path, &ExtractOptions::default())
+    }
+
+    /// With `options.mmap`, map `path` if it is a regular file of at least
+    /// `MMAP_THRESHOLD` bytes; otherwise, or if it cannot be mapped, read it.
+    pub fn open_with(path: &str, options: &ExtractOptions) -> Result<FileBytes, BlitzParseError> {
+        let file = File::open(path)?;
+        let metadata = file.metadata()?;
+        if options.mmap && metadata.is_file() && metadata.len() >= MMAP_THRESHOLD {
+            // SAFETY: the map is read-only, but it is not a snapshot: another
+            // process can still rewrite the file underneath it. Rewritten bytes
+            // are only inconsistent, and `changed` lets callers re-read them,
+            // but truncating the file during a parse is fatal (SIGBUS), which
+            // is why mapping is opt-in.
+            if let Ok(map) = unsafe { Mmap::map(&file) } {
+                return Ok(FileBytes::Mapped {
+                    map,
+                    len: metadata.len(),
+                    modified: metadata.modified().ok(),
+                });
+            }
+        }
+        Ok(FileBytes::Owned(std::fs::read(path)?))
+    }
+
+    /// True if the file was mapped and has since been resized or rewritten,
+    /// so anything parsed from the mapping may be inconsistent. A file that
+    /// was removed or renamed is not a change: the mapping still holds the
+    /// contents it had.
+    pub fn changed(&self, path: &str) -> bool {
+        match self {
+            FileBytes::Owned(_) => false,
+            FileBytes::Mapped { len, modified, .. } => std::fs::metadata(path)
+                .is_ok_and(|now| now.len() != *len || now.modified().ok() != *modified),
+        }
+    }
+}
+
+impl Deref for FileBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileBytes::Owned(bytes) => bytes,
+            FileBytes::Mapped { map, .. } => map,
+        }
+    }
+}
+
+/// Run `parse` over the contents of `path`, read into memory.
+pub fn with_file_bytes<T>(
+    path: &str,
+    parse: impl FnMut(&[u8]) -> Result<T, BlitzParseError>,
+) -> Result<T, BlitzParseError> {
+    with_file_bytes_with(path, &ExtractOptions::default(), parse)
+}
+
+/// Like `with_file_bytes`, memory-mapping large files if `options.mmap` is
+/// set. If a mapped file changes while it is being parsed, it is read into
+/// memory and parsed again from that stable copy; otherwise the first result,
+/// success or error, is returned as is. See `MMAP_THRESHOLD` for why
+/// truncation is still fatal.
+pub fn with_file_bytes_with<T>(
+    path: &str,
+    options: &ExtractOptions,
+    mut parse: impl FnMut(&[u8]) -> Result<T, BlitzParseError>,
+) -> Result<T, BlitzParseError> {
+    let bytes = FileBytes::open_with(path, options)?;
+    let result = parse(&bytes);
+    if bytes.changed(path) {
+        drop(bytes);
+        return parse(&std::fs::read(path)?);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_files_are_read_unless_mapping_is_enabled() {
+        let dir = tempdir().unwrap();
+        let large = dir.path().join("large.txt");
+        std::fs::write(&large, vec![b'a'; MMAP_THRESHOLD as usize]).unwrap();
+        let path = large.to_str().unwrap();
+
+        let bytes = FileBytes::open(path).unwrap();
+        assert!(matches!(bytes, FileBytes::Owned(_)));
+        assert_eq!(bytes.len(), MMAP_THRESHOLD as usize);
+
+        // A read copy is unaffected by the file shrinking underneath it.
+        let len = with_file_bytes(path, |bytes| {
+            std::fs::write(path, "short").unwrap();
+            Ok(bytes.len())
+        });
+        assert_eq!(len.unwrap(), MMAP_THRESHOLD as usize);
+        assert_eq!(crate::extract_text(path).unwrap(), "short");
+    }
+
+    #[test]
+    fn test_large_files_are_mapped() {
+        let dir = tempdir().unwrap();
+        let small = dir.path().join("small.txt");
+        let large = dir.path().join("large.txt");
+        std::fs::write(&small, "small").unwrap();
+        std::fs::write(&large, vec![b'a'; MMAP_THRESHOLD as usize]).unwrap();
+
+        let options = ExtractOptions {
+            mmap: true,
+            ..Default::default()
+        };
+        let bytes = FileBytes::open_with(small.to_str().unwrap(), &options).unwrap();
+        assert!(matches!(bytes, FileBytes::Owned(_)));
+        assert_eq!(&*bytes, b"small");
+
+        let path = large.to_str().unwrap();
+        let bytes = FileBytes::open_with(path, &options).unwrap();
+        assert!(matches!(bytes, FileBytes::Mapped { .. }));
+        assert_eq!(bytes.len(), MMAP_THRESHOLD as usize);
+        assert!(!bytes.changed(path));
+
+        // Growing the file is detected, and `with_file_bytes` re-reads it.
+        let mut calls = 0;
+        let len = with_file_bytes_with(path, &options, |bytes| {
+            if calls == 0 {
+                let mut file = std::fs::OpenOptions::new().append(true).open(path).unwrap();
+                std::io::Write::write_all(&mut file, b"more").unwrap();
+            }
+            calls += 1;
+            Ok(bytes.len())
+        });
+        assert_eq!(len.unwrap(), MMAP_THRESHOLD as usize + 4);
+        assert_eq!(calls, 2);
+
+        // An unchanged file keeps its first result, and so does a removed one.
+        let mut calls = 0;
+        let result: Result<(), _> = with_file_bytes_with(path, &options, |_| {
+            calls += 1;
+            Err(BlitzParseError::Cancelled)
+        });
+        assert!(matches!(result, Err(BlitzParseError::Cancelled)));
+        let len = with_file_bytes_with(path, &options, |bytes| {
+            std::fs::remove_file(path).unwrap();
+            calls += 1;
+            Ok(bytes.len())
+        });
+        assert_eq!(len.unwrap(), MMAP_THRESHOLD as usize + 4);
+        assert_eq!(calls, 2);
+    }
+}
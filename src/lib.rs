@@ -1,64 +1,792 @@
 use pyo3::prelude::*;
+pub mod document;
 pub mod errors;
+pub mod exceptions;
 pub mod graph_builder;
+pub mod html;
+pub mod ingest;
+pub mod input;
+pub mod markdown;
+pub mod normalize;
+pub mod options;
 pub mod parsers;
+pub mod progress;
+pub mod provenance;
+pub mod registry;
+pub mod stream;
+pub mod tree;
 
 use crate::graph_builder::builder::GraphBuilder;
 use crate::graph_builder::chunker::ChunkingStrategy;
-use errors::BlitzParseError;
+use crate::graph_builder::models::KnowledgeGraph;
+use crate::parsers::code::CodeFile;
+use document::{ExtractResult, ParsedDocument};
+use errors::{BlitzParseError, ErrorContext};
+use options::ExtractOptions;
+use provenance::Locator;
+use rayon::prelude::*;
+use registry::{ParserRegistry, Source};
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
 use std::path::Path;
+use std::sync::Arc;
 
 use std::collections::HashMap;
 
 pub fn extract_text(path: &str) -> Result<String, BlitzParseError> {
+    Ok(extract_document(path)?.text)
+}
+
+/// Like `extract_text`, within the limits set in `options`.
+pub fn extract_text_with(path: &str, options: &ExtractOptions) -> Result<String, BlitzParseError> {
+    Ok(extract_document_with(path, options)?.text)
+}
+
+/// Extract a file's text along with the problems that were recovered from,
+/// such as unreadable PDF pages, and whether a fallback parser was needed.
+pub fn extract(path: &str) -> Result<ExtractResult, BlitzParseError> {
+    extract_with(path, &ExtractOptions::default())
+}
+
+pub fn extract_with(
+    path: &str,
+    options: &ExtractOptions,
+) -> Result<ExtractResult, BlitzParseError> {
+    Ok(extract_document_with(path, options)?.into())
+}
+
+/// Render a file as Markdown: headings, lists, tables, links, emphasis,
+/// code and footnotes where its format has them.
+pub fn extract_markdown(path: &str) -> Result<String, BlitzParseError> {
+    extract_markdown_with(path, &ExtractOptions::default())
+}
+
+pub fn extract_markdown_with(
+    path: &str,
+    options: &ExtractOptions,
+) -> Result<String, BlitzParseError> {
+    markdown::file_to_markdown(path, options)
+}
+
+/// Read a file into typed blocks with their style hints and source
+/// locations: the document tree behind the Markdown, HTML and JSON output.
+pub fn extract_tree(path: &str) -> Result<tree::DocumentTree, BlitzParseError> {
+    extract_tree_with(path, &ExtractOptions::default())
+}
+
+pub fn extract_tree_with(
+    path: &str,
+    options: &ExtractOptions,
+) -> Result<tree::DocumentTree, BlitzParseError> {
+    tree::file_to_tree(path, options)
+}
+
+/// Render a file as a sanitized HTML fragment for display.
+pub fn extract_html(path: &str) -> Result<String, BlitzParseError> {
+    extract_html_with(path, &ExtractOptions::default())
+}
+
+pub fn extract_html_with(path: &str, options: &ExtractOptions) -> Result<String, BlitzParseError> {
+    let tree = tree::read_tree(path, options)?;
+    Ok(html::render(&tree.blocks, &tree.footnotes))
+}
+
+/// Serialize a file's document tree as JSON, in the schema documented on
+/// `tree::DocumentTree`.
+pub fn extract_json(path: &str) -> Result<String, BlitzParseError> {
+    extract_json_with(path, &ExtractOptions::default())
+}
+
+pub fn extract_json_with(path: &str, options: &ExtractOptions) -> Result<String, BlitzParseError> {
+    let tree = extract_tree_with(path, options)?;
+    serde_json::to_string_pretty(&tree).map_err(|e| BlitzParseError::Io(e.into()))
+}
+
+/// Parse a file with the parser registered for its extension, or, for files
+/// without one, the parser whose sniffer recognises its content. A parser's
+/// `PartialSuccess` is accepted, its failures becoming warnings.
+pub fn extract_document(path: &str) -> Result<ParsedDocument, BlitzParseError> {
+    extract_document_with(path, &ExtractOptions::default())
+}
+
+pub fn extract_document_with(
+    path: &str,
+    options: &ExtractOptions,
+) -> Result<ParsedDocument, BlitzParseError> {
+    let options = options.started();
+    options
+        .check_input(std::fs::metadata(path)?.len())
+        .map_err(|e| e.with_path(path))?;
     let extension = Path::new(path)
         .extension()
         .and_then(|s| s.to_str())
-        .ok_or_else(|| BlitzParseError::UnsupportedFileType("No extension".to_string()))?;
+        .map(|s| s.to_lowercase());
+    input::with_file_bytes_with(path, &options, |bytes| {
+        // `resolve` releases the registry lock before any parser runs, since
+        // parsers may call back into it.
+        let parser = registry::resolve(ParserRegistry::global(), path, bytes)?;
+        ParsedDocument::recover(parser.parse(&Source {
+            bytes,
+            path: Some(path),
+            extension: extension.as_deref(),
+            options: &options,
+        }))
+    })
+    // A progress callback that failed on the last update fails the file.
+    .and_then(|document| options.checkpoint().map(|()| document))
+    .map(|mut document| {
+        for warning in &mut document.warnings {
+            warning.context.path.get_or_insert_with(|| path.to_string());
+        }
+        if let Some(normalize) = &options.normalize {
+            document.normalize(normalize);
+        }
+        document
+    })
+    .map_err(|e| e.with_path(path))
+}
 
-    match extension.to_lowercase().as_str() {
-        "pdf" => parsers::pdf::parse_pdf(path),
-        "docx" => parsers::docx::parse_docx(path),
-        "txt" => parsers::txt::parse_txt(path),
-        _ => Err(BlitzParseError::UnsupportedFileType(extension.to_string())),
+/// Extract many files in parallel. Results are returned in input order, and a
+/// file that fails yields its error without stopping the rest of the batch.
+/// Limits in `options` apply to each file, including a separate `timeout`. A
+/// parser that panics fails only its own file, with `BlitzParseError::Panicked`.
+pub fn extract_many<P: AsRef<str> + Sync>(
+    paths: &[P],
+    options: &ExtractOptions,
+) -> Result<Vec<Result<String, BlitzParseError>>, BlitzParseError> {
+    let extract = || {
+        paths
+            .par_iter()
+            .map(|path| {
+                let path = path.as_ref();
+                std::panic::catch_unwind(AssertUnwindSafe(|| extract_text_with(path, options)))
+                    .unwrap_or_else(|payload| {
+                        Err(BlitzParseError::Panicked {
+                            message: panic_message(&*payload),
+                            context: ErrorContext::default(),
+                        })
+                    })
+            })
+            .collect()
+    };
+    match options.threads {
+        // A pool of its own for each batch, so its threads end with the batch.
+        Some(threads) => Ok(rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|e| BlitzParseError::Io(std::io::Error::other(e)))?
+            .install(extract)),
+        None => Ok(extract()),
     }
 }
 
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Extract text from a file held in memory, such as an email attachment.
+/// `extension` selects the parser exactly as the file extension does for `extract_text`.
+pub fn extract_text_from_bytes(bytes: &[u8], extension: &str) -> Result<String, BlitzParseError> {
+    extract_text_from_bytes_with(bytes, extension, &ExtractOptions::default())
+}
+
+pub fn extract_text_from_bytes_with(
+    bytes: &[u8],
+    extension: &str,
+    options: &ExtractOptions,
+) -> Result<String, BlitzParseError> {
+    let options = options.started();
+    options.check_input(bytes.len() as u64)?;
+    let parser = ParserRegistry::global()
+        .read()
+        .unwrap()
+        .for_extension(extension)
+        .ok_or_else(|| BlitzParseError::unsupported(extension))?;
+    let extension = extension.to_lowercase();
+    let mut document = ParsedDocument::recover(parser.parse(&Source {
+        bytes,
+        path: None,
+        extension: Some(&extension),
+        options: &options,
+    }))?;
+    options.checkpoint()?;
+    if let Some(normalize) = &options.normalize {
+        document.normalize(normalize);
+    }
+    Ok(document.text)
+}
+
+/// Extract a file and build its knowledge graph.
+///
+/// Formats with their own structure (e-books, email, source code, transcripts) get richer
+/// graphs than the flat Document/Chunk layout used for everything else.
+pub fn build_graph(
+    path: &str,
+    metadata: &HashMap<String, String>,
+    strategy: &ChunkingStrategy,
+) -> Result<KnowledgeGraph, BlitzParseError> {
+    build_graph_with(path, metadata, strategy, &ExtractOptions::default())
+}
+
+/// Like `build_graph`, within the limits set in `options`.
+pub fn build_graph_with(
+    path: &str,
+    metadata: &HashMap<String, String>,
+    strategy: &ChunkingStrategy,
+    options: &ExtractOptions,
+) -> Result<KnowledgeGraph, BlitzParseError> {
+    let mut builder = GraphBuilder::with_options(options.clone());
+    add_to_graph(&mut builder, path, metadata, strategy, options)?;
+    Ok(builder.build())
+}
+
+/// Extract a file and add its Document (and everything beneath it) to `builder`.
+pub(crate) fn add_to_graph(
+    builder: &mut GraphBuilder,
+    path: &str,
+    metadata: &HashMap<String, String>,
+    strategy: &ChunkingStrategy,
+    options: &ExtractOptions,
+) -> Result<(), BlitzParseError> {
+    add_file_to_graph(builder, path, metadata, strategy, options).map_err(|e| e.with_path(path))
+}
+
+fn add_file_to_graph(
+    builder: &mut GraphBuilder,
+    path: &str,
+    metadata: &HashMap<String, String>,
+    strategy: &ChunkingStrategy,
+    options: &ExtractOptions,
+) -> Result<(), BlitzParseError> {
+    let options = options.started();
+    options.check_input(std::fs::metadata(path)?.len())?;
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase());
+
+    // A parser registered by the caller replaces the built-in graph shape for
+    // its extensions; the graph is then built from its `ParsedDocument`.
+    let custom = extension.as_deref().is_some_and(|ext| {
+        let registry = ParserRegistry::global().read().unwrap();
+        registry.for_extension(ext).is_some() && !registry.is_builtin(ext)
+    });
+
+    match extension.as_deref() {
+        _ if custom => build_from_document(builder, path, metadata, strategy, &options)?,
+        // An extensionless file is read as source only after a shebang; any
+        // other is sniffed below, as `extract_document` does.
+        None if has_shebang(path)? => {
+            let file = read_code(path, &options)?;
+            builder.with_code(metadata, &file, strategy);
+        }
+        Some(ext) if parsers::code::Language::from_extension(ext).is_some() => {
+            let file = read_code(path, &options)?;
+            builder.with_code(metadata, &file, strategy);
+        }
+        Some("csv") | Some("tsv") => {
+            let mut records = parsers::csv::read_csv(path)?.records();
+            normalize_records(&mut records, &options);
+            builder.with_records(metadata, &records);
+        }
+        Some(ext @ ("jsonl" | "ndjson")) => {
+            // Each line is one record.
+            let flatten = parsers::structured::FlattenOptions {
+                records_at: Some("/".to_string()),
+                ..Default::default()
+            };
+            let bytes = std::fs::read(path)?;
+            let mut records = parsers::structured::flatten_records(&bytes, ext, &flatten)?;
+            normalize_records(&mut records, &options);
+            builder.with_records(metadata, &records);
+        }
+        Some("epub") => {
+            // E-books carry their own Dublin Core metadata and outline; caller-supplied
+            // metadata takes precedence over what the book declares.
+            let book = parsers::epub::read_epub_with(path, &options)?;
+            let mut merged = book.metadata.to_map();
+            merged.extend(metadata.clone());
+            let mut sections = book.sections();
+            for section in &mut sections {
+                options.normalize_text(&mut section.content);
+            }
+            builder.with_sections(&merged, &sections, strategy);
+        }
+        Some("ipynb") => {
+            // Notebook metadata yields title/author/language nodes; the caller's wins.
+            let mut notebook = parsers::notebook::read_ipynb(path)?;
+            for cell in &mut notebook.cells {
+                options.normalize_text(&mut cell.source);
+                cell.outputs
+                    .iter_mut()
+                    .for_each(|output| options.normalize_text(output));
+            }
+            let mut merged = notebook.metadata.to_map();
+            merged.extend(metadata.clone());
+            builder.with_notebook(&merged, &notebook, true, strategy);
+        }
+        Some("srt") | Some("vtt") => {
+            let mut cues = parsers::subtitle::read_subtitles(path)?.cues;
+            for cue in &mut cues {
+                options.normalize_text(&mut cue.text);
+            }
+            builder.with_transcript(metadata, &cues, strategy);
+        }
+        Some("eml") => {
+            let mut message = parsers::email::read_eml_with(path, &options)?;
+            // Attachment text is normalized as it is extracted.
+            options.normalize_text(&mut message.body);
+            builder.with_emails(metadata, &[message], strategy);
+        }
+        Some("mbox") => {
+            let mut mbox = parsers::email::read_mbox_with(path, &options)?;
+            for message in &mut mbox.messages {
+                options.normalize_text(&mut message.body);
+            }
+            builder.with_emails(metadata, &mbox.messages, strategy);
+        }
+        _ => build_from_document(builder, path, metadata, strategy, &options)?,
+    }
+    // The builder stops chunking when cancelled; fail rather than return a partial graph.
+    options.checkpoint()
+}
+
+/// Read a source file, normalizing the code of each item.
+fn read_code(path: &str, options: &ExtractOptions) -> Result<CodeFile, BlitzParseError> {
+    let mut file = parsers::code::read_code(path)?;
+    for item in &mut file.items {
+        options.normalize_text(&mut item.code);
+    }
+    Ok(file)
+}
+
+/// Whether the file's first line is a `#!` line naming a language `read_code`
+/// knows. Only the first 256 bytes are read.
+fn has_shebang(path: &str) -> Result<bool, BlitzParseError> {
+    use std::io::{BufRead, Read};
+    let mut line = Vec::new();
+    std::io::BufReader::new(std::fs::File::open(path)?)
+        .take(256)
+        .read_until(b'\n', &mut line)?;
+    Ok(parsers::code::Language::from_shebang(&String::from_utf8_lossy(&line)).is_some())
+}
+
+/// Normalize the values of records; column names are left as they are.
+fn normalize_records(records: &mut [Vec<(String, String)>], options: &ExtractOptions) {
+    for (_, value) in records.iter_mut().flatten() {
+        options.normalize_text(value);
+    }
+}
+
+fn build_from_document(
+    builder: &mut GraphBuilder,
+    path: &str,
+    metadata: &HashMap<String, String>,
+    strategy: &ChunkingStrategy,
+    options: &ExtractOptions,
+) -> Result<(), BlitzParseError> {
+    let document = extract_document_with(path, options)?;
+    let mut merged = document.metadata;
+    merged.extend(metadata.clone());
+    if document.sections.is_empty() {
+        builder.with_located_document(&merged, &document.text, &document.provenance, strategy);
+    } else {
+        builder.with_sections(&merged, &document.sections, strategy);
+    }
+    Ok(())
+}
+
 // This `#[pymodule]` block creates the Python module.
 // Python will be able to `import blitzparse` because of this.
 #[pymodule]
 fn blitz_parse(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(extract_text_py, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_result_py, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_markdown_py, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_html_py, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_json_py, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_many_py, m)?)?;
     m.add_function(wrap_pyfunction!(build_graph_py, m)?)?;
+    m.add_function(wrap_pyfunction!(ingest_directory_py, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_archive_py, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_structured_py, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_txt_py, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_notebook_py, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_transcript_py, m)?)?;
+    m.add_function(wrap_pyfunction!(iter_chunks_py, m)?)?;
+    m.add_function(wrap_pyfunction!(register_parser_py, m)?)?;
     m.add_class::<ChunkingStrategy>()?;
+    m.add_class::<ChunkIterator>()?;
+    m.add_class::<progress::CancellationToken>()?;
+    m.add_class::<ExtractOptions>()?;
+    m.add_class::<normalize::NormalizeOptions>()?;
+    m.add_class::<normalize::UnicodeForm>()?;
+    exceptions::register(m)?;
     Ok(())
 }
 
 // This `#[pyfunction]` attribute exposes our Rust function to Python.
 // We also handle the error conversion here, turning a Rust `Result` into a Python exception.
 #[pyfunction]
-fn extract_text_py(path: &str) -> PyResult<String> {
-    match extract_text(path) {
+#[pyo3(signature = (path, options=None))]
+fn extract_text_py(
+    py: Python<'_>,
+    path: &str,
+    options: Option<ExtractOptions>,
+) -> PyResult<String> {
+    // Release the GIL so another thread can cancel the extraction.
+    let options = options.unwrap_or_default();
+    match py.detach(|| extract_text_with(path, &options)) {
         Ok(text) => Ok(text),
-        Err(e) => Err(pyo3::exceptions::PyValueError::new_err(e.to_string())),
+        Err(e) => Err(exceptions::to_py_err(e, Some(path))),
+    }
+}
+
+/// Extract a file, keeping going past broken pages and parts.
+/// Returns a dict with `content`, `strategy` (`fast_path` or `fallback`),
+/// `warnings`, a list of dicts with `kind`, `message`, `part` and `offset`,
+/// and `provenance`, a list of dicts with `start` and `end` offsets into
+/// `content`, the locator's `kind` and its fields.
+#[pyfunction]
+#[pyo3(signature = (path, options=None))]
+fn extract_result_py<'py>(
+    py: Python<'py>,
+    path: &str,
+    options: Option<ExtractOptions>,
+) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+    let options = options.unwrap_or_default();
+    let result = match py.detach(|| extract_with(path, &options)) {
+        Ok(result) => result,
+        Err(e) => return Err(exceptions::to_py_err(e, Some(path))),
+    };
+
+    let warnings = pyo3::types::PyList::empty(py);
+    for warning in result.warnings {
+        let item = pyo3::types::PyDict::new(py);
+        item.set_item("kind", warning.kind.to_string())?;
+        item.set_item("message", warning.message)?;
+        item.set_item("part", warning.context.part)?;
+        item.set_item("offset", warning.context.offset)?;
+        warnings.append(item)?;
+    }
+
+    let provenance = pyo3::types::PyList::empty(py);
+    for entry in result.provenance {
+        let item = pyo3::types::PyDict::new(py);
+        item.set_item("start", entry.start)?;
+        item.set_item("end", entry.end)?;
+        match entry.locator {
+            Locator::Docx {
+                part,
+                paragraph,
+                last_paragraph,
+            } => {
+                item.set_item("kind", "docx")?;
+                item.set_item("part", part)?;
+                item.set_item("paragraph", paragraph)?;
+                item.set_item("last_paragraph", last_paragraph)?;
+            }
+            Locator::Pdf { page, bbox } => {
+                item.set_item("kind", "pdf")?;
+                item.set_item("page", page)?;
+                item.set_item("bbox", bbox)?;
+            }
+            Locator::Xlsx { sheet, range } => {
+                item.set_item("kind", "xlsx")?;
+                item.set_item("sheet", sheet)?;
+                item.set_item("range", range)?;
+            }
+            Locator::Cell { cell } => {
+                item.set_item("kind", "cell")?;
+                item.set_item("cell", cell)?;
+            }
+        }
+        provenance.append(item)?;
+    }
+
+    let dict = pyo3::types::PyDict::new(py);
+    dict.set_item("content", result.content)?;
+    dict.set_item("strategy", result.strategy.to_string())?;
+    dict.set_item("warnings", warnings)?;
+    dict.set_item("provenance", provenance)?;
+    Ok(dict)
+}
+
+/// Render a file as GitHub-flavoured Markdown.
+#[pyfunction]
+#[pyo3(signature = (path, options=None))]
+fn extract_markdown_py(
+    py: Python<'_>,
+    path: &str,
+    options: Option<ExtractOptions>,
+) -> PyResult<String> {
+    let options = options.unwrap_or_default();
+    py.detach(|| extract_markdown_with(path, &options))
+        .map_err(|e| exceptions::to_py_err(e, Some(path)))
+}
+
+/// Render a file as a sanitized HTML fragment.
+#[pyfunction]
+#[pyo3(signature = (path, options=None))]
+fn extract_html_py(
+    py: Python<'_>,
+    path: &str,
+    options: Option<ExtractOptions>,
+) -> PyResult<String> {
+    let options = options.unwrap_or_default();
+    py.detach(|| extract_html_with(path, &options))
+        .map_err(|e| exceptions::to_py_err(e, Some(path)))
+}
+
+/// Return a file's document tree as JSON, with `schema_version` as its first key.
+#[pyfunction]
+#[pyo3(signature = (path, options=None))]
+fn extract_json_py(
+    py: Python<'_>,
+    path: &str,
+    options: Option<ExtractOptions>,
+) -> PyResult<String> {
+    let options = options.unwrap_or_default();
+    py.detach(|| extract_json_with(path, &options))
+        .map_err(|e| exceptions::to_py_err(e, Some(path)))
+}
+
+/// Extract many files in parallel without holding the GIL.
+/// Returns one dict per input path, in order, as described on `result_dict`.
+#[pyfunction]
+#[pyo3(signature = (paths, threads=None, options=None))]
+fn extract_many_py(
+    py: Python<'_>,
+    paths: Vec<String>,
+    threads: Option<usize>,
+    options: Option<ExtractOptions>,
+) -> PyResult<Vec<Bound<'_, pyo3::types::PyDict>>> {
+    let mut options = options.unwrap_or_default();
+    options.threads = threads.or(options.threads);
+    let results = match py.detach(|| extract_many(&paths, &options)) {
+        Ok(results) => results,
+        Err(e) => return Err(exceptions::to_py_err(e, None)),
+    };
+
+    paths
+        .into_iter()
+        .zip(results)
+        .map(|(path, result)| result_dict(py, path, result))
+        .collect()
+}
+
+/// A dict with the `path` of one file or member and either its `text` or its
+/// failure: `error` holds the message and `exception` the exception object
+/// `to_py_err` would raise for it, with its class and structured context.
+fn result_dict(
+    py: Python<'_>,
+    path: String,
+    result: Result<String, BlitzParseError>,
+) -> PyResult<Bound<'_, pyo3::types::PyDict>> {
+    let dict = pyo3::types::PyDict::new(py);
+    let (text, error, exception) = match result {
+        Ok(text) => (Some(text), None, None),
+        Err(e) => {
+            let message = e.to_string();
+            let exception = exceptions::to_py_err(e, Some(&path)).into_value(py);
+            (None, Some(message), Some(exception))
+        }
+    };
+    dict.set_item("path", path)?;
+    dict.set_item("text", text)?;
+    dict.set_item("error", error)?;
+    dict.set_item("exception", exception)?;
+    Ok(dict)
+}
+
+/// Run `extract` over `path` with the GIL released, under started `options`
+/// with `max_input_bytes` enforced, raising failures as `to_py_err` does.
+fn extract_detached<T: Send>(
+    py: Python<'_>,
+    path: &str,
+    options: Option<ExtractOptions>,
+    extract: impl FnOnce(&ExtractOptions) -> Result<T, BlitzParseError> + Send,
+) -> PyResult<T> {
+    let options = options.unwrap_or_default();
+    py.detach(|| {
+        let options = options.started();
+        options.check_input(std::fs::metadata(path)?.len())?;
+        options.checkpoint()?;
+        let extracted = extract(&options)?;
+        options.checkpoint()?;
+        Ok(extracted)
+    })
+    .map_err(|e| exceptions::to_py_err(e, Some(path)))
+}
+
+/// Extract each member of a .zip/.tar/.tar.gz/.gz archive, descending at
+/// most `max_depth` containers deep. Returns one dict per member, as
+/// described on `result_dict`.
+#[pyfunction]
+#[pyo3(signature = (path, max_depth=4, options=None))]
+fn extract_archive_py<'py>(
+    py: Python<'py>,
+    path: &str,
+    max_depth: usize,
+    options: Option<ExtractOptions>,
+) -> PyResult<Vec<Bound<'py, pyo3::types::PyDict>>> {
+    let limits = parsers::archive::ArchiveLimits {
+        max_depth,
+        ..Default::default()
+    };
+    let members = extract_detached(py, path, options, |options| {
+        parsers::archive::extract_archive_with(path, &limits, options)
+    })?;
+
+    members
+        .into_iter()
+        .map(|member| result_dict(py, member.path, member.text))
+        .collect()
+}
+
+/// Flatten a .json/.jsonl/.xml file into `path: value` text.
+/// `include`/`exclude` take JSON-pointer style paths; `records_at` selects the
+/// array or element whose items are rendered one record per line.
+#[pyfunction]
+#[pyo3(signature = (path, include=Vec::new(), exclude=Vec::new(), records_at=None, options=None))]
+fn extract_structured_py(
+    py: Python<'_>,
+    path: &str,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    records_at: Option<String>,
+    options: Option<ExtractOptions>,
+) -> PyResult<String> {
+    let flatten = parsers::structured::FlattenOptions {
+        include,
+        exclude,
+        records_at,
+    };
+    extract_detached(py, path, options, |options| {
+        let mut text = parsers::structured::extract_structured_with(path, &flatten, options)?;
+        options.normalize_text(&mut text);
+        Ok(text)
+    })
+}
+
+/// Decode a plain-text file in any common encoding.
+/// Returns a dict with `text`, `encoding`, `confidence` and `had_errors`; with
+/// `lossy=True` undecodable bytes become U+FFFD instead of raising.
+#[pyfunction]
+#[pyo3(signature = (path, lossy=false, options=None))]
+fn extract_txt_py<'py>(
+    py: Python<'py>,
+    path: &str,
+    lossy: bool,
+    options: Option<ExtractOptions>,
+) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+    let decoded = extract_detached(py, path, options, |options| {
+        let mut decoded = parsers::txt::read_txt_with(path, lossy, options)?;
+        options.normalize_text(&mut decoded.text);
+        Ok(decoded)
+    })?;
+    let dict = pyo3::types::PyDict::new(py);
+    dict.set_item("text", decoded.text)?;
+    dict.set_item("encoding", decoded.encoding.name())?;
+    dict.set_item("confidence", decoded.encoding.confidence)?;
+    dict.set_item("had_errors", decoded.had_errors)?;
+    Ok(dict)
+}
+
+/// Parse a Jupyter notebook into its metadata and ordered cells.
+/// Returns a dict with `metadata` (a dict of strings) and `cells`, each a dict
+/// with `cell_type`, `source`, `execution_count` and, with
+/// `include_outputs=True`, `outputs`.
+#[pyfunction]
+#[pyo3(signature = (path, include_outputs=false, options=None))]
+fn extract_notebook_py<'py>(
+    py: Python<'py>,
+    path: &str,
+    include_outputs: bool,
+    options: Option<ExtractOptions>,
+) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+    let notebook = extract_detached(py, path, options, |options| {
+        let mut notebook = parsers::notebook::read_ipynb(path)?;
+        for cell in &mut notebook.cells {
+            options.normalize_text(&mut cell.source);
+        }
+        Ok(notebook)
+    })?;
+    let meta = &notebook.metadata;
+    let metadata = pyo3::types::PyDict::new(py);
+    metadata.set_item("title", &meta.title)?;
+    metadata.set_item("authors", &meta.authors)?;
+    metadata.set_item("language", &meta.language)?;
+    metadata.set_item("language_version", &meta.language_version)?;
+    metadata.set_item("kernel", &meta.kernel_name)?;
+    metadata.set_item("kernel_display_name", &meta.kernel_display_name)?;
+    metadata.set_item("nbformat", &meta.nbformat)?;
+
+    let cells = pyo3::types::PyList::empty(py);
+    for cell in notebook.cells {
+        let item = pyo3::types::PyDict::new(py);
+        item.set_item("cell_type", cell.cell_type)?;
+        item.set_item("source", cell.source)?;
+        item.set_item("execution_count", cell.execution_count)?;
+        if include_outputs {
+            item.set_item("outputs", cell.outputs)?;
+        }
+        cells.append(item)?;
     }
+
+    let dict = pyo3::types::PyDict::new(py);
+    dict.set_item("metadata", metadata)?;
+    dict.set_item("cells", cells)?;
+    Ok(dict)
 }
 
+/// Read the cues of a .srt/.vtt file.
+/// Returns one dict per cue with `start_ms`, `end_ms`, `speaker` and `text`.
 #[pyfunction]
+#[pyo3(signature = (path, options=None))]
+fn extract_transcript_py<'py>(
+    py: Python<'py>,
+    path: &str,
+    options: Option<ExtractOptions>,
+) -> PyResult<Vec<Bound<'py, pyo3::types::PyDict>>> {
+    let cues = extract_detached(py, path, options, |options| {
+        let mut cues = parsers::subtitle::read_subtitles(path)?.cues;
+        for cue in &mut cues {
+            options.normalize_text(&mut cue.text);
+        }
+        Ok(cues)
+    })?;
+    cues.into_iter()
+        .map(|cue| {
+            let dict = pyo3::types::PyDict::new(py);
+            dict.set_item("start_ms", cue.start_ms)?;
+            dict.set_item("end_ms", cue.end_ms)?;
+            dict.set_item("speaker", cue.speaker)?;
+            dict.set_item("text", cue.text)?;
+            Ok(dict)
+        })
+        .collect()
+}
+
+#[pyfunction]
+#[pyo3(signature = (path, metadata, strategy, options=None))]
 fn build_graph_py(
+    py: Python<'_>,
     path: &str,
     metadata: HashMap<String, String>,
     strategy: ChunkingStrategy,
+    options: Option<ExtractOptions>,
 ) -> PyResult<String> {
-    let text_content = match extract_text(path) {
-        Ok(text) => text,
-        Err(e) => return Err(pyo3::exceptions::PyValueError::new_err(e.to_string())),
+    let options = options.unwrap_or_default();
+    let graph = match py.detach(|| build_graph_with(path, &metadata, &strategy, &options)) {
+        Ok(graph) => graph,
+        Err(e) => return Err(exceptions::to_py_err(e, Some(path))),
     };
 
-    let mut builder = GraphBuilder::new();
-    builder.with_document(&metadata, &text_content, &strategy);
-    let graph = builder.build();
-
     match serde_json::to_string_pretty(&graph) {
         Ok(json_string) => Ok(json_string),
         Err(e) => Err(pyo3::exceptions::PyValueError::new_err(format!(
@@ -68,6 +796,196 @@ fn build_graph_py(
     }
 }
 
+/// Walk a directory tree into one knowledge graph, without holding the GIL.
+/// Returns the graph as JSON and a list of `{path, error}` dicts for files
+/// that could not be extracted.
+#[pyfunction]
+#[pyo3(signature = (root, strategy, include_globs=Vec::new(), exclude_globs=Vec::new(), follow_links=false, metadata=HashMap::new(), options=None))]
+#[allow(clippy::too_many_arguments)]
+fn ingest_directory_py(
+    py: Python<'_>,
+    root: &str,
+    strategy: ChunkingStrategy,
+    include_globs: Vec<String>,
+    exclude_globs: Vec<String>,
+    follow_links: bool,
+    metadata: HashMap<String, String>,
+    options: Option<ExtractOptions>,
+) -> PyResult<(String, Vec<HashMap<&'static str, String>>)> {
+    let options = ingest::IngestOptions {
+        include: include_globs,
+        exclude: exclude_globs,
+        follow_links,
+        extract: options.unwrap_or_default(),
+    };
+    let ingested =
+        match py.detach(|| ingest::ingest_directory(root, &options, &metadata, &strategy)) {
+            Ok(ingested) => ingested,
+            Err(e) => return Err(exceptions::to_py_err(e, Some(root))),
+        };
+
+    let graph = serde_json::to_string_pretty(&ingested.graph).map_err(|e| {
+        pyo3::exceptions::PyValueError::new_err(format!("Failed to serialize graph to JSON: {}", e))
+    })?;
+    let errors = ingested
+        .errors
+        .into_iter()
+        .map(|(path, e)| {
+            HashMap::from([
+                ("path", path.to_string_lossy().into_owned()),
+                ("error", e.to_string()),
+            ])
+        })
+        .collect();
+    Ok((graph, errors))
+}
+
+/// Python iterator over the chunks of one file, from `iter_chunks`.
+#[pyclass]
+struct ChunkIterator {
+    path: String,
+    inner: std::sync::Mutex<stream::ChunkIter>,
+}
+
+#[pymethods]
+impl ChunkIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<String>> {
+        match py.detach(|| self.inner.lock().unwrap().next()) {
+            Some(Ok(chunk)) => Ok(Some(chunk)),
+            Some(Err(e)) => Err(exceptions::to_py_err(e, Some(&self.path))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Iterate over a file's chunks as they are extracted, a block of a text
+/// file or a page of a PDF at a time. A PDF is still loaded whole first:
+/// `for chunk in blitz_parse.iter_chunks(path, strategy)`.
+#[pyfunction(name = "iter_chunks")]
+#[pyo3(signature = (path, strategy, options=None))]
+fn iter_chunks_py(
+    path: &str,
+    strategy: ChunkingStrategy,
+    options: Option<ExtractOptions>,
+) -> PyResult<ChunkIterator> {
+    match stream::iter_chunks(path, &strategy, &options.unwrap_or_default()) {
+        Ok(iter) => Ok(ChunkIterator {
+            path: path.to_string(),
+            inner: std::sync::Mutex::new(iter),
+        }),
+        Err(e) => Err(exceptions::to_py_err(e, Some(path))),
+    }
+}
+
+/// A parser implemented by Python callables, registered via `register_parser_py`.
+struct PyParser {
+    name: String,
+    extensions: Vec<String>,
+    mime_types: Vec<String>,
+    parse: Py<PyAny>,
+    sniff: Option<Py<PyAny>>,
+}
+
+impl registry::Parser for PyParser {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        self.extensions.clone()
+    }
+
+    fn mime_types(&self) -> Vec<String> {
+        self.mime_types.clone()
+    }
+
+    fn sniff(&self, bytes: &[u8]) -> bool {
+        let Some(sniff) = &self.sniff else {
+            return false;
+        };
+        Python::attach(|py| {
+            let bytes = pyo3::types::PyBytes::new(py, bytes);
+            sniff
+                .call1(py, (bytes,))
+                .and_then(|result| result.extract::<bool>(py))
+                .unwrap_or(false)
+        })
+    }
+
+    fn parse(&self, source: &Source) -> Result<ParsedDocument, BlitzParseError> {
+        Python::attach(|py| {
+            let bytes = pyo3::types::PyBytes::new(py, source.bytes);
+            let plugin_error = |e: PyErr| BlitzParseError::Plugin {
+                name: self.name.clone(),
+                context: ErrorContext::default(),
+                source: Some(Box::new(e)),
+            };
+            let result = self
+                .parse
+                .call1(py, (bytes, source.path))
+                .map_err(plugin_error)?;
+            // Either the text itself or a dict with `text` and optional `metadata`.
+            if let Ok(text) = result.extract::<String>(py) {
+                return Ok(ParsedDocument::from_text(text));
+            }
+            let dict = result
+                .downcast_bound::<pyo3::types::PyDict>(py)
+                .map_err(|e| plugin_error(e.into()))?;
+            let text = match dict.get_item("text").map_err(plugin_error)? {
+                Some(text) => text.extract::<String>().map_err(plugin_error)?,
+                None => String::new(),
+            };
+            let metadata = match dict.get_item("metadata").map_err(plugin_error)? {
+                Some(metadata) => metadata
+                    .extract::<HashMap<String, String>>()
+                    .map_err(plugin_error)?,
+                None => HashMap::new(),
+            };
+            Ok(ParsedDocument {
+                text,
+                metadata,
+                ..Default::default()
+            })
+        })
+    }
+}
+
+/// Register a Python parser for `extensions`, taking precedence over any
+/// built-in parser for them. `parse(data: bytes, path: str | None)` returns
+/// the text, or a dict with `text` and an optional `metadata` dict of strings.
+/// `sniff(data: bytes) -> bool` lets the parser claim files without an extension.
+#[pyfunction]
+#[pyo3(signature = (name, extensions, parse, mime_types=Vec::new(), sniff=None))]
+fn register_parser_py(
+    py: Python<'_>,
+    name: String,
+    extensions: Vec<String>,
+    parse: Py<PyAny>,
+    mime_types: Vec<String>,
+    sniff: Option<Py<PyAny>>,
+) -> PyResult<()> {
+    let parser = PyParser {
+        name,
+        extensions,
+        mime_types,
+        parse,
+        sniff,
+    };
+    // Release the GIL while waiting for the lock: a reader may be running a
+    // Python sniffer, which needs it.
+    py.detach(|| {
+        ParserRegistry::global()
+            .write()
+            .unwrap()
+            .register(Arc::new(parser))
+    });
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,4 +1007,137 @@ mod tests {
         let extracted_text = result.unwrap();
         assert_eq!(extracted_text.trim(), expected_text);
     }
+
+    #[test]
+    fn it_normalizes_text_in_structured_graphs() {
+        let dir = tempdir().unwrap();
+        let csv = dir.path().join("items.csv");
+        std::fs::write(&csv, "name\n\u{201c}\u{fb01}ne\u{201d}   print\n").unwrap();
+        let srt = dir.path().join("talk.srt");
+        std::fs::write(
+            &srt,
+            "1\n00:00:01,000 --> 00:00:02,000\n\u{fb01}rst  words\n",
+        )
+        .unwrap();
+
+        let options = ExtractOptions {
+            normalize: Some(normalize::NormalizeOptions::default()),
+            ..Default::default()
+        };
+        let strategy = ChunkingStrategy::ByLine();
+        let texts = |path: &Path| -> Vec<String> {
+            build_graph_with(path.to_str().unwrap(), &HashMap::new(), &strategy, &options)
+                .unwrap()
+                .nodes
+                .into_iter()
+                .filter_map(|node| node.properties.get("text").cloned())
+                .collect()
+        };
+        assert_eq!(texts(&csv), vec!["name: \"fine\" print"]);
+        assert_eq!(texts(&srt), vec!["first words"]);
+    }
+
+    #[test]
+    fn it_sniffs_extensionless_files_unless_they_have_a_shebang() {
+        let dir = tempdir().unwrap();
+        let script = dir.path().join("deploy");
+        std::fs::write(&script, "#!/usr/bin/env python3\ndef main():\n    pass\n").unwrap();
+        let report = dir.path().join("report");
+        std::fs::copy("tests/assets/sample.pdf", &report).unwrap();
+
+        let strategy = ChunkingStrategy::ByLine();
+        let language = |path: &Path| {
+            build_graph(path.to_str().unwrap(), &HashMap::new(), &strategy)
+                .unwrap()
+                .nodes
+                .into_iter()
+                .find(|node| node.label == "Document")
+                .unwrap()
+                .properties
+                .get("language")
+                .cloned()
+        };
+        assert_eq!(language(&script).as_deref(), Some("python"));
+        // The PDF is chunked from its extracted text, not read as source.
+        assert_eq!(language(&report), None);
+        let text = extract_text(report.to_str().unwrap()).unwrap();
+        let graph = build_graph(report.to_str().unwrap(), &HashMap::new(), &strategy).unwrap();
+        let chunks: Vec<_> = graph
+            .nodes
+            .iter()
+            .filter(|node| node.label == "Chunk")
+            .collect();
+        assert!(!chunks.is_empty());
+        assert!(chunks
+            .iter()
+            .all(|chunk| text.contains(chunk.properties["text"].as_str())));
+    }
+
+    #[test]
+    fn it_extracts_many_files_in_order() {
+        let dir = tempdir().unwrap();
+        let mut paths = Vec::new();
+        for i in 0..8 {
+            let file_path = dir.path().join(format!("{}.txt", i));
+            std::fs::write(&file_path, format!("file {}", i)).unwrap();
+            paths.push(file_path.to_str().unwrap().to_string());
+        }
+        paths.insert(
+            3,
+            dir.path().join("missing.txt").to_str().unwrap().to_string(),
+        );
+
+        let options = ExtractOptions {
+            threads: Some(2),
+            ..Default::default()
+        };
+        let results = extract_many(&paths, &options).unwrap();
+        assert_eq!(results.len(), 9);
+        assert!(results[3].is_err());
+        let texts: Vec<_> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+        let expected: Vec<_> = (0..8).map(|i| format!("file {}", i)).collect();
+        assert_eq!(texts, expected.iter().collect::<Vec<_>>());
+    }
+
+    struct Panicking;
+
+    impl registry::Parser for Panicking {
+        fn name(&self) -> String {
+            "panicking".to_string()
+        }
+
+        fn extensions(&self) -> Vec<String> {
+            vec!["boom".to_string()]
+        }
+
+        fn parse(&self, _source: &Source) -> Result<ParsedDocument, BlitzParseError> {
+            panic!("parser bug")
+        }
+    }
+
+    #[test]
+    fn it_contains_a_parser_panic_to_its_file() {
+        ParserRegistry::global()
+            .write()
+            .unwrap()
+            .register(Arc::new(Panicking));
+        let dir = tempdir().unwrap();
+        let bad = dir.path().join("bad.boom");
+        let good = dir.path().join("good.txt");
+        std::fs::write(&bad, "x").unwrap();
+        std::fs::write(&good, "fine").unwrap();
+        let paths = [bad.to_str().unwrap(), good.to_str().unwrap()];
+
+        let options = ExtractOptions {
+            threads: Some(2),
+            ..Default::default()
+        };
+        for _ in 0..2 {
+            let results = extract_many(&paths, &options).unwrap();
+            assert!(
+                matches!(&results[0], Err(BlitzParseError::Panicked { message, .. }) if message == "parser bug")
+            );
+            assert_eq!(results[1].as_ref().unwrap(), "fine");
+        }
+    }
 }
@@ -0,0 +1,340 @@
+use crate::errors::BlitzParseError;
+use crate::options::ExtractOptions;
+use crate::parsers;
+use crate::tree::{Block, BlockKind, Footnote, Span};
+use std::path::Path;
+
+/// Render blocks and footnotes as GitHub-flavoured Markdown.
+pub fn render(blocks: &[Block], footnotes: &[Footnote]) -> String {
+    let mut out = String::new();
+    let mut previous_was_item = false;
+    for block in blocks {
+        let is_item = matches!(block.kind, BlockKind::ListItem { .. });
+        if !out.is_empty() {
+            // Items of one list stay together; everything else is separated.
+            out.push_str(if is_item && previous_was_item {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        previous_was_item = is_item;
+
+        match &block.kind {
+            BlockKind::Heading { level, spans } => {
+                out.push_str(&"#".repeat((*level).clamp(1, 6)));
+                out.push(' ');
+                out.push_str(&render_inline(spans).replace('\n', " "));
+            }
+            BlockKind::Paragraph { spans } => {
+                out.push_str(&escape_line_start(&render_inline(spans)))
+            }
+            BlockKind::ListItem {
+                depth,
+                ordered,
+                spans,
+            } => {
+                out.push_str(&"   ".repeat(*depth));
+                out.push_str(if *ordered { "1. " } else { "- " });
+                out.push_str(&render_inline(spans).replace('\n', " "));
+            }
+            BlockKind::Table { rows } => out.push_str(&render_table(rows)),
+            BlockKind::Code { language, text } => {
+                out.push_str(&code_block(language.as_deref(), text))
+            }
+        }
+    }
+
+    for Footnote { label, spans } in footnotes {
+        if !out.is_empty() {
+            out.push_str("\n\n");
+        }
+        out.push_str(&format!(
+            "[^{}]: {}",
+            label,
+            render_inline(spans).replace('\n', " ")
+        ));
+    }
+    out
+}
+
+/// A fenced code block, fenced with more backticks than `text` contains in a row.
+pub fn code_block(language: Option<&str>, text: &str) -> String {
+    let fence = "`".repeat(longest_run(text, '`').max(2) + 1);
+    format!(
+        "{}{}\n{}\n{}",
+        fence,
+        language.unwrap_or(""),
+        text.trim_end_matches('\n'),
+        fence
+    )
+}
+
+/// A GFM table. Rows are padded to the widest one, so the result is valid
+/// however ragged the source was.
+pub fn render_table(rows: &[Vec<Vec<Span>>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+    let cell = |spans: &Vec<Span>| {
+        render_inline(spans)
+            .trim()
+            .replace('|', "\\|")
+            .replace('\n', "<br>")
+    };
+    let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    for (i, row) in rows.iter().enumerate() {
+        let mut cells: Vec<String> = row.iter().map(cell).collect();
+        cells.resize(columns, String::new());
+        lines.push(line(cells));
+        if i == 0 {
+            lines.push(line(vec!["---".to_string(); columns]));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Render spans as inline Markdown. Adjacent spans with the same formatting
+/// are merged first, so emphasis markers are not repeated run by run.
+pub fn render_inline(spans: &[Span]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < spans.len() {
+        // Group spans under the same link, then by formatting within it.
+        let link = &spans[i].link;
+        let end = (i..spans.len())
+            .find(|&j| spans[j].link != *link)
+            .unwrap_or(spans.len());
+        let mut content = String::new();
+        let mut j = i;
+        while j < end {
+            let first = &spans[j];
+            if let Some(label) = &first.footnote {
+                content.push_str(&format!("[^{}]", label));
+                j += 1;
+                continue;
+            }
+            let mut text = first.text.clone();
+            j += 1;
+            while j < end && spans[j].same_format(first) {
+                text.push_str(&spans[j].text);
+                j += 1;
+            }
+            content.push_str(&format_run(&text, first));
+        }
+        match link {
+            Some(url) if !content.trim().is_empty() => {
+                let url = if url.contains([' ', '(', ')']) {
+                    format!("<{}>", url)
+                } else {
+                    url.clone()
+                };
+                // Like emphasis markers, the brackets hug the link text.
+                let trimmed = content.trim();
+                let start = content.len() - content.trim_start().len();
+                content = format!(
+                    "{}[{}]({}){}",
+                    &content[..start],
+                    trimmed,
+                    url,
+                    &content[start + trimmed.len()..]
+                );
+            }
+            _ => {}
+        }
+        out.push_str(&content);
+        i = end;
+    }
+    out
+}
+
+/// Escape and mark up one run. Markers hug the text: leading and trailing
+/// whitespace stays outside them, as CommonMark requires.
+fn format_run(text: &str, format: &Span) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_string();
+    }
+    let start = text.len() - text.trim_start().len();
+    let (leading, rest) = text.split_at(start);
+    let trailing = &rest[trimmed.len()..];
+
+    let body = if format.code {
+        let ticks = "`".repeat(longest_run(trimmed, '`') + 1);
+        let pad = if trimmed.starts_with('`') || trimmed.ends_with('`') {
+            " "
+        } else {
+            ""
+        };
+        format!("{}{}{}{}{}", ticks, pad, trimmed, pad, ticks)
+    } else {
+        escape(trimmed)
+    };
+    let marker = match (format.bold, format.italic) {
+        (true, true) => "***",
+        (true, false) => "**",
+        (false, true) => "*",
+        (false, false) => "",
+    };
+    format!("{}{}{}{}{}", leading, marker, body, marker, trailing)
+}
+
+/// Backslash-escape characters with inline meaning in Markdown.
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Escape each line of a paragraph that would otherwise start a heading,
+/// list or quote.
+fn escape_line_start(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            // Markers may follow up to three spaces of indentation.
+            let (indent, line) = line.split_at(line.len() - line.trim_start().len());
+            let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let starts_list = digits > 0 && line[digits..].starts_with(['.', ')']);
+            if starts_list {
+                format!("{}{}\\{}", indent, &line[..digits], &line[digits..])
+            } else if line.starts_with(['#', '-', '+', '=']) {
+                format!("{}\\{}", indent, line)
+            } else {
+                format!("{}{}", indent, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    let (mut longest, mut run) = (0, 0);
+    for ch in text.chars() {
+        run = if ch == c { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    longest
+}
+
+/// Render a file as Markdown. Notebook Markdown cells are kept as written;
+/// everything else is rendered from `tree::read_tree`.
+pub fn file_to_markdown(path: &str, options: &ExtractOptions) -> Result<String, BlitzParseError> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase());
+    if extension.as_deref() != Some("ipynb") {
+        let tree = crate::tree::read_tree(path, options)?;
+        return Ok(render(&tree.blocks, &tree.footnotes));
+    }
+
+    options
+        .started()
+        .check_input(std::fs::metadata(path)?.len())
+        .map_err(|e| e.with_path(path))?;
+    let notebook = parsers::notebook::read_ipynb(path).map_err(|e| e.with_path(path))?;
+    let language = notebook.metadata.language.as_deref();
+    let cells: Vec<String> = notebook
+        .cells
+        .iter()
+        .filter(|cell| !cell.source.trim().is_empty())
+        .map(|cell| match cell.cell_type.as_str() {
+            "markdown" => cell.source.trim().to_string(),
+            "code" => code_block(language, &cell.source),
+            _ => code_block(None, &cell.source),
+        })
+        .collect();
+    Ok(cells.join("\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, bold: bool, italic: bool) -> Span {
+        Span {
+            text: text.to_string(),
+            bold,
+            italic,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_renders_blocks_as_gfm() {
+        let blocks = vec![
+            BlockKind::Heading {
+                level: 2,
+                spans: vec![Span::plain("Results")],
+            },
+            BlockKind::Paragraph {
+                spans: vec![
+                    span("Plain ", false, false),
+                    span("bold ", true, false),
+                    span("still bold", true, false),
+                    span(" and ", false, false),
+                    span("both", true, true),
+                    Span {
+                        footnote: Some("1".to_string()),
+                        ..Default::default()
+                    },
+                    span(" with a_b * c", false, false),
+                ],
+            },
+            BlockKind::ListItem {
+                depth: 0,
+                ordered: false,
+                spans: vec![Span {
+                    text: "docs".to_string(),
+                    link: Some("https://example.com/a b".to_string()),
+                    ..Default::default()
+                }],
+            },
+            BlockKind::ListItem {
+                depth: 1,
+                ordered: true,
+                spans: vec![Span {
+                    text: "x`y".to_string(),
+                    code: true,
+                    ..Default::default()
+                }],
+            },
+            BlockKind::Table {
+                rows: vec![
+                    vec![vec![Span::plain("Name")], vec![Span::plain("Value")]],
+                    vec![vec![Span::plain("a|b")]],
+                ],
+            },
+            BlockKind::Paragraph {
+                spans: vec![Span::plain("1. not a list")],
+            },
+            BlockKind::Paragraph {
+                spans: vec![Span::plain("Total\n- 3\n  # four\n2) five")],
+            },
+        ];
+        let blocks: Vec<Block> = blocks.into_iter().map(Block::from).collect();
+        let footnotes = vec![Footnote {
+            label: "1".to_string(),
+            spans: vec![Span::plain("A note.")],
+        }];
+        assert_eq!(
+            render(&blocks, &footnotes),
+            "## Results\n\n\
+             Plain **bold still bold** and ***both***[^1] with a\\_b \\* c\n\n\
+             - [docs](<https://example.com/a b>)\n   \
+             1. ``x`y``\n\n\
+             | Name | Value |\n| --- | --- |\n| a\\|b |  |\n\n\
+             1\\. not a list\n\n\
+             Total\n\\- 3\n  \\# four\n2\\) five\n\n\
+             [^1]: A note."
+        );
+    }
+}
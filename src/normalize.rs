@@ -0,0 +1,220 @@
+use pyo3::prelude::*;
+use unicode_normalization::UnicodeNormalization;
+
+/// The Unicode normalization form text is converted to.
+#[pyclass(eq, eq_int)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnicodeForm {
+    /// Canonical composition: only merges equivalent sequences such as `e` +
+    /// combining acute into `é`.
+    #[pyo3(name = "NFC")]
+    Nfc,
+    /// Compatibility composition: also folds ligatures, full-width forms,
+    /// superscripts and the like into their plain equivalents.
+    #[pyo3(name = "NFKC")]
+    Nfkc,
+}
+
+/// Steps applied to extracted text before it is chunked. Each can be turned
+/// off; the default runs all of them with NFC.
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct NormalizeOptions {
+    #[pyo3(get, set)]
+    pub form: Option<UnicodeForm>,
+    /// Replace presentation-form ligatures such as `ﬁ` with their letters.
+    #[pyo3(get, set)]
+    pub expand_ligatures: bool,
+    /// Join words split by a hyphen at the end of a line.
+    #[pyo3(get, set)]
+    pub dehyphenate: bool,
+    /// Turn non-breaking and other Unicode spaces into plain ones, collapse
+    /// runs of spaces, trim lines and allow at most one blank line in a row.
+    #[pyo3(get, set)]
+    pub collapse_whitespace: bool,
+    /// Remove control characters other than newline and tab, along with soft
+    /// hyphens, zero-width spaces and byte order marks.
+    #[pyo3(get, set)]
+    pub strip_control: bool,
+    /// Replace curly quotes and primes with `'` and `"`.
+    #[pyo3(get, set)]
+    pub fold_quotes: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            form: Some(UnicodeForm::Nfc),
+            expand_ligatures: true,
+            dehyphenate: true,
+            collapse_whitespace: true,
+            strip_control: true,
+            fold_quotes: true,
+        }
+    }
+}
+
+#[pymethods]
+impl NormalizeOptions {
+    #[new]
+    #[pyo3(signature = (form=Some(UnicodeForm::Nfc), expand_ligatures=true, dehyphenate=true, collapse_whitespace=true, strip_control=true, fold_quotes=true))]
+    fn py_new(
+        form: Option<UnicodeForm>,
+        expand_ligatures: bool,
+        dehyphenate: bool,
+        collapse_whitespace: bool,
+        strip_control: bool,
+        fold_quotes: bool,
+    ) -> Self {
+        NormalizeOptions {
+            form,
+            expand_ligatures,
+            dehyphenate,
+            collapse_whitespace,
+            strip_control,
+            fold_quotes,
+        }
+    }
+}
+
+/// Apply the steps enabled in `options` to `text`.
+pub fn normalize(text: &str, options: &NormalizeOptions) -> String {
+    let mut text = text.replace("\r\n", "\n");
+    if options.strip_control {
+        text.retain(|c| !is_invisible(c));
+    }
+    if options.expand_ligatures {
+        text = expand_ligatures(&text);
+    }
+    match options.form {
+        Some(UnicodeForm::Nfc) => text = text.nfc().collect(),
+        Some(UnicodeForm::Nfkc) => text = text.nfkc().collect(),
+        None => {}
+    }
+    if options.fold_quotes {
+        text = text.chars().map(fold_quote).collect();
+    }
+    if options.dehyphenate {
+        text = dehyphenate(&text);
+    }
+    if options.collapse_whitespace {
+        text = collapse_whitespace(&text);
+    }
+    text
+}
+
+fn is_invisible(c: char) -> bool {
+    match c {
+        '\n' | '\t' => false,
+        // Soft hyphen, zero-width space, word joiner, BOM.
+        '\u{00ad}' | '\u{200b}' | '\u{2060}' | '\u{feff}' => true,
+        c => c.is_control(),
+    }
+}
+
+fn expand_ligatures(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            'ﬀ' => out.push_str("ff"),
+            'ﬁ' => out.push_str("fi"),
+            'ﬂ' => out.push_str("fl"),
+            'ﬃ' => out.push_str("ffi"),
+            'ﬄ' => out.push_str("ffl"),
+            'ﬅ' | 'ﬆ' => out.push_str("st"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn fold_quote(c: char) -> char {
+    match c {
+        '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}' | '\u{2032}' => '\'',
+        '\u{201c}' | '\u{201d}' | '\u{201e}' | '\u{201f}' | '\u{2033}' => '"',
+        c => c,
+    }
+}
+
+/// `exam-\nple text` becomes `example\ntext`: the rest of the word moves
+/// up to the first line. Only a hyphen after a letter and before a lowercase
+/// letter is taken as a break, so `COVID-\n19` and dashes are left alone.
+fn dehyphenate(text: &str) -> String {
+    // A line emptied by moving its only word up is dropped (`None`) rather
+    // than left as a blank line, which would read as a paragraph break.
+    let mut lines: Vec<Option<String>> = text.split('\n').map(|l| Some(l.to_string())).collect();
+    for i in 0..lines.len().saturating_sub(1) {
+        let (Some(line), Some(next)) = (&lines[i], &lines[i + 1]) else {
+            continue;
+        };
+        let line = line.trim_end();
+        let mut tail = line.chars().rev();
+        let hyphen = match (tail.next(), tail.next()) {
+            (Some(c @ ('-' | '\u{2010}')), Some(before)) if before.is_alphabetic() => c,
+            _ => continue,
+        };
+        let next = next.trim_start();
+        if !next.starts_with(char::is_lowercase) {
+            continue;
+        }
+        let (word, rest) = next.split_at(next.find(char::is_whitespace).unwrap_or(next.len()));
+        let joined = format!("{}{}", &line[..line.len() - hyphen.len_utf8()], word);
+        let rest = rest.trim_start();
+        lines[i + 1] = (!rest.is_empty()).then(|| rest.to_string());
+        lines[i] = Some(joined);
+    }
+    lines.into_iter().flatten().collect::<Vec<_>>().join("\n")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank_lines = 0;
+    for line in text.split('\n') {
+        let mut collapsed = String::with_capacity(line.len());
+        for word in line.split(|c: char| c.is_whitespace()) {
+            if word.is_empty() {
+                continue;
+            }
+            if !collapsed.is_empty() {
+                collapsed.push(' ');
+            }
+            collapsed.push_str(word);
+        }
+        if collapsed.is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+        blank_lines = 0;
+        out.push_str(&collapsed);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalizes_pdf_style_text() {
+        let text = "The e\u{301}\u{fb01}rst  \u{201c}smart\u{201d}\u{a0}quote\u{200b}s\r\n\
+                    were exam-\n  ple text, not COVID-\n19.\n\n\n\nNext\u{ad} para\u{7}graph. ";
+        assert_eq!(
+            normalize(text, &NormalizeOptions::default()),
+            "The \u{e9}first \"smart\" quotes\nwere example\ntext, not COVID-\n19.\n\nNext paragraph."
+        );
+
+        let options = NormalizeOptions {
+            form: Some(UnicodeForm::Nfkc),
+            expand_ligatures: false,
+            dehyphenate: false,
+            collapse_whitespace: false,
+            strip_control: false,
+            fold_quotes: false,
+        };
+        assert_eq!(normalize("\u{fb01}ve\u{b2} \u{ff21}", &options), "five2 A");
+        assert_eq!(dehyphenate("exam-\nple"), "example");
+    }
+}
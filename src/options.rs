@@ -0,0 +1,389 @@
+use crate::errors::{BlitzParseError, LimitKind};
+use crate::normalize::NormalizeOptions;
+use crate::parsers::archive::ArchiveLimits;
+use crate::progress::{CancellationToken, Progress, ProgressCallback};
+use pyo3::prelude::*;
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often, in XML elements, a running parse checks the deadline and
+/// cancellation, and reports progress.
+const DEADLINE_CHECK_INTERVAL: usize = 1024;
+
+/// Settings shared by the extraction entry points.
+///
+/// The limits guard against untrusted input such as zip bombs, deeply nested
+/// XML or pathological PDFs; each is off when `None`, though an archive
+/// extracted without `max_entries` or `max_total_bytes` still stops at its
+/// `ArchiveLimits`. Reaching one fails the document with
+/// `BlitzParseError::LimitExceeded`.
+#[pyclass]
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// Worker threads for batch extraction, in a pool built for each batch.
+    /// `None` uses rayon's global pool, which defaults to one thread per CPU.
+    #[pyo3(get, set)]
+    pub threads: Option<usize>,
+    /// Largest file, or in-memory buffer, that will be parsed at all.
+    #[pyo3(get, set)]
+    pub max_input_bytes: Option<u64>,
+    /// Largest decompressed size of any one ZIP member, such as a DOCX part.
+    #[pyo3(get, set)]
+    pub max_entry_bytes: Option<u64>,
+    #[pyo3(get, set)]
+    pub max_xml_depth: Option<usize>,
+    #[pyo3(get, set)]
+    pub max_xml_elements: Option<usize>,
+    /// Largest PDF page count that will be extracted.
+    #[pyo3(get, set)]
+    pub max_pages: Option<usize>,
+    /// Most entries decompressed in one extraction, counted across every
+    /// archive, part and attachment nested in it.
+    #[pyo3(get, set)]
+    pub max_entries: Option<usize>,
+    /// Most bytes decompressed in one extraction, counted like `max_entries`.
+    #[pyo3(get, set)]
+    pub max_total_bytes: Option<u64>,
+    /// Wall-clock budget for each file, starting when its extraction begins.
+    #[pyo3(get, set)]
+    pub timeout: Option<Duration>,
+    /// Absolute deadline. If unset, `started` derives it from `timeout`.
+    pub deadline: Option<Instant>,
+    /// Set from another thread to stop the extraction.
+    #[pyo3(get, set)]
+    pub cancel: Option<CancellationToken>,
+    /// Receives pages or bytes processed for PDF, DOCX and TXT, and chunks
+    /// created while building a graph.
+    pub progress: Option<ProgressCallback>,
+    /// Clean up extracted text before it is returned or chunked.
+    #[pyo3(get, set)]
+    pub normalize: Option<NormalizeOptions>,
+    /// Memory-map files of `input::MMAP_THRESHOLD` bytes or more instead of
+    /// reading them. Only for files nothing else truncates during extraction:
+    /// reading a truncated mapping raises SIGBUS and kills the process.
+    #[pyo3(get, set)]
+    pub mmap: bool,
+    /// How deeply the input is embedded in other documents, e.g. 2 for an
+    /// attachment of an attached message. Set by nested extraction so
+    /// nesting limits hold across formats.
+    pub nesting: usize,
+    /// Entries and bytes decompressed so far, shared with every archive,
+    /// part and attachment nested inside the extraction. If unset, `started`
+    /// creates one when `max_entries` or `max_total_bytes` is set, and an
+    /// archive creates one from its `ArchiveLimits`.
+    pub expansion: Option<Arc<ExpansionBudget>>,
+}
+
+#[pymethods]
+impl ExtractOptions {
+    #[new]
+    #[pyo3(signature = (threads=None, max_input_bytes=None, max_entry_bytes=None, max_xml_depth=None, max_xml_elements=None, max_pages=None, max_entries=None, max_total_bytes=None, timeout=None, cancel=None, progress=None, normalize=None, mmap=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        threads: Option<usize>,
+        max_input_bytes: Option<u64>,
+        max_entry_bytes: Option<u64>,
+        max_xml_depth: Option<usize>,
+        max_xml_elements: Option<usize>,
+        max_pages: Option<usize>,
+        max_entries: Option<usize>,
+        max_total_bytes: Option<u64>,
+        timeout: Option<Duration>,
+        cancel: Option<CancellationToken>,
+        progress: Option<Py<PyAny>>,
+        normalize: Option<NormalizeOptions>,
+        mmap: bool,
+    ) -> Self {
+        ExtractOptions {
+            threads,
+            max_input_bytes,
+            max_entry_bytes,
+            max_xml_depth,
+            max_xml_elements,
+            max_pages,
+            max_entries,
+            max_total_bytes,
+            timeout,
+            deadline: None,
+            cancel,
+            progress: progress.map(ProgressCallback::from_py),
+            normalize,
+            mmap,
+            nesting: 0,
+            expansion: None,
+        }
+    }
+}
+
+impl ExtractOptions {
+    /// Start the clock: a copy whose deadline is `timeout` from now. Options
+    /// that are already running keep their deadline and expansion budget, so
+    /// nested extraction (archive members, attachments) shares the outer
+    /// file's budget.
+    pub fn started(&self) -> ExtractOptions {
+        let mut options = self.clone();
+        if options.deadline.is_none() {
+            options.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        }
+        if options.expansion.is_none()
+            && (options.max_entries.is_some() || options.max_total_bytes.is_some())
+        {
+            options.expansion = Some(Arc::new(ExpansionBudget::new(&ArchiveLimits {
+                max_entries: options.max_entries.unwrap_or(usize::MAX),
+                max_total_bytes: options.max_total_bytes.unwrap_or(u64::MAX),
+                ..Default::default()
+            })));
+        }
+        options
+    }
+
+    /// A copy for extracting a document embedded at depth `nesting`.
+    pub(crate) fn nested(&self, nesting: usize) -> ExtractOptions {
+        ExtractOptions {
+            nesting,
+            ..self.clone()
+        }
+    }
+
+    pub fn check_input(&self, len: u64) -> Result<(), BlitzParseError> {
+        exceeds(self.max_input_bytes, len, LimitKind::InputBytes)
+    }
+
+    pub fn check_pages(&self, pages: usize) -> Result<(), BlitzParseError> {
+        exceeds(self.max_pages, pages, LimitKind::Pages)
+    }
+
+    /// Fails if the extraction was cancelled or has run past its deadline.
+    /// Parsers call this between units of work.
+    pub fn checkpoint(&self) -> Result<(), BlitzParseError> {
+        if let Some(source) = self.progress.as_ref().and_then(|p| p.take_failure()) {
+            return Err(BlitzParseError::Callback {
+                context: Default::default(),
+                source,
+            });
+        }
+        if self.cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+            return Err(BlitzParseError::Cancelled);
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                Err(BlitzParseError::limit_exceeded(LimitKind::Deadline))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn report(&self, stage: &'static str, unit: &'static str, done: u64, total: Option<u64>) {
+        if let Some(progress) = &self.progress {
+            progress.call(&Progress {
+                stage,
+                unit,
+                done,
+                total,
+            });
+        }
+    }
+
+    /// Read a decompressed archive member, failing once it passes
+    /// `max_entry_bytes` or exhausts the expansion budget. Sizes declared in
+    /// headers are not trusted.
+    pub fn read_entry(&self, reader: impl Read) -> Result<Vec<u8>, BlitzParseError> {
+        if let Some(expansion) = &self.expansion {
+            expansion.take_entry()?;
+        }
+        let remaining = self
+            .expansion
+            .as_ref()
+            .map_or(u64::MAX, |expansion| expansion.remaining());
+        let entry_max = self.max_entry_bytes.unwrap_or(u64::MAX);
+
+        // Read one byte past the limit so an overrun is detectable.
+        let mut data = Vec::new();
+        reader
+            .take(remaining.min(entry_max).saturating_add(1))
+            .read_to_end(&mut data)?;
+        exceeds(
+            self.max_entry_bytes,
+            data.len() as u64,
+            LimitKind::EntryBytes,
+        )?;
+        if let Some(expansion) = &self.expansion {
+            expansion.take_bytes(data.len() as u64)?;
+        }
+        Ok(data)
+    }
+
+    /// Apply `normalize`, if set, to text that is chunked or stored without
+    /// passing through a `ParsedDocument`.
+    pub(crate) fn normalize_text(&self, text: &mut String) {
+        if let Some(normalize) = &self.normalize {
+            *text = crate::normalize::normalize(text, normalize);
+        }
+    }
+
+    /// A counter to feed the start and end of every element of one XML document.
+    pub fn xml_budget(&self) -> XmlBudget<'_> {
+        XmlBudget {
+            options: self,
+            depth: 0,
+            elements: 0,
+        }
+    }
+}
+
+/// What one extraction may decompress in total, counted across every
+/// container nested inside it: archive members, DOCX and EPUB parts, and
+/// archives attached to emails. Shared by clones of the options it sits in.
+#[derive(Debug)]
+pub struct ExpansionBudget {
+    max_entries: usize,
+    max_bytes: u64,
+    entries: AtomicUsize,
+    bytes: AtomicU64,
+}
+
+impl ExpansionBudget {
+    pub fn new(limits: &ArchiveLimits) -> Self {
+        ExpansionBudget {
+            max_entries: limits.max_entries,
+            max_bytes: limits.max_total_bytes,
+            entries: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+        }
+    }
+
+    /// Count one more decompressed entry.
+    pub fn take_entry(&self) -> Result<(), BlitzParseError> {
+        let entries = self.entries.fetch_add(1, Ordering::Relaxed) + 1;
+        exceeds(Some(self.max_entries), entries, LimitKind::Entries)
+    }
+
+    /// Bytes that may still be decompressed.
+    pub fn remaining(&self) -> u64 {
+        self.max_bytes
+            .saturating_sub(self.bytes.load(Ordering::Relaxed))
+    }
+
+    pub fn take_bytes(&self, len: u64) -> Result<(), BlitzParseError> {
+        let bytes = self.bytes.fetch_add(len, Ordering::Relaxed) + len;
+        exceeds(Some(self.max_bytes), bytes, LimitKind::TotalBytes)
+    }
+}
+
+/// Tracks nesting depth and element count while an XML document is read.
+pub struct XmlBudget<'a> {
+    options: &'a ExtractOptions,
+    depth: usize,
+    elements: usize,
+}
+
+impl XmlBudget<'_> {
+    /// Record a start tag. Self-closing elements call `open` then `close`.
+    pub fn open(&mut self) -> Result<(), BlitzParseError> {
+        self.depth += 1;
+        self.elements += 1;
+        exceeds(self.options.max_xml_depth, self.depth, LimitKind::XmlDepth)?;
+        exceeds(
+            self.options.max_xml_elements,
+            self.elements,
+            LimitKind::XmlElements,
+        )?;
+        if self.elements.is_multiple_of(DEADLINE_CHECK_INTERVAL) {
+            self.options.checkpoint()?;
+        }
+        Ok(())
+    }
+
+    pub fn close(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+}
+
+fn exceeds<T: PartialOrd>(
+    limit: Option<T>,
+    value: T,
+    kind: LimitKind,
+) -> Result<(), BlitzParseError> {
+    match limit {
+        Some(limit) if value > limit => Err(BlitzParseError::limit_exceeded(kind)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind<T>(result: Result<T, BlitzParseError>) -> Option<LimitKind> {
+        match result {
+            Err(BlitzParseError::LimitExceeded { kind, .. }) => Some(kind),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_limits_report_their_kind() {
+        let options = ExtractOptions {
+            max_entry_bytes: Some(4),
+            max_xml_depth: Some(2),
+            max_xml_elements: Some(3),
+            timeout: Some(Duration::ZERO),
+            ..Default::default()
+        };
+
+        assert_eq!(options.read_entry(&b"1234"[..]).unwrap(), b"1234");
+        assert_eq!(
+            kind(options.read_entry(&b"12345"[..])),
+            Some(LimitKind::EntryBytes)
+        );
+
+        let mut budget = options.xml_budget();
+        budget.open().unwrap();
+        budget.open().unwrap();
+        assert_eq!(kind(budget.open()), Some(LimitKind::XmlDepth));
+        budget.close();
+        budget.close();
+        assert_eq!(kind(budget.open()), Some(LimitKind::XmlElements));
+
+        // The clock only runs once extraction has started.
+        assert!(options.checkpoint().is_ok());
+        assert_eq!(
+            kind(options.started().checkpoint()),
+            Some(LimitKind::Deadline)
+        );
+    }
+
+    #[test]
+    fn test_expansion_is_only_bounded_when_asked() {
+        let options = ExtractOptions::default().started();
+        assert!(options.expansion.is_none());
+        for _ in 0..3 {
+            options.read_entry(&b"1234"[..]).unwrap();
+        }
+
+        let options = ExtractOptions {
+            max_entries: Some(2),
+            ..Default::default()
+        }
+        .started();
+        options.read_entry(&b"1234"[..]).unwrap();
+        options.read_entry(&b"1234"[..]).unwrap();
+        assert_eq!(
+            kind(options.read_entry(&b"1234"[..])),
+            Some(LimitKind::Entries)
+        );
+
+        let options = ExtractOptions {
+            max_total_bytes: Some(6),
+            ..Default::default()
+        }
+        .started();
+        options.read_entry(&b"1234"[..]).unwrap();
+        assert_eq!(
+            kind(options.read_entry(&b"1234"[..])),
+            Some(LimitKind::TotalBytes)
+        );
+    }
+}
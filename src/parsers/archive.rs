@@ -0,0 +1,398 @@
+use crate::errors::{zip_error, BlitzParseError, ErrorContext, LimitKind};
+use crate::options::{ExpansionBudget, ExtractOptions};
+use crate::parsers::sniff::sniff_format;
+use flate2::read::GzDecoder;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Bounds on archive traversal, guarding against zip bombs and deeply nested containers.
+///
+/// The entry and byte limits are shared with everything the archive expands
+/// to, including archives inside documents or attachments among its members;
+/// an archive nested in another extraction draws from that extraction's
+/// `ExtractOptions::expansion` instead.
+#[derive(Debug, Clone, Copy)]
+pub struct ArchiveLimits {
+    /// How many containers deep to descend; the outermost archive is depth 1.
+    pub max_depth: usize,
+    /// Maximum number of members across all nesting levels.
+    pub max_entries: usize,
+    /// Maximum bytes decompressed across all members.
+    pub max_total_bytes: u64,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        ArchiveLimits {
+            max_depth: 4,
+            max_entries: 10_000,
+            max_total_bytes: 1 << 30,
+        }
+    }
+}
+
+/// The extraction result for one file inside an archive.
+#[derive(Debug)]
+pub struct ArchiveMember {
+    /// Path of the member, with nested containers joined by `/`.
+    pub path: String,
+    pub text: Result<String, BlitzParseError>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Container {
+    Zip,
+    Tar,
+    TarGz,
+    Gz,
+}
+
+impl Container {
+    fn from_name(name: &str) -> Option<Container> {
+        let name = name.to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Container::TarGz)
+        } else if name.ends_with(".zip") {
+            Some(Container::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Container::Tar)
+        } else if name.ends_with(".gz") {
+            Some(Container::Gz)
+        } else {
+            None
+        }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Container> {
+        match sniff_format(bytes)? {
+            "zip" => Some(Container::Zip),
+            "tar" => Some(Container::Tar),
+            "gz" => Some(Container::Gz),
+            _ => None,
+        }
+    }
+}
+
+/// Returns true if `name` looks like a container handled by this module.
+pub fn is_archive(name: &str) -> bool {
+    Container::from_name(name).is_some()
+}
+
+/// Extract every member of an archive as plain text, one section per member.
+pub fn parse_archive(path: &str) -> Result<String, BlitzParseError> {
+    let members = extract_archive(path, &ArchiveLimits::default())?;
+    Ok(join_members(&members))
+}
+
+pub fn extract_archive(
+    path: &str,
+    limits: &ArchiveLimits,
+) -> Result<Vec<ArchiveMember>, BlitzParseError> {
+    extract_archive_with(path, limits, &ExtractOptions::default())
+}
+
+/// Like `extract_archive`, extracting members under `options` as
+/// `extract_archive_bytes_with` does.
+pub fn extract_archive_with(
+    path: &str,
+    limits: &ArchiveLimits,
+    options: &ExtractOptions,
+) -> Result<Vec<ArchiveMember>, BlitzParseError> {
+    let bytes = std::fs::read(path)?;
+    let name = Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(path);
+    extract_archive_bytes_with(&bytes, name, limits, options)
+}
+
+/// Walk an in-memory archive. `name` is used to pick the container type.
+pub fn extract_archive_bytes(
+    bytes: &[u8],
+    name: &str,
+    limits: &ArchiveLimits,
+) -> Result<Vec<ArchiveMember>, BlitzParseError> {
+    extract_archive_bytes_with(bytes, name, limits, &ExtractOptions::default())
+}
+
+/// Like `extract_archive_bytes`, also bounding each member by
+/// `max_entry_bytes` and extracting members under `options`.
+pub fn extract_archive_bytes_with(
+    bytes: &[u8],
+    name: &str,
+    limits: &ArchiveLimits,
+    options: &ExtractOptions,
+) -> Result<Vec<ArchiveMember>, BlitzParseError> {
+    let container = Container::from_name(name)
+        .or_else(|| Container::from_bytes(bytes))
+        .ok_or_else(|| BlitzParseError::unsupported(name))?;
+
+    let mut options = options.clone();
+    options
+        .expansion
+        .get_or_insert_with(|| Arc::new(ExpansionBudget::new(limits)));
+    let mut walker = Walker {
+        limits: *limits,
+        options,
+        members: Vec::new(),
+    };
+    walker.visit_container(bytes, container, name, "", 1)?;
+    Ok(walker.members)
+}
+
+/// Render members as `=== path ===` headed sections, skipping failures.
+pub fn join_members(members: &[ArchiveMember]) -> String {
+    members
+        .iter()
+        .filter_map(|m| {
+            m.text
+                .as_ref()
+                .ok()
+                .map(|text| format!("=== {} ===\n{}", m.path, text.trim()))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+struct Walker {
+    limits: ArchiveLimits,
+    /// Members are extracted under these, so anything they expand to draws
+    /// from the same `expansion` budget.
+    options: ExtractOptions,
+    members: Vec<ArchiveMember>,
+}
+
+impl Walker {
+    fn visit_container(
+        &mut self,
+        bytes: &[u8],
+        container: Container,
+        name: &str,
+        prefix: &str,
+        depth: usize,
+    ) -> Result<(), BlitzParseError> {
+        match container {
+            Container::Zip => {
+                let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+                    .map_err(|e| zip_error("zip", Some(name), e))?;
+                for i in 0..archive.len() {
+                    let mut entry = archive
+                        .by_index(i)
+                        .map_err(|e| zip_error("zip", Some(name), e))?;
+                    if entry.is_dir() {
+                        continue;
+                    }
+                    let entry_name = entry.name().to_string();
+                    let data = self.read_entry(&mut entry)?;
+                    self.visit_member(&entry_name, data, prefix, depth)?;
+                }
+            }
+            Container::Tar | Container::TarGz => {
+                let reader: Box<dyn Read> = if container == Container::TarGz {
+                    Box::new(GzDecoder::new(bytes))
+                } else {
+                    Box::new(bytes)
+                };
+                let mut archive = tar::Archive::new(reader);
+                let entries = archive
+                    .entries()
+                    .map_err(|e| BlitzParseError::corrupt("tar", ErrorContext::default(), e))?;
+                for entry in entries {
+                    let mut entry = entry
+                        .map_err(|e| BlitzParseError::corrupt("tar", ErrorContext::default(), e))?;
+                    if !entry.header().entry_type().is_file() {
+                        continue;
+                    }
+                    let entry_name = entry
+                        .path()
+                        .map(|p| p.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    let data = self.read_entry(&mut entry)?;
+                    self.visit_member(&entry_name, data, prefix, depth)?;
+                }
+            }
+            Container::Gz => {
+                // A bare .gz holds a single file named after the archive.
+                let inner = name
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or(name)
+                    .trim_end_matches(".gz")
+                    .trim_end_matches(".GZ");
+                let data = self.read_entry(&mut GzDecoder::new(bytes))?;
+                self.visit_member(inner, data, prefix, depth)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_member(
+        &mut self,
+        name: &str,
+        data: Vec<u8>,
+        prefix: &str,
+        depth: usize,
+    ) -> Result<(), BlitzParseError> {
+        let path = format!("{}{}", prefix, name);
+
+        let container = Container::from_name(name).or_else(|| Container::from_bytes(&data));
+        if let Some(container) = container {
+            if depth >= self.limits.max_depth {
+                self.members.push(ArchiveMember {
+                    text: Err(BlitzParseError::LimitExceeded {
+                        kind: LimitKind::ArchiveDepth,
+                        context: ErrorContext::part(&path),
+                    }),
+                    path,
+                });
+                return Ok(());
+            }
+            let prefix = format!("{}/", path);
+            return self.visit_container(&data, container, name, &prefix, depth + 1);
+        }
+
+        let extension = Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        let text = match extension.as_deref().or_else(|| sniff_format(&data)) {
+            Some(ext) => crate::extract_text_from_bytes_with(&data, ext, &self.options),
+            None => Err(BlitzParseError::unsupported(name)),
+        };
+        self.members.push(ArchiveMember { path, text });
+        Ok(())
+    }
+
+    /// Read one member, enforcing the entry-count, total-size and entry-size limits.
+    fn read_entry(&mut self, reader: &mut impl Read) -> Result<Vec<u8>, BlitzParseError> {
+        self.options.checkpoint()?;
+        self.options.read_entry(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use zip::write::FileOptions;
+
+    fn zip_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn tar_gz_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *content).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_nested_archives_report_inner_paths() {
+        let inner = tar_gz_of(&[("notes/readme.txt", b"Inside the tarball.")]);
+        let outer = zip_of(&[
+            ("a.txt", b"Top-level text."),
+            ("bundle.tar.gz", &inner),
+            ("no_extension", b"{\\rtf1 Sniffed RTF.}"),
+            ("image.png", b"\x89PNG\r\n\x1a\n\0\0"),
+        ]);
+
+        let members =
+            extract_archive_bytes(&outer, "upload.zip", &ArchiveLimits::default()).unwrap();
+        let paths: Vec<_> = members.iter().map(|m| m.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "a.txt",
+                "bundle.tar.gz/notes/readme.txt",
+                "no_extension",
+                "image.png"
+            ]
+        );
+        assert_eq!(members[1].text.as_ref().unwrap(), "Inside the tarball.");
+        assert_eq!(members[2].text.as_ref().unwrap(), "Sniffed RTF.");
+        assert!(members[3].text.is_err());
+
+        let text = join_members(&members);
+        assert!(text.starts_with("=== a.txt ===\nTop-level text."));
+    }
+
+    #[test]
+    fn test_limits_stop_expansion() {
+        let zeros = vec![b'0'; 64 * 1024];
+        let bomb = zip_of(&[("a.txt", &zeros), ("b.txt", &zeros)]);
+        let limits = ArchiveLimits {
+            max_total_bytes: 100 * 1024,
+            ..Default::default()
+        };
+        assert!(extract_archive_bytes(&bomb, "bomb.zip", &limits).is_err());
+
+        let limits = ArchiveLimits {
+            max_entries: 1,
+            ..Default::default()
+        };
+        assert!(extract_archive_bytes(&bomb, "bomb.zip", &limits).is_err());
+
+        let nested = zip_of(&[("inner.zip", &zip_of(&[("deep.txt", b"deep")]))]);
+        let limits = ArchiveLimits {
+            max_depth: 1,
+            ..Default::default()
+        };
+        let members = extract_archive_bytes(&nested, "nested.zip", &limits).unwrap();
+        assert_eq!(members.len(), 1);
+        assert!(members[0].text.is_err());
+    }
+
+    #[test]
+    fn test_nested_containers_share_the_budget() {
+        let xml = format!(
+            "<w:document xmlns:w=\"w\"><w:body><w:p><w:r><w:t>{}</w:t></w:r></w:p></w:body></w:document>",
+            "a".repeat(200 * 1024)
+        );
+        let docx = zip_of(&[("word/document.xml", xml.as_bytes())]);
+        let outer = zip_of(&[("report.docx", &docx)]);
+
+        let members =
+            extract_archive_bytes(&outer, "upload.zip", &ArchiveLimits::default()).unwrap();
+        assert!(members[0].text.is_ok());
+
+        // The DOCX parts count against the outer archive's total.
+        let limits = ArchiveLimits {
+            max_total_bytes: 100 * 1024,
+            ..Default::default()
+        };
+        let members = extract_archive_bytes(&outer, "upload.zip", &limits).unwrap();
+        assert!(matches!(
+            members[0].text,
+            Err(BlitzParseError::LimitExceeded {
+                kind: LimitKind::TotalBytes,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_single_gzip_member() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(b"Compressed log line.").unwrap();
+        let gz = encoder.finish().unwrap();
+
+        let members =
+            extract_archive_bytes(&gz, "server.log.txt.gz", &ArchiveLimits::default()).unwrap();
+        assert_eq!(members[0].path, "server.log.txt");
+        assert_eq!(members[0].text.as_ref().unwrap(), "Compressed log line.");
+    }
+}
@@ -0,0 +1,1173 @@
+use crate::errors::BlitzParseError;
+use crate::parsers::txt::decode_text;
+use std::path::Path;
+
+/// Programming languages the code parser understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    C,
+    Cpp,
+    Java,
+    Shell,
+}
+
+/// File extensions recognised as source code; `Language::from_extension`
+/// maps each of them.
+pub const EXTENSIONS: &[&str] = &[
+    "rs", "py", "pyi", "pyw", "js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts", "go", "c", "h",
+    "cc", "cpp", "cxx", "hh", "hpp", "hxx", "java", "sh", "bash", "zsh",
+];
+
+impl Language {
+    pub fn from_extension(extension: &str) -> Option<Language> {
+        match extension.to_lowercase().as_str() {
+            "rs" => Some(Language::Rust),
+            "py" | "pyi" | "pyw" => Some(Language::Python),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Language::JavaScript),
+            "ts" | "tsx" | "mts" | "cts" => Some(Language::TypeScript),
+            "go" => Some(Language::Go),
+            "c" | "h" => Some(Language::C),
+            "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => Some(Language::Cpp),
+            "java" => Some(Language::Java),
+            "sh" | "bash" | "zsh" => Some(Language::Shell),
+            _ => None,
+        }
+    }
+
+    /// Recognise scripts by their `#!` line, including `#!/usr/bin/env python3`.
+    pub fn from_shebang(source: &str) -> Option<Language> {
+        let line = source.lines().next()?.strip_prefix("#!")?;
+        let mut words = line.split_whitespace();
+        let mut program = words.next()?.rsplit('/').next()?;
+        if program == "env" {
+            program = words.find(|w| !w.starts_with('-'))?;
+        }
+        // python3.11 -> python
+        match program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+            "python" | "pypy" => Some(Language::Python),
+            "node" | "nodejs" => Some(Language::JavaScript),
+            "deno" | "ts-node" => Some(Language::TypeScript),
+            "sh" | "bash" | "zsh" | "dash" | "ksh" => Some(Language::Shell),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Python => "python",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::Go => "go",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+            Language::Java => "java",
+            Language::Shell => "shell",
+        }
+    }
+
+    fn line_comment(&self) -> &'static str {
+        match self {
+            Language::Python | Language::Shell => "#",
+            _ => "//",
+        }
+    }
+
+    fn has_block_comments(&self) -> bool {
+        !matches!(self, Language::Python | Language::Shell)
+    }
+
+    /// Languages where a line break can end a top-level statement.
+    fn newline_ends_items(&self) -> bool {
+        matches!(
+            self,
+            Language::Go | Language::JavaScript | Language::TypeScript | Language::Shell
+        )
+    }
+
+    fn keywords(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::Rust => &[
+                ("fn", "function"),
+                ("struct", "struct"),
+                ("enum", "enum"),
+                ("union", "union"),
+                ("trait", "trait"),
+                ("impl", "impl"),
+                ("mod", "module"),
+                ("const", "const"),
+                ("static", "static"),
+                ("type", "type"),
+                ("macro_rules", "macro"),
+                ("use", "import"),
+                ("crate", "import"),
+            ],
+            Language::Python => &[
+                ("def", "function"),
+                ("class", "class"),
+                ("import", "import"),
+                ("from", "import"),
+            ],
+            Language::JavaScript | Language::TypeScript => &[
+                ("function", "function"),
+                ("class", "class"),
+                ("interface", "interface"),
+                ("type", "type"),
+                ("enum", "enum"),
+                ("namespace", "module"),
+                ("module", "module"),
+                ("const", "variable"),
+                ("let", "variable"),
+                ("var", "variable"),
+                ("import", "import"),
+            ],
+            Language::Go => &[
+                ("func", "function"),
+                ("type", "type"),
+                ("const", "const"),
+                ("var", "variable"),
+                ("import", "import"),
+                ("package", "package"),
+            ],
+            Language::C | Language::Cpp => &[
+                ("struct", "struct"),
+                ("class", "class"),
+                ("enum", "enum"),
+                ("union", "union"),
+                ("namespace", "module"),
+                ("typedef", "type"),
+                ("using", "import"),
+            ],
+            Language::Java => &[
+                ("class", "class"),
+                ("interface", "interface"),
+                ("enum", "enum"),
+                ("record", "record"),
+                ("package", "package"),
+                ("import", "import"),
+            ],
+            Language::Shell => &[("function", "function")],
+        }
+    }
+
+    /// Words that may precede the keyword of an item without changing its kind.
+    fn modifiers(&self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &["pub", "async", "unsafe", "default", "extern"],
+            Language::Python => &["async"],
+            Language::JavaScript | Language::TypeScript => &[
+                "export", "default", "async", "declare", "abstract", "readonly",
+            ],
+            Language::C | Language::Cpp => &["template", "inline", "static", "extern"],
+            Language::Java => &[
+                "public",
+                "protected",
+                "private",
+                "static",
+                "final",
+                "abstract",
+                "sealed",
+                "strictfp",
+            ],
+            Language::Go | Language::Shell => &[],
+        }
+    }
+}
+
+/// A top-level item: a function, class, impl block, import and so on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeItem {
+    /// Normalised kind, e.g. `function`, `class`, `struct`, `impl`, `import`;
+    /// `statement` when nothing more specific applies.
+    pub kind: &'static str,
+    /// The declared name, if the item declares one.
+    pub name: Option<String>,
+    /// Docstring or the comment block directly above the item.
+    pub doc: Option<String>,
+    pub code: String,
+    /// 1-based, inclusive line range of `code`.
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct CodeFile {
+    pub language: Language,
+    /// Module-level documentation: a Python module docstring or Rust `//!` comments.
+    pub doc: Option<String>,
+    pub items: Vec<CodeItem>,
+    /// Every comment in the file, markers stripped, in source order.
+    pub comments: Vec<String>,
+    pub source: String,
+}
+
+impl CodeFile {
+    /// Items that declare a name, i.e. those that become `Symbol` nodes.
+    pub fn symbols(&self) -> impl Iterator<Item = &CodeItem> {
+        self.items.iter().filter(|item| item.name.is_some())
+    }
+}
+
+pub fn parse_code(path: &str) -> Result<String, BlitzParseError> {
+    Ok(read_code(path)?.source)
+}
+
+/// Read a source file, detecting its language from the extension or, failing
+/// that, the shebang line.
+pub fn read_code(path: &str) -> Result<CodeFile, BlitzParseError> {
+    let bytes = std::fs::read(path)?;
+    let source = decode_text(&bytes, true)?.text;
+    let extension = Path::new(path).extension().and_then(|e| e.to_str());
+    let language = extension
+        .and_then(Language::from_extension)
+        .or_else(|| Language::from_shebang(&source))
+        .ok_or_else(|| BlitzParseError::unsupported(extension.unwrap_or("No extension")))?;
+    Ok(parse_source(&source, language))
+}
+
+pub fn parse_code_bytes(bytes: &[u8]) -> Result<String, BlitzParseError> {
+    Ok(decode_text(bytes, true)?.text)
+}
+
+/// Split `source` into top-level items.
+pub fn parse_source(source: &str, language: Language) -> CodeFile {
+    let scan = match language {
+        Language::Python => scan_python(source),
+        _ => scan_braced(source, language),
+    };
+    let lines = LineIndex::new(source);
+
+    let mut doc = join_doc(scan.module_doc);
+    let mut items = Vec::new();
+    for raw in scan.items {
+        let code = source[raw.start..raw.end].trim_end();
+        if code.is_empty() {
+            continue;
+        }
+        if language == Language::Python && items.is_empty() && doc.is_none() {
+            // A bare string before any other statement is the module docstring.
+            if let Some(docstring) = leading_string(code).filter(|(_, rest)| rest.is_empty()) {
+                doc = Some(docstring.0);
+                continue;
+            }
+        }
+        let (kind, name) = describe(code, language);
+        let docstring = match (language, kind) {
+            (Language::Python, "function" | "class") => python_docstring(code),
+            _ => None,
+        };
+        items.push(CodeItem {
+            kind,
+            name,
+            doc: docstring.or_else(|| join_doc(raw.doc)),
+            code: code.to_string(),
+            start_line: lines.line_of(raw.start),
+            end_line: lines.line_of(raw.start + code.len()),
+        });
+    }
+
+    CodeFile {
+        language,
+        doc,
+        items,
+        comments: scan.comments,
+        source: source.to_string(),
+    }
+}
+
+struct RawItem {
+    start: usize,
+    end: usize,
+    doc: Vec<String>,
+}
+
+#[derive(Default)]
+struct Scan {
+    items: Vec<RawItem>,
+    comments: Vec<String>,
+    module_doc: Vec<String>,
+}
+
+/// Split a brace-delimited language into items. An item ends at a `;` or a
+/// closing `}` at depth zero, or, in languages with optional semicolons, at a
+/// line break followed by an unindented line.
+fn scan_braced(source: &str, language: Language) -> Scan {
+    let b = source.as_bytes();
+    let mut scan = Scan::default();
+    let mut pending: Vec<String> = Vec::new();
+    let mut current: Option<(usize, Vec<String>)> = None;
+    let mut depth = 0usize;
+    let mut newlines = 0;
+    let mut swallow_semicolon = false;
+    let mut i = 0;
+
+    while i < b.len() {
+        let c = b[i];
+
+        if let Some(end) = comment_end(b, i, language) {
+            let raw = &source[i..end];
+            let text = clean_comment(raw);
+            if !text.is_empty() {
+                scan.comments.push(text.clone());
+                if language == Language::Rust && (raw.starts_with("//!") || raw.starts_with("/*!"))
+                {
+                    scan.module_doc.push(text);
+                } else if current.is_none() && depth == 0 {
+                    pending.push(text);
+                }
+            }
+            newlines = 0;
+            i = end;
+            continue;
+        }
+
+        if c == b'\n' {
+            match &current {
+                None => {
+                    newlines += 1;
+                    // A blank line detaches a comment block from what follows.
+                    if newlines >= 2 {
+                        pending.clear();
+                    }
+                }
+                Some((start, _)) if depth == 0 && ends_at_newline(source, *start, i, language) => {
+                    let (start, doc) = current.take().unwrap();
+                    scan.items.push(RawItem { start, end: i, doc });
+                    newlines = 1;
+                }
+                _ => {}
+            }
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        newlines = 0;
+        if std::mem::take(&mut swallow_semicolon) && c == b';' && current.is_none() {
+            i += 1;
+            continue;
+        }
+        if current.is_none() {
+            current = Some((i, std::mem::take(&mut pending)));
+        }
+
+        if let Some(end) = string_end(source, i, language) {
+            i = end;
+            continue;
+        }
+        match c {
+            b'{' | b'(' | b'[' => depth += 1,
+            b'}' | b')' | b']' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 && c == b'}' && closes_item(&source[i + 1..]) {
+                    let (start, doc) = current.take().unwrap();
+                    scan.items.push(RawItem {
+                        start,
+                        end: i + 1,
+                        doc,
+                    });
+                    swallow_semicolon = true;
+                }
+            }
+            b';' if depth == 0 && language != Language::Shell => {
+                let (start, doc) = current.take().unwrap();
+                scan.items.push(RawItem {
+                    start,
+                    end: i + 1,
+                    doc,
+                });
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    if let Some((start, doc)) = current {
+        scan.items.push(RawItem {
+            start,
+            end: b.len(),
+            doc,
+        });
+    }
+    scan
+}
+
+/// A closing brace ends an item unless the line goes on, as in
+/// `typedef struct { .. } Name;` or `import { x } from 'x'`.
+fn closes_item(rest: &str) -> bool {
+    let line = rest.split('\n').next().unwrap_or("").trim();
+    line.is_empty() || line.starts_with(';') || line.starts_with("//")
+}
+
+/// Whether a line break at `at` ends the item that began at `start`.
+fn ends_at_newline(source: &str, start: usize, at: usize, language: Language) -> bool {
+    let so_far = &source[start..at];
+    // Preprocessor directives run to the end of the line unless continued.
+    if matches!(language, Language::C | Language::Cpp) && so_far.starts_with('#') {
+        return !so_far.ends_with('\\');
+    }
+    if !language.newline_ends_items() {
+        return false;
+    }
+    // Decorators belong to the declaration on the following line.
+    let last_line = so_far.rsplit('\n').next().unwrap_or(so_far);
+    if last_line.trim_start().starts_with('@') {
+        return false;
+    }
+    match source[at + 1..].chars().next() {
+        None => true,
+        Some(c) if c.is_whitespace() => source[at + 1..]
+            .lines()
+            .next()
+            .is_some_and(|line| line.trim().is_empty()),
+        // Lines starting with an operator or closing bracket continue the statement.
+        Some(c) => !".)]}+-*/%&|?:,=".contains(c),
+    }
+}
+
+/// If a comment starts at `i`, the index just past it.
+fn comment_end(b: &[u8], i: usize, language: Language) -> Option<usize> {
+    let marker = language.line_comment().as_bytes();
+    if b[i..].starts_with(marker) {
+        // In shell, `#` only starts a comment at the beginning of a word (`$#` is not one).
+        if language == Language::Shell && i > 0 && !b[i - 1].is_ascii_whitespace() {
+            return None;
+        }
+        let end = b[i..]
+            .iter()
+            .position(|&c| c == b'\n')
+            .map_or(b.len(), |p| i + p);
+        return Some(end);
+    }
+    if language.has_block_comments() && b[i..].starts_with(b"/*") {
+        // Rust block comments nest.
+        let nested = language == Language::Rust;
+        let mut depth = 0;
+        let mut j = i;
+        while j < b.len() {
+            if b[j..].starts_with(b"/*") && (nested || depth == 0) {
+                depth += 1;
+                j += 2;
+            } else if b[j..].starts_with(b"*/") {
+                depth -= 1;
+                j += 2;
+                if depth == 0 {
+                    return Some(j);
+                }
+            } else {
+                j += 1;
+            }
+        }
+        return Some(b.len());
+    }
+    None
+}
+
+/// If a string or character literal starts at `i`, the index just past it.
+fn string_end(source: &str, i: usize, language: Language) -> Option<usize> {
+    let b = source.as_bytes();
+    let c = b[i];
+
+    if language == Language::Rust {
+        if c == b'r' || c == b'b' {
+            return rust_raw_string_end(b, i);
+        }
+        if c == b'\'' {
+            // Either a char literal or a lifetime such as `'a`.
+            if b.get(i + 1) == Some(&b'\\') {
+                return quoted_end(b, i, b'\'', true, false);
+            }
+            let ch = source[i + 1..].chars().next()?;
+            let after = i + 1 + ch.len_utf8();
+            return Some(if b.get(after) == Some(&b'\'') {
+                after + 1
+            } else {
+                i + 1
+            });
+        }
+    }
+
+    // (escapes, may span lines)
+    let rules = match (c, language) {
+        (b'"', Language::Rust | Language::Shell) => (true, true),
+        (b'"', _) => (true, false),
+        (b'\'', Language::Shell) => (false, true),
+        (b'\'', _) => (true, false),
+        (b'`', Language::Go) => (false, true),
+        (b'`', Language::JavaScript | Language::TypeScript) => (true, true),
+        _ => return None,
+    };
+    quoted_end(b, i, c, rules.0, rules.1)
+}
+
+fn quoted_end(b: &[u8], i: usize, quote: u8, escapes: bool, multiline: bool) -> Option<usize> {
+    let mut j = i + 1;
+    while j < b.len() {
+        match b[j] {
+            b'\\' if escapes => j += 2,
+            c if c == quote => return Some(j + 1),
+            // An unterminated single-line literal stops at the line break.
+            b'\n' if !multiline => return Some(j),
+            _ => j += 1,
+        }
+    }
+    Some(b.len())
+}
+
+/// Raw strings like `r#"..."#` and `br"..."`; `None` when `i` starts an identifier.
+fn rust_raw_string_end(b: &[u8], i: usize) -> Option<usize> {
+    if i > 0 && (b[i - 1].is_ascii_alphanumeric() || b[i - 1] == b'_') {
+        return None;
+    }
+    let mut j = i;
+    if b[j] == b'b' {
+        j += 1;
+        if b.get(j) == Some(&b'"') || b.get(j) == Some(&b'\'') {
+            return quoted_end(b, j, b[j], true, b[j] == b'"');
+        }
+    }
+    if b.get(j) != Some(&b'r') {
+        return None;
+    }
+    j += 1;
+    let hashes = b[j..].iter().take_while(|&&c| c == b'#').count();
+    j += hashes;
+    if b.get(j) != Some(&b'"') {
+        return None;
+    }
+    let mut closing = vec![b'"'];
+    closing.extend(std::iter::repeat_n(b'#', hashes));
+    let end = b[j + 1..]
+        .windows(closing.len())
+        .position(|w| w == closing.as_slice())
+        .map_or(b.len(), |p| j + 1 + p + closing.len());
+    Some(end)
+}
+
+/// Split Python source by indentation: every unindented statement starts a
+/// new item, and decorators stay with the definition they decorate.
+fn scan_python(source: &str) -> Scan {
+    let mut scan = Scan::default();
+    let mut pending: Vec<String> = Vec::new();
+    // (start offset, end offset of the last code line, doc, decorators only)
+    let mut current: Option<(usize, usize, Vec<String>, bool)> = None;
+
+    let mut string: Option<(u8, bool)> = None;
+    let mut depth = 0usize;
+    let mut continued = false;
+    let mut offset = 0;
+
+    for line in source.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let logical_start = string.is_none() && depth == 0 && !continued;
+        let body = line.trim_end_matches(['\n', '\r']);
+
+        // Lex the line, tracking strings, brackets and comments.
+        let b = body.as_bytes();
+        let mut comment = None;
+        let mut i = 0;
+        while i < b.len() {
+            if let Some((quote, triple)) = string {
+                if b[i] == b'\\' {
+                    i += 2;
+                    continue;
+                }
+                if b[i] == quote && (!triple || b[i..].starts_with(&[quote; 3])) {
+                    i += if triple { 3 } else { 1 };
+                    string = None;
+                    continue;
+                }
+                i += 1;
+                continue;
+            }
+            match b[i] {
+                b'#' => {
+                    comment = Some(clean_comment(&body[i..]));
+                    break;
+                }
+                q @ (b'"' | b'\'') => {
+                    let triple = b[i..].starts_with(&[q; 3]);
+                    string = Some((q, triple));
+                    i += if triple { 3 } else { 1 };
+                    continue;
+                }
+                b'(' | b'[' | b'{' => depth += 1,
+                b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            i += 1;
+        }
+        // Single-quoted strings cannot span lines without a backslash.
+        if matches!(string, Some((_, false))) && !body.ends_with('\\') {
+            string = None;
+        }
+        continued = comment.is_none() && body.ends_with('\\') && string.is_none();
+
+        if let Some(text) = comment.as_ref().filter(|t| !t.is_empty()) {
+            scan.comments.push(text.clone());
+        }
+
+        let trimmed = body.trim_start();
+        let line_end = line_start + body.len();
+        if !logical_start {
+            if let Some(item) = current.as_mut() {
+                item.1 = line_end;
+            }
+            continue;
+        }
+        if trimmed.is_empty() {
+            pending.clear();
+            continue;
+        }
+        let indented = trimmed.len() != body.len();
+        if trimmed.starts_with('#') {
+            if !indented {
+                pending.extend(comment.filter(|t| !t.is_empty()));
+            }
+            continue;
+        }
+
+        match current.as_mut() {
+            Some(item) if indented || item.3 => {
+                item.1 = line_end;
+                item.3 = item.3 && !indented && trimmed.starts_with('@');
+                pending.clear();
+            }
+            _ => {
+                if let Some((start, end, doc, _)) = current.take() {
+                    scan.items.push(RawItem { start, end, doc });
+                }
+                current = Some((
+                    line_start + (body.len() - trimmed.len()),
+                    line_end,
+                    std::mem::take(&mut pending),
+                    trimmed.starts_with('@'),
+                ));
+            }
+        }
+    }
+
+    if let Some((start, end, doc, _)) = current {
+        scan.items.push(RawItem { start, end, doc });
+    }
+    scan
+}
+
+/// Work out an item's kind and declared name from its leading tokens.
+fn describe(code: &str, language: Language) -> (&'static str, Option<String>) {
+    let tokens = tokenize(code, language);
+    let mut i = 0;
+
+    // Skip attributes (`#[derive(..)]`), decorators and annotations (`@Foo(..)`).
+    loop {
+        match tokens.get(i) {
+            Some(Token::Punct('#')) if language == Language::Rust => {
+                i += 1;
+                if tokens.get(i) == Some(&Token::Punct('!')) {
+                    i += 1;
+                }
+                i = skip_group(&tokens, i);
+            }
+            Some(Token::Punct('@')) if tokens.get(i + 1) != Some(&Token::Ident("interface")) => {
+                i += 2;
+                while tokens.get(i) == Some(&Token::Punct('.')) {
+                    i += 2;
+                }
+                i = skip_group(&tokens, i);
+            }
+            _ => break,
+        }
+    }
+
+    if matches!(language, Language::C | Language::Cpp) && tokens.get(i) == Some(&Token::Punct('#'))
+    {
+        return match tokens.get(i + 1) {
+            Some(Token::Ident("include" | "import")) => ("import", None),
+            Some(Token::Ident("define")) => ("macro", ident_at(&tokens, i + 2)),
+            _ => ("directive", None),
+        };
+    }
+
+    // Skip modifiers, including `pub(crate)`, `extern "C"` and `template<..>`.
+    while let Some(Token::Ident(word)) = tokens.get(i) {
+        // Rust's `const fn` is a function, not a constant.
+        let const_fn = language == Language::Rust
+            && *word == "const"
+            && matches!(
+                tokens.get(i + 1),
+                Some(Token::Ident("fn" | "unsafe" | "async" | "extern"))
+            );
+        if !language.modifiers().contains(word) && !const_fn {
+            break;
+        }
+        i += 1;
+        if matches!(tokens.get(i), Some(Token::Punct('(' | '<'))) {
+            i = skip_group(&tokens, i);
+        }
+        while tokens.get(i) == Some(&Token::Str) {
+            i += 1;
+        }
+    }
+    if tokens.get(i) == Some(&Token::Punct('@')) {
+        i += 1; // Java's `@interface`
+    }
+
+    let keyword = match tokens.get(i) {
+        Some(Token::Ident(word)) => language.keywords().iter().find(|(k, _)| k == word).copied(),
+        _ => None,
+    };
+
+    match keyword {
+        Some((_, "import")) => ("import", None),
+        Some(("impl", kind)) => (kind, impl_name(code)),
+        Some(("func", _)) => {
+            // Go methods have a receiver before the name.
+            if tokens.get(i + 1) == Some(&Token::Punct('(')) {
+                ("method", ident_at(&tokens, skip_group(&tokens, i + 1)))
+            } else {
+                ("function", ident_at(&tokens, i + 1))
+            }
+        }
+        Some((_, "package")) => ("package", dotted_name_at(&tokens, i + 1)),
+        Some(("macro_rules", kind)) => (kind, ident_at(&tokens, i + 2)),
+        Some(("typedef", kind)) => {
+            // The declared name is the last identifier before the semicolon.
+            let name = tokens[i..].iter().rev().find_map(|t| match t {
+                Token::Ident(word) => Some(word.to_string()),
+                _ => None,
+            });
+            (kind, name)
+        }
+        Some((_, kind)) => {
+            let mut j = i + 1;
+            // `enum class` in C++, `function*` generators in JavaScript.
+            while matches!(
+                tokens.get(j),
+                Some(Token::Ident("class" | "struct" | "enum"))
+            ) || tokens.get(j) == Some(&Token::Punct('*'))
+            {
+                j += 1;
+            }
+            (kind, ident_at(&tokens, j))
+        }
+        None => fallback(&tokens[i..], language),
+    }
+}
+
+/// C-style function definitions and shell functions: the identifier before the first `(`.
+fn fallback(tokens: &[Token], language: Language) -> (&'static str, Option<String>) {
+    if language == Language::Python {
+        // Top-level assignments, with or without an annotation.
+        return match (tokens.first(), tokens.get(1)) {
+            (Some(Token::Ident(name)), Some(Token::Punct('=' | ':'))) => {
+                ("variable", Some(name.to_string()))
+            }
+            _ => ("statement", None),
+        };
+    }
+    if !matches!(language, Language::C | Language::Cpp | Language::Shell) {
+        return ("statement", None);
+    }
+    let Some(paren) = tokens.iter().position(|t| *t == Token::Punct('(')) else {
+        return ("statement", None);
+    };
+    let Some(Token::Ident(name)) = paren.checked_sub(1).and_then(|p| tokens.get(p)) else {
+        return ("statement", None);
+    };
+
+    // Keep C++ qualifiers: `Widget::draw`.
+    let mut name = name.to_string();
+    let mut p = paren - 1;
+    while p >= 3 && tokens[p - 1] == Token::Punct(':') && tokens[p - 2] == Token::Punct(':') {
+        match &tokens[p - 3] {
+            Token::Ident(scope) => name = format!("{}::{}", scope, name),
+            _ => break,
+        }
+        p -= 3;
+    }
+    let defines = tokens[paren..].contains(&Token::Punct('{'));
+    (if defines { "function" } else { "declaration" }, Some(name))
+}
+
+/// `impl<T> Display for Wrapper<T> where ...` -> `Display for Wrapper<T>`.
+fn impl_name(code: &str) -> Option<String> {
+    let start = code.find("impl")? + 4;
+    let mut rest = code[start..].trim_start();
+    if rest.starts_with('<') {
+        let mut depth = 0;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                rest = &rest[i + 1..];
+                break;
+            }
+        }
+    }
+    let end = rest.find('{').unwrap_or(rest.len());
+    let header = &rest[..end];
+    let header = header.split(" where").next().unwrap_or(header);
+    let name = header.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!name.is_empty()).then_some(name)
+}
+
+/// A possibly dotted name such as Java's `com.example.app`.
+fn dotted_name_at(tokens: &[Token], mut i: usize) -> Option<String> {
+    let mut name = ident_at(tokens, i)?;
+    while tokens.get(i + 1) == Some(&Token::Punct('.')) {
+        let Some(part) = ident_at(tokens, i + 2) else {
+            break;
+        };
+        name = format!("{}.{}", name, part);
+        i += 2;
+    }
+    Some(name)
+}
+
+fn ident_at(tokens: &[Token], i: usize) -> Option<String> {
+    match tokens.get(i) {
+        Some(Token::Ident(word)) => Some(word.to_string()),
+        _ => None,
+    }
+}
+
+/// Skip a bracketed group starting at `i`; returns `i` unchanged if none starts there.
+fn skip_group(tokens: &[Token], i: usize) -> usize {
+    let (open, close) = match tokens.get(i) {
+        Some(Token::Punct('(')) => ('(', ')'),
+        Some(Token::Punct('[')) => ('[', ']'),
+        Some(Token::Punct('<')) => ('<', '>'),
+        _ => return i,
+    };
+    let mut depth = 0;
+    for (j, token) in tokens.iter().enumerate().skip(i) {
+        match token {
+            Token::Punct(c) if *c == open => depth += 1,
+            Token::Punct(c) if *c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return j + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Punct(char),
+    Str,
+}
+
+/// Tokens of an item's header, up to its body.
+fn tokenize(code: &str, language: Language) -> Vec<Token<'_>> {
+    let b = code.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < b.len() && tokens.len() < 256 {
+        let c = b[i];
+        if let Some(end) = comment_end(b, i, language) {
+            i = end;
+            continue;
+        }
+        if c.is_ascii_whitespace() {
+            i += 1;
+        } else if c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || c >= 0x80 {
+            let start = i;
+            while i < b.len()
+                && (b[i].is_ascii_alphanumeric() || matches!(b[i], b'_' | b'$') || b[i] >= 0x80)
+            {
+                i += 1;
+            }
+            tokens.push(Token::Ident(&code[start..i]));
+        } else if let Some(end) = string_end(code, i, language) {
+            tokens.push(Token::Str);
+            i = end;
+        } else {
+            tokens.push(Token::Punct(c as char));
+            i += 1;
+        }
+    }
+    tokens
+}
+
+/// Strip comment markers: `//`, `///`, `//!`, `#`, `/* */`, `/** */` and leading `*`.
+fn clean_comment(raw: &str) -> String {
+    if let Some(body) = raw.strip_prefix("/*") {
+        let body = body.strip_suffix("*/").unwrap_or(body);
+        let body = body.trim_start_matches(['*', '!']);
+        return body
+            .lines()
+            .map(|line| line.trim().trim_start_matches('*').trim())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string();
+    }
+    raw.trim_start_matches(['/', '#'])
+        .trim_start_matches('!')
+        .trim()
+        .to_string()
+}
+
+fn join_doc(lines: Vec<String>) -> Option<String> {
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+/// The docstring of a Python `def` or `class`: a string literal opening its body.
+fn python_docstring(code: &str) -> Option<String> {
+    // Find the `:` ending the header, outside brackets and strings.
+    let b = code.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    let mut body = None;
+    while i < b.len() {
+        match b[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            q @ (b'"' | b'\'') => {
+                i = quoted_end(b, i, q, true, false)?;
+                continue;
+            }
+            b'#' => {
+                while i < b.len() && b[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b':' if depth == 0 => {
+                body = Some(i + 1);
+                break;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    let mut rest = &code[body?..];
+    loop {
+        rest = rest.trim_start();
+        if rest.starts_with('#') {
+            rest = rest.split_once('\n').map_or("", |(_, r)| r);
+        } else {
+            break;
+        }
+    }
+    leading_string(rest).map(|(doc, _)| doc)
+}
+
+/// If `code` starts with a (non-f, non-bytes) string literal, its dedented
+/// content and the remaining code.
+fn leading_string(code: &str) -> Option<(String, &str)> {
+    let code = code.trim_start();
+    let unprefixed = code.trim_start_matches(['r', 'R', 'u', 'U']);
+    if unprefixed.len() + 1 < code.len() {
+        return None;
+    }
+    let quote = unprefixed
+        .chars()
+        .next()
+        .filter(|c| *c == '"' || *c == '\'')?;
+    let delimiter: String = if unprefixed[1..].starts_with(&format!("{0}{0}", quote)) {
+        quote.to_string().repeat(3)
+    } else {
+        quote.to_string()
+    };
+    let content_start = delimiter.len();
+    let end = unprefixed[content_start..].find(&delimiter)? + content_start;
+    let content = &unprefixed[content_start..end];
+    let doc = content
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string();
+    Some((doc, unprefixed[end + delimiter.len()..].trim()))
+}
+
+/// Maps byte offsets to 1-based line numbers.
+struct LineIndex(Vec<usize>);
+
+impl LineIndex {
+    fn new(source: &str) -> Self {
+        LineIndex(
+            std::iter::once(0)
+                .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+                .collect(),
+        )
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        self.0.partition_point(|&start| start <= offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(file: &CodeFile) -> Vec<(&str, Option<&str>)> {
+        file.items
+            .iter()
+            .map(|item| (item.kind, item.name.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_items_ignore_braces_in_strings_and_comments() {
+        let source = r####"//! Crate docs.
+use std::fmt;
+
+/// Says hello.
+/// Twice.
+pub fn greet(name: &str) -> String {
+    let s = "}{ not a brace";
+    let r = r#"also } not"#;
+    let c = '}';
+    /* nested /* } */ comment */
+    format!("{}{}", s, name)
+}
+
+#[derive(Debug)]
+pub(crate) struct Point<'a> { label: &'a str }
+
+impl<'a> fmt::Display for Point<'a> where Self: Sized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { Ok(()) }
+}
+"####;
+        let file = parse_source(source, Language::Rust);
+        assert_eq!(
+            summary(&file),
+            vec![
+                ("import", None),
+                ("function", Some("greet")),
+                ("struct", Some("Point")),
+                ("impl", Some("fmt::Display for Point<'a>")),
+            ]
+        );
+        assert_eq!(file.doc.as_deref(), Some("Crate docs."));
+        let greet = &file.items[1];
+        assert_eq!(greet.doc.as_deref(), Some("Says hello.\nTwice."));
+        assert_eq!((greet.start_line, greet.end_line), (6, 12));
+        assert!(greet.code.ends_with("format!(\"{}{}\", s, name)\n}"));
+        assert!(file
+            .comments
+            .contains(&"nested /* } */ comment".to_string()));
+    }
+
+    #[test]
+    fn test_python_items_docstrings_and_decorators() {
+        let source = r#"#!/usr/bin/env python3
+"""Module docstring."""
+import os
+
+# Cached lookup.
+@functools.lru_cache(maxsize=None)
+def lookup(key: str) -> dict[str, int]:
+    """Find a key.
+
+    Returns a dict.
+    """
+    text = """
+def not_an_item():
+"""
+    return {}
+
+# Helper comment inside is kept with the body.
+    # (indented)
+
+class Store(Base):
+    '''A store.'''
+    def get(self):
+        pass
+
+MAX_SIZE: int = 10
+"#;
+        let file = parse_source(source, Language::Python);
+        assert_eq!(file.doc.as_deref(), Some("Module docstring."));
+        assert_eq!(
+            summary(&file),
+            vec![
+                ("import", None),
+                ("function", Some("lookup")),
+                ("class", Some("Store")),
+                ("variable", Some("MAX_SIZE")),
+            ]
+        );
+        let lookup = &file.items[1];
+        assert!(lookup.code.starts_with("@functools.lru_cache"));
+        assert!(lookup.code.ends_with("return {}"));
+        assert_eq!(
+            lookup.doc.as_deref(),
+            Some("Find a key.\n\nReturns a dict.")
+        );
+        assert_eq!(file.items[2].doc.as_deref(), Some("A store."));
+        assert_eq!(Language::from_shebang(source), Some(Language::Python));
+        assert!(EXTENSIONS
+            .iter()
+            .all(|ext| Language::from_extension(ext).is_some()));
+    }
+
+    #[test]
+    fn test_go_and_typescript_without_semicolons() {
+        let go = "package main\n\nimport (\n\t\"fmt\"\n)\n\n// Server serves.\ntype Server struct {\n\tAddr string\n}\n\nfunc (s *Server) Start() error {\n\treturn nil\n}\n\nvar raw = `}`\n";
+        let file = parse_source(go, Language::Go);
+        assert_eq!(
+            summary(&file),
+            vec![
+                ("package", Some("main")),
+                ("import", None),
+                ("type", Some("Server")),
+                ("method", Some("Start")),
+                ("variable", Some("raw")),
+            ]
+        );
+        assert_eq!(file.items[2].doc.as_deref(), Some("Server serves."));
+
+        let ts = "import { x } from './x'\n\n/** Adds. */\nexport async function add(a: number, b: number) {\n  return `${a}}`\n}\n\n@Component({ selector: 'app' })\nexport default class App {}\n\nexport const answer = compute()\n  .then(v => v)\n";
+        let file = parse_source(ts, Language::TypeScript);
+        assert_eq!(
+            summary(&file),
+            vec![
+                ("import", None),
+                ("function", Some("add")),
+                ("class", Some("App")),
+                ("variable", Some("answer")),
+            ]
+        );
+        assert_eq!(file.items[1].doc.as_deref(), Some("Adds."));
+        assert!(file.items[2].code.starts_with("@Component"));
+    }
+
+    #[test]
+    fn test_c_functions_and_directives() {
+        let source = "#include <stdio.h>\n#define MAX 10\n\nstatic int add(int a, int b);\n\nint Widget::draw(const char *s) {\n  return printf(\"%s}\", s);\n}\n\ntypedef struct { int x; } Pair;\n";
+        let file = parse_source(source, Language::Cpp);
+        assert_eq!(
+            summary(&file),
+            vec![
+                ("import", None),
+                ("macro", Some("MAX")),
+                ("declaration", Some("add")),
+                ("function", Some("Widget::draw")),
+                ("type", Some("Pair")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,357 @@
+use crate::errors::BlitzParseError;
+use std::collections::HashMap;
+
+/// Delimiters tried when sniffing, in order of preference on ties.
+const CANDIDATE_DELIMITERS: &[char] = &[',', '\t', ';', '|'];
+/// Number of records inspected when sniffing a dialect.
+const SNIFF_RECORDS: usize = 50;
+
+/// How a delimited file is laid out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CsvDialect {
+    pub delimiter: char,
+    pub quote: char,
+    pub has_header: bool,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        CsvDialect {
+            delimiter: ',',
+            quote: '"',
+            has_header: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvTable {
+    pub dialect: CsvDialect,
+    /// Column names; generated as `column_1`, `column_2`, ... when the file has no header.
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl CsvTable {
+    /// Each row as `(header, value)` pairs, skipping empty values.
+    pub fn records(&self) -> Vec<Vec<(String, String)>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                self.headers
+                    .iter()
+                    .zip(row)
+                    .filter(|(_, value)| !value.trim().is_empty())
+                    .map(|(header, value)| (header.clone(), value.trim().to_string()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// One line per row. With a header each row reads `header: value; header: value`,
+    /// otherwise fields are separated by ` | `.
+    pub fn text(&self) -> String {
+        if self.dialect.has_header {
+            self.records()
+                .iter()
+                .map(|record| format_record(record))
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            self.rows
+                .iter()
+                .map(|row| row.join(" | "))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+    }
+}
+
+/// Render one record as `header: value; header: value`.
+pub fn format_record(record: &[(String, String)]) -> String {
+    record
+        .iter()
+        .map(|(header, value)| format!("{}: {}", header, value))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+pub fn parse_csv(path: &str) -> Result<String, BlitzParseError> {
+    Ok(read_csv(path)?.text())
+}
+
+pub fn read_csv(path: &str) -> Result<CsvTable, BlitzParseError> {
+    let bytes = std::fs::read(path)?;
+    parse_csv_bytes(&bytes)
+}
+
+pub fn parse_csv_bytes(bytes: &[u8]) -> Result<CsvTable, BlitzParseError> {
+    // Exports from legacy tools are often in a local codepage.
+    let text = crate::parsers::txt::decode_text(bytes, true)?.text;
+    let dialect = sniff_dialect(&text);
+    Ok(parse_with_dialect(&text, dialect))
+}
+
+/// Parse `text` with a known dialect.
+pub fn parse_with_dialect(text: &str, dialect: CsvDialect) -> CsvTable {
+    let mut rows = parse_records(text, dialect.delimiter, dialect.quote);
+    // Drop blank lines, which parse as a single empty field.
+    rows.retain(|row| !(row.len() == 1 && row[0].trim().is_empty()));
+
+    let headers = if dialect.has_header && !rows.is_empty() {
+        let header = rows.remove(0);
+        header
+            .iter()
+            .enumerate()
+            .map(|(i, h)| match h.trim() {
+                "" => format!("column_{}", i + 1),
+                h => h.to_string(),
+            })
+            .collect()
+    } else {
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        (1..=width).map(|i| format!("column_{}", i)).collect()
+    };
+
+    CsvTable {
+        dialect,
+        headers,
+        rows,
+    }
+}
+
+/// Split delimited text into records, honoring quoted fields that contain
+/// delimiters, doubled quotes and line breaks.
+pub fn parse_records(text: &str, delimiter: char, quote: char) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == quote {
+                if chars.peek() == Some(&quote) {
+                    field.push(quote);
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+            continue;
+        }
+
+        match c {
+            // A quote only opens a quoted field at the start of the field.
+            _ if c == quote && field.trim().is_empty() => {
+                field.clear();
+                in_quotes = true;
+            }
+            _ if c == delimiter => record.push(std::mem::take(&mut field)),
+            '\r' => {
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// Guess the delimiter, quote character and header presence from a sample.
+pub fn sniff_dialect(text: &str) -> CsvDialect {
+    let sample = sample_lines(text);
+    let quote = sniff_quote(sample);
+
+    // Prefer the delimiter that splits rows into the most consistent number of fields.
+    let mut best: Option<(f64, char, Vec<Vec<String>>)> = None;
+    for &delimiter in CANDIDATE_DELIMITERS {
+        let mut records = parse_records(sample, delimiter, quote);
+        records.retain(|r| !(r.len() == 1 && r[0].trim().is_empty()));
+        records.truncate(SNIFF_RECORDS);
+        let score = consistency(&records);
+        if best
+            .as_ref()
+            .is_none_or(|(best_score, _, _)| score > *best_score)
+        {
+            best = Some((score, delimiter, records));
+        }
+    }
+
+    let (_, delimiter, records) = best.unwrap_or((0.0, ',', Vec::new()));
+    CsvDialect {
+        delimiter,
+        quote,
+        has_header: sniff_header(&records),
+    }
+}
+
+/// The first lines of `text`, enough to cover `SNIFF_RECORDS` records.
+fn sample_lines(text: &str) -> &str {
+    let mut end = text.len();
+    for (count, (i, _)) in text.match_indices('\n').enumerate() {
+        if count == SNIFF_RECORDS * 2 {
+            end = i;
+            break;
+        }
+    }
+    &text[..end]
+}
+
+/// Single quotes are only used when they clearly wrap fields and double quotes never do.
+fn sniff_quote(sample: &str) -> char {
+    let wraps = |q: char| {
+        CANDIDATE_DELIMITERS
+            .iter()
+            .map(|d| {
+                sample.matches(&format!("{}{}", d, q)).count()
+                    + sample.matches(&format!("{}{}", q, d)).count()
+            })
+            .sum::<usize>()
+    };
+    if wraps('\'') > 0 && wraps('"') == 0 {
+        '\''
+    } else {
+        '"'
+    }
+}
+
+/// Scores how uniformly records split into more than one field; higher is better.
+fn consistency(records: &[Vec<String>]) -> f64 {
+    if records.is_empty() {
+        return 0.0;
+    }
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for record in records {
+        *counts.entry(record.len()).or_default() += 1;
+    }
+    let (&width, &rows) = counts
+        .iter()
+        .max_by_key(|(width, rows)| (**rows, **width))
+        .unwrap();
+    if width < 2 {
+        return 0.0;
+    }
+    // Weight by width so a delimiter that happens to appear once per line in
+    // free text does not beat the real one.
+    (rows as f64 / records.len() as f64) * (width as f64).ln()
+}
+
+/// Decide whether the first record is a header, in the spirit of Python's
+/// `csv.Sniffer.has_header`: columns whose body values share a type or length
+/// vote on whether the first row breaks that pattern.
+fn sniff_header(records: &[Vec<String>]) -> bool {
+    let Some((first, body)) = records.split_first() else {
+        return false;
+    };
+    if body.is_empty() {
+        return !first.iter().any(|f| is_numeric(f));
+    }
+
+    let mut votes = 0i32;
+    for (col, header) in first.iter().enumerate() {
+        let values: Vec<&str> = body
+            .iter()
+            .filter_map(|r| r.get(col).map(|v| v.trim()))
+            .filter(|v| !v.is_empty())
+            .collect();
+        if values.is_empty() {
+            continue;
+        }
+
+        if values.iter().all(|v| is_numeric(v)) {
+            votes += if is_numeric(header) { -1 } else { 1 };
+        } else if values
+            .iter()
+            .all(|v| v.chars().count() == values[0].chars().count())
+        {
+            let same = header.trim().chars().count() == values[0].chars().count();
+            votes += if same { -1 } else { 1 };
+        }
+    }
+
+    if votes != 0 {
+        return votes > 0;
+    }
+    // No column gave a signal: assume a header when the first row is made of
+    // distinct, non-empty, non-numeric labels.
+    let mut seen = std::collections::HashSet::new();
+    first
+        .iter()
+        .all(|f| !f.trim().is_empty() && !is_numeric(f) && seen.insert(f.trim()))
+}
+
+fn is_numeric(value: &str) -> bool {
+    let value = value.trim().trim_start_matches(['$', '€', '£']);
+    let value = value.trim_end_matches('%').replace(',', "");
+    !value.is_empty() && value.parse::<f64>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_records_with_quotes_and_multiline_fields() {
+        let text = "name,notes\r\n\"Lovelace, Ada\",\"said \"\"hi\"\"\nthen left\"\r\nTuring,\n";
+        let records = parse_records(text, ',', '"');
+        assert_eq!(
+            records,
+            vec![
+                vec!["name", "notes"],
+                vec!["Lovelace, Ada", "said \"hi\"\nthen left"],
+                vec!["Turing", ""],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sniff_tsv_with_header_and_bom() {
+        let bytes = "\u{feff}id\tname\tscore\n1\tAda\t97.5\n2\tAlan\t88\n".as_bytes();
+        let table = parse_csv_bytes(bytes).unwrap();
+        assert_eq!(table.dialect.delimiter, '\t');
+        assert!(table.dialect.has_header);
+        assert_eq!(table.headers, vec!["id", "name", "score"]);
+        assert_eq!(
+            table.text(),
+            "id: 1; name: Ada; score: 97.5\nid: 2; name: Alan; score: 88"
+        );
+    }
+
+    #[test]
+    fn test_sniff_semicolons_without_header() {
+        let text = "1;2024-01-01;12,50\n2;2024-01-02;13,75\n3;2024-01-03;9,00\n";
+        let dialect = sniff_dialect(text);
+        assert_eq!(dialect.delimiter, ';');
+        assert!(!dialect.has_header);
+
+        let table = parse_with_dialect(text, dialect);
+        assert_eq!(table.headers, vec!["column_1", "column_2", "column_3"]);
+        assert_eq!(table.rows.len(), 3);
+    }
+
+    #[test]
+    fn test_sniff_single_quoted_fields() {
+        let text = "'city','population'\n'Paris, FR','2100000'\n'Lyon, FR','513000'\n";
+        let dialect = sniff_dialect(text);
+        assert_eq!(dialect.quote, '\'');
+        assert_eq!(dialect.delimiter, ',');
+        let table = parse_with_dialect(text, dialect);
+        assert_eq!(table.rows[0], vec!["Paris, FR", "2100000"]);
+    }
+}
@@ -0,0 +1,654 @@
+use crate::errors::{BlitzParseError, ErrorContext};
+use encoding_rs::WINDOWS_1252;
+use std::io::{Read, Seek};
+use std::ops::Range;
+
+/// Signature of an OLE2 compound file.
+const CFB_SIGNATURE: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+/// `wIdent` of a Word Binary File FIB.
+const WORD_IDENT: u16 = 0xA5EC;
+/// The lowest `nFib` of a Word 97 FIB; Word 6.0 and 95 files use 101-105 and
+/// lay out the FIB and text differently.
+const MIN_NFIB: u16 = 0x00C0;
+/// Set in an FcCompressed when the piece is stored as 8-bit cp1252.
+const FC_COMPRESSED: u32 = 0x4000_0000;
+/// FibBase flags: `fEncrypted` and `fWhichTblStm`.
+const F_ENCRYPTED: u16 = 0x0100;
+const F_WHICH_TBL_STM: u16 = 0x0200;
+/// Size of a formatted disk page holding paragraph properties.
+const FKP_SIZE: usize = 512;
+/// Paragraph sprms marking the end of a table row, and of a nested one.
+const SPRM_P_F_TTP: u16 = 0x2417;
+const SPRM_P_F_INNER_TTP: u16 = 0x244C;
+/// Sprms whose variable-length operand has a size other than one byte.
+const SPRM_T_DEF_TABLE: u16 = 0xD608;
+const SPRM_P_CHG_TABS: u16 = 0xC615;
+
+pub fn parse_doc(path: &str) -> Result<String, BlitzParseError> {
+    let bytes = std::fs::read(path)?;
+    parse_doc_bytes(&bytes)
+}
+
+pub fn parse_doc_bytes(bytes: &[u8]) -> Result<String, BlitzParseError> {
+    // Word happily saves RTF under a .doc name, so sniff before assuming OLE.
+    if bytes.trim_ascii_start().starts_with(b"{\\rtf") {
+        return crate::parsers::rtf::rtf_to_text(bytes);
+    }
+    if !bytes.starts_with(CFB_SIGNATURE) {
+        return Err(BlitzParseError::corrupt(
+            "doc",
+            ErrorContext::default().at(0),
+            "Not an OLE compound file",
+        ));
+    }
+
+    parse_doc_from(std::io::Cursor::new(bytes))
+}
+
+/// Extract the main document text from a Word 97-2003 compound file.
+pub fn parse_doc_from<R: Read + Seek>(reader: R) -> Result<String, BlitzParseError> {
+    let mut compound = cfb::CompoundFile::open(reader)
+        .map_err(|e| BlitzParseError::corrupt("doc", ErrorContext::default(), e))?;
+
+    let word_document = read_stream(&mut compound, "/WordDocument")?;
+    let fib = Fib::parse(&word_document)?;
+    if fib.encrypted {
+        return Err(BlitzParseError::Encrypted {
+            format: "doc",
+            context: ErrorContext::part("WordDocument"),
+            source: None,
+        });
+    }
+
+    let table_stream = fib.table_stream();
+    let table = read_stream(&mut compound, table_stream)?;
+    let table_part = || ErrorContext::part(&table_stream[1..]);
+    let clx = slice(&table, fib.fc_clx as usize, fib.lcb_clx as usize).ok_or_else(|| {
+        BlitzParseError::corrupt(
+            "doc",
+            table_part().at(fib.fc_clx.into()),
+            "CLX lies outside the table stream",
+        )
+    })?;
+    let pieces = parse_piece_table(clx)
+        .ok_or_else(|| corrupt(table_part().at(fib.fc_clx.into()), "Malformed piece table"))?;
+
+    let raw = decode_pieces(&word_document, &pieces, fib.ccp_text)?;
+    // Paragraph properties say which cell marks end a row; a file whose
+    // properties cannot be read falls back to guessing from empty cells.
+    let row_ends = slice(
+        &table,
+        fib.fc_plcf_bte_papx as usize,
+        fib.lcb_plcf_bte_papx as usize,
+    )
+    .and_then(|plc| row_end_runs(&word_document, plc))
+    .and_then(|runs| row_end_marks(&word_document, &pieces, fib.ccp_text, &runs));
+    Ok(clean_text(&raw, row_ends.as_deref()))
+}
+
+fn read_stream<R: Read + Seek>(
+    compound: &mut cfb::CompoundFile<R>,
+    name: &str,
+) -> Result<Vec<u8>, BlitzParseError> {
+    let part = &name[1..];
+    let mut stream = compound.open_stream(name).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => BlitzParseError::MissingPart {
+            format: "doc",
+            context: ErrorContext::part(part),
+        },
+        _ => corrupt(ErrorContext::part(part), e),
+    })?;
+    let mut bytes = Vec::with_capacity(stream.len() as usize);
+    stream.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// The parts of the File Information Block needed to find the text.
+struct Fib {
+    encrypted: bool,
+    use_1table: bool,
+    ccp_text: u32,
+    fc_clx: u32,
+    lcb_clx: u32,
+    fc_plcf_bte_papx: u32,
+    lcb_plcf_bte_papx: u32,
+}
+
+impl Fib {
+    fn parse(stream: &[u8]) -> Result<Fib, BlitzParseError> {
+        let truncated = || corrupt(ErrorContext::part("WordDocument"), "Truncated FIB");
+
+        if read_u16(stream, 0).ok_or_else(truncated)? != WORD_IDENT {
+            return Err(corrupt(
+                ErrorContext::part("WordDocument").at(0),
+                "WordDocument stream has no Word FIB",
+            ));
+        }
+        let n_fib = read_u16(stream, 2).ok_or_else(truncated)?;
+        if n_fib < MIN_NFIB {
+            return Err(BlitzParseError::unsupported(format!(
+                "doc (Word 6.0/95, nFib {})",
+                n_fib
+            )));
+        }
+        let flags = read_u16(stream, 0x0A).ok_or_else(truncated)?;
+
+        // FibBase is 32 bytes, followed by the variable-length FibRgW97,
+        // FibRgLw97 and FibRgFcLcb blocks, each prefixed by its element count.
+        let mut pos = 32;
+        let csw = read_u16(stream, pos).ok_or_else(truncated)? as usize;
+        pos += 2 + csw * 2;
+        let cslw = read_u16(stream, pos).ok_or_else(truncated)? as usize;
+        let rg_lw = pos + 2;
+        pos = rg_lw + cslw * 4;
+        let rg_fc_lcb = pos + 2;
+
+        Ok(Fib {
+            encrypted: flags & F_ENCRYPTED != 0,
+            use_1table: flags & F_WHICH_TBL_STM != 0,
+            // FibRgLw97.ccpText
+            ccp_text: read_u32(stream, rg_lw + 3 * 4).ok_or_else(truncated)?,
+            // FibRgFcLcb97.fcClx / lcbClx
+            fc_clx: read_u32(stream, rg_fc_lcb + 33 * 8).ok_or_else(truncated)?,
+            lcb_clx: read_u32(stream, rg_fc_lcb + 33 * 8 + 4).ok_or_else(truncated)?,
+            // FibRgFcLcb97.fcPlcfBtePapx / lcbPlcfBtePapx
+            fc_plcf_bte_papx: read_u32(stream, rg_fc_lcb + 13 * 8).ok_or_else(truncated)?,
+            lcb_plcf_bte_papx: read_u32(stream, rg_fc_lcb + 13 * 8 + 4).ok_or_else(truncated)?,
+        })
+    }
+
+    fn table_stream(&self) -> &'static str {
+        if self.use_1table {
+            "/1Table"
+        } else {
+            "/0Table"
+        }
+    }
+}
+
+/// One entry of the piece table: a run of characters and where they live.
+struct Piece {
+    cp_start: u32,
+    cp_end: u32,
+    fc: u32,
+}
+
+impl Piece {
+    /// The WordDocument bytes of the piece's first `count` characters, their
+    /// offset, and the width of each character.
+    fn bytes<'a>(&self, stream: &'a [u8], count: usize) -> Option<(usize, &'a [u8], usize)> {
+        let (offset, width) = if self.fc & FC_COMPRESSED != 0 {
+            (((self.fc & !FC_COMPRESSED) / 2) as usize, 1)
+        } else {
+            (self.fc as usize, 2)
+        };
+        Some((offset, slice(stream, offset, count * width)?, width))
+    }
+}
+
+/// Parse the CLX: skip any Prc property blocks, then read the PlcPcd.
+/// `None` if it is malformed.
+fn parse_piece_table(clx: &[u8]) -> Option<Vec<Piece>> {
+    let mut pos = 0;
+    while clx.get(pos) == Some(&0x01) {
+        let cb = read_u16(clx, pos + 1)? as usize;
+        pos += 3 + cb;
+    }
+    if clx.get(pos) != Some(&0x02) {
+        return None;
+    }
+    let lcb = read_u32(clx, pos + 1)? as usize;
+    let plc = slice(clx, pos + 5, lcb)?;
+
+    // A PlcPcd holds n + 1 CPs followed by n 8-byte PCDs.
+    if plc.len() < 4 {
+        return None;
+    }
+    let n = (plc.len() - 4) / 12;
+    let mut pieces = Vec::with_capacity(n);
+    for i in 0..n {
+        let pcd = 4 * (n + 1) + i * 8;
+        pieces.push(Piece {
+            cp_start: read_u32(plc, i * 4)?,
+            cp_end: read_u32(plc, (i + 1) * 4)?,
+            fc: read_u32(plc, pcd + 2)?,
+        });
+    }
+    Some(pieces)
+}
+
+/// Concatenate the first `ccp_text` characters described by the piece table.
+fn decode_pieces(
+    stream: &[u8],
+    pieces: &[Piece],
+    ccp_text: u32,
+) -> Result<String, BlitzParseError> {
+    let mut text = String::with_capacity(ccp_text as usize);
+    for piece in pieces {
+        if piece.cp_start >= ccp_text {
+            break;
+        }
+        let count = piece
+            .cp_end
+            .min(ccp_text)
+            .checked_sub(piece.cp_start)
+            .ok_or_else(|| {
+                corrupt(
+                    ErrorContext::part("piece table"),
+                    "Piece ends before it starts",
+                )
+            })? as usize;
+        let (_, bytes, width) = piece.bytes(stream, count).ok_or_else(|| {
+            let context = ErrorContext::part("WordDocument").at(piece.fc.into());
+            corrupt(context, "Piece lies outside WordDocument")
+        })?;
+
+        if width == 1 {
+            let (decoded, _) = WINDOWS_1252.decode_without_bom_handling(bytes);
+            text.push_str(&decoded);
+        } else {
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                .collect();
+            text.push_str(&String::from_utf16_lossy(&units));
+        }
+    }
+    Ok(text)
+}
+
+/// The WordDocument byte ranges of paragraphs that end a table row, read
+/// from the PAPX pages listed in the PlcBtePapx. `None` if they are malformed.
+fn row_end_runs(stream: &[u8], plc: &[u8]) -> Option<Vec<Range<usize>>> {
+    // A PlcBtePapx holds n + 1 FCs followed by n 4-byte page numbers.
+    if plc.len() < 4 {
+        return None;
+    }
+    let n = (plc.len() - 4) / 8;
+    let mut runs = Vec::new();
+    for i in 0..n {
+        let pn = read_u32(plc, 4 * (n + 1) + i * 4)? & 0x3F_FFFF;
+        let page = slice(stream, pn as usize * FKP_SIZE, FKP_SIZE)?;
+        // A PapxFkp holds crun + 1 FCs, then crun 13-byte BxPaps whose first
+        // byte is half the offset of the run's PapxInFkp, or 0 for none.
+        let crun = page[FKP_SIZE - 1] as usize;
+        for run in 0..crun {
+            let start = read_u32(page, run * 4)? as usize;
+            let end = read_u32(page, (run + 1) * 4)? as usize;
+            let papx = *page.get(4 * (crun + 1) + run * 13)? as usize * 2;
+            if papx != 0 && papx_ends_row(page, papx)? {
+                runs.push(start..end);
+            }
+        }
+    }
+    runs.sort_by_key(|run| run.start);
+    Some(runs)
+}
+
+/// Whether the PapxInFkp at `offset` sets sprmPFTtp or sprmPFInnerTtp.
+fn papx_ends_row(page: &[u8], offset: usize) -> Option<bool> {
+    let (start, len) = match *page.get(offset)? as usize {
+        0 => (offset + 2, *page.get(offset + 1)? as usize * 2),
+        cb => (offset + 1, cb * 2 - 1),
+    };
+    // The grpprl follows the paragraph's 2-byte style index.
+    let grpprl = slice(page, start, len)?.get(2..)?;
+    let mut pos = 0;
+    while pos + 2 <= grpprl.len() {
+        let sprm = read_u16(grpprl, pos)?;
+        pos += 2;
+        let operand = match sprm >> 13 {
+            0 | 1 => 1,
+            2 | 4 | 5 => 2,
+            3 => 4,
+            7 => 3,
+            _ if sprm == SPRM_T_DEF_TABLE => read_u16(grpprl, pos)? as usize + 1,
+            // A tab change this long lists its deletions separately.
+            _ if sprm == SPRM_P_CHG_TABS && grpprl.get(pos) == Some(&255) => return Some(false),
+            _ => *grpprl.get(pos)? as usize + 1,
+        };
+        if matches!(sprm, SPRM_P_F_TTP | SPRM_P_F_INNER_TTP) {
+            return Some(*grpprl.get(pos)? != 0);
+        }
+        pos += operand;
+    }
+    Some(false)
+}
+
+/// For each cell mark in the first `ccp_text` characters, in order, whether
+/// its paragraph ends a table row.
+fn row_end_marks(
+    stream: &[u8],
+    pieces: &[Piece],
+    ccp_text: u32,
+    runs: &[Range<usize>],
+) -> Option<Vec<bool>> {
+    let ends_row = |offset: usize| {
+        let i = runs.partition_point(|run| run.start <= offset);
+        i > 0 && runs[i - 1].contains(&offset)
+    };
+    let mut marks = Vec::new();
+    for piece in pieces {
+        if piece.cp_start >= ccp_text {
+            break;
+        }
+        let count = piece.cp_end.min(ccp_text).checked_sub(piece.cp_start)? as usize;
+        let (offset, bytes, width) = piece.bytes(stream, count)?;
+        for (i, c) in bytes.chunks_exact(width).enumerate() {
+            if c[0] == 0x07 && c.get(1).is_none_or(|&high| high == 0) {
+                marks.push(ends_row(offset + i * width));
+            }
+        }
+    }
+    Some(marks)
+}
+
+/// Turn Word's in-band control characters into plain text.
+///
+/// Paragraph marks become newlines, field codes are dropped in favour of their
+/// results, and table cells are rendered as `| a | b |` rows like the other parsers.
+/// `row_ends` says, for each cell mark in order, whether it ends its row;
+/// without it a cell mark directly after another is taken to end the row.
+fn clean_text(raw: &str, row_ends: Option<&[bool]>) -> String {
+    let mut result = String::with_capacity(raw.len());
+    let mut line = String::new();
+    let mut cells: Vec<String> = Vec::new();
+    // One entry per open field; `true` once the separator has been seen.
+    let mut fields: Vec<bool> = Vec::new();
+    let mut marks = row_ends.map(|ends| ends.iter());
+
+    for c in raw.chars() {
+        let row_end = match (c, &mut marks) {
+            ('\u{07}', Some(marks)) => marks.next().copied().unwrap_or(false),
+            ('\u{07}', None) => line.trim().is_empty() && !cells.is_empty(),
+            _ => false,
+        };
+        match c {
+            '\u{13}' => {
+                fields.push(false);
+                continue;
+            }
+            '\u{14}' => {
+                if let Some(in_result) = fields.last_mut() {
+                    *in_result = true;
+                }
+                continue;
+            }
+            '\u{15}' => {
+                fields.pop();
+                continue;
+            }
+            _ => {}
+        }
+        // Inside a field's instructions, e.g. `HYPERLINK "..."`.
+        if fields.last() == Some(&false) {
+            continue;
+        }
+
+        match c {
+            '\r' | '\u{0B}' | '\u{0C}' => {
+                if cells.is_empty() {
+                    result.push_str(&line);
+                    result.push('\n');
+                    line.clear();
+                } else {
+                    line.push(' ');
+                }
+            }
+            '\u{07}' => {
+                if !row_end {
+                    cells.push(line.trim().to_string());
+                } else if !cells.is_empty() {
+                    result.push_str("| ");
+                    result.push_str(&cells.join(" | "));
+                    result.push_str(" |\n");
+                    cells.clear();
+                }
+                line.clear();
+            }
+            '\u{1E}' => line.push('-'),
+            '\u{1F}' | '\u{01}' | '\u{02}' | '\u{05}' | '\u{08}' => {}
+            _ => line.push(c),
+        }
+    }
+    result.push_str(&line);
+
+    // Normalize whitespace
+    result
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn corrupt(context: ErrorContext, cause: impl Into<crate::errors::Cause>) -> BlitzParseError {
+    BlitzParseError::corrupt("doc", context, cause)
+}
+
+fn slice(bytes: &[u8], offset: usize, len: usize) -> Option<&[u8]> {
+    bytes.get(offset..offset.checked_add(len)?)
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    slice(bytes, offset, 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    slice(bytes, offset, 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    /// Build a minimal Word 97 file whose text is split across a compressed
+    /// piece and a UTF-16 piece.
+    fn build_doc(compressed: &str, unicode: &str) -> Cursor<Vec<u8>> {
+        build_doc_with(compressed, unicode, 0, None)
+    }
+
+    /// Like `build_doc`, with the given FibBase flags.
+    fn build_doc_with_flags(compressed: &str, unicode: &str, flags: u16) -> Cursor<Vec<u8>> {
+        build_doc_with(compressed, unicode, flags, None)
+    }
+
+    /// Like `build_doc`, with the given FibBase flags and, with `row_ends`,
+    /// a PAPX page marking those characters of `unicode` as ending a row.
+    fn build_doc_with(
+        compressed: &str,
+        unicode: &str,
+        flags: u16,
+        row_ends: Option<&[usize]>,
+    ) -> Cursor<Vec<u8>> {
+        let text_offset = 0x800usize;
+        let ccp_text = (compressed.len() + unicode.encode_utf16().count()) as u32;
+
+        let mut word = vec![0u8; 32];
+        word[0..2].copy_from_slice(&WORD_IDENT.to_le_bytes());
+        word[2..4].copy_from_slice(&0x00C1u16.to_le_bytes());
+        word[0x0A..0x0C].copy_from_slice(&flags.to_le_bytes());
+        word.extend_from_slice(&14u16.to_le_bytes());
+        word.extend_from_slice(&[0; 28]);
+        word.extend_from_slice(&22u16.to_le_bytes());
+        let mut rg_lw = [0u8; 88];
+        rg_lw[12..16].copy_from_slice(&ccp_text.to_le_bytes());
+        word.extend_from_slice(&rg_lw);
+        word.extend_from_slice(&93u16.to_le_bytes());
+        let mut rg_fc_lcb = vec![0u8; 93 * 8];
+
+        // Table stream: a CLX with one Prc and a two-piece PlcPcd.
+        let mut plc = Vec::new();
+        let split = compressed.len() as u32;
+        for cp in [0, split, ccp_text] {
+            plc.extend_from_slice(&cp.to_le_bytes());
+        }
+        let fc_compressed = ((text_offset as u32) * 2) | FC_COMPRESSED;
+        let fc_unicode = (text_offset + compressed.len()) as u32;
+        for fc in [fc_compressed, fc_unicode] {
+            plc.extend_from_slice(&0u16.to_le_bytes());
+            plc.extend_from_slice(&fc.to_le_bytes());
+            plc.extend_from_slice(&0u16.to_le_bytes());
+        }
+        let mut table = vec![0x01, 0x02, 0x00, 0xAA, 0xBB, 0x02];
+        table.extend_from_slice(&(plc.len() as u32).to_le_bytes());
+        table.extend_from_slice(&plc);
+
+        rg_fc_lcb[33 * 8..33 * 8 + 4].copy_from_slice(&0u32.to_le_bytes());
+        rg_fc_lcb[33 * 8 + 4..33 * 8 + 8].copy_from_slice(&(table.len() as u32).to_le_bytes());
+
+        // One PAPX page, at page 2, whose runs alternate between no
+        // properties and a TTP paragraph for each row end.
+        let mut page = vec![0u8; FKP_SIZE];
+        if let Some(row_ends) = row_ends {
+            let mut fcs = vec![fc_unicode];
+            let mut ttp = Vec::new();
+            for &i in row_ends {
+                let start = fc_unicode + 2 * i as u32;
+                if fcs.last() != Some(&start) {
+                    fcs.push(start);
+                    ttp.push(false);
+                }
+                fcs.push(start + 2);
+                ttp.push(true);
+            }
+            let crun = ttp.len();
+            for (i, fc) in fcs.iter().enumerate() {
+                page[i * 4..i * 4 + 4].copy_from_slice(&fc.to_le_bytes());
+            }
+            for (i, &ttp) in ttp.iter().enumerate() {
+                page[4 * (crun + 1) + i * 13] = if ttp { 240 } else { 0 };
+            }
+            page[FKP_SIZE - 1] = crun as u8;
+            // istd 0, sprmPFInTable 1, sprmPFTtp 1, stored with cb = 0.
+            page[480..490].copy_from_slice(&[0, 4, 0, 0, 0x16, 0x24, 1, 0x17, 0x24, 1]);
+
+            let mut plc = Vec::new();
+            plc.extend_from_slice(&fc_unicode.to_le_bytes());
+            plc.extend_from_slice(&fcs.last().unwrap().to_le_bytes());
+            plc.extend_from_slice(&2u32.to_le_bytes());
+            rg_fc_lcb[13 * 8..13 * 8 + 4].copy_from_slice(&(table.len() as u32).to_le_bytes());
+            rg_fc_lcb[13 * 8 + 4..13 * 8 + 8].copy_from_slice(&(plc.len() as u32).to_le_bytes());
+            table.extend_from_slice(&plc);
+        }
+        word.extend_from_slice(&rg_fc_lcb);
+
+        word.resize(2 * FKP_SIZE, 0);
+        word.extend_from_slice(&page);
+        word.resize(text_offset, 0);
+        word.extend_from_slice(compressed.as_bytes());
+        for unit in unicode.encode_utf16() {
+            word.extend_from_slice(&unit.to_le_bytes());
+        }
+
+        let mut compound = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        compound
+            .create_stream("/WordDocument")
+            .unwrap()
+            .write_all(&word)
+            .unwrap();
+        compound
+            .create_stream("/0Table")
+            .unwrap()
+            .write_all(&table)
+            .unwrap();
+        let mut cursor = compound.into_inner();
+        cursor.set_position(0);
+        cursor
+    }
+
+    #[test]
+    fn test_doc_compressed_and_unicode_pieces() {
+        let doc = build_doc(
+            "Hello \u{13} HYPERLINK \"x\" \u{14}world\u{15}!\r",
+            "Grüße, Ελληνικά\rA1\u{07}A2\u{07}\u{07}",
+        );
+        let text = parse_doc_from(doc).unwrap();
+        assert_eq!(text, "Hello world!\nGrüße, Ελληνικά\n| A1 | A2 |");
+    }
+
+    #[test]
+    fn test_doc_table_rows_end_at_ttp_marks() {
+        // The middle cell of the first row and the last of the second are
+        // empty, which a guess from empty cells would take for row ends.
+        let unicode = "A1\u{07}\u{07}A3\u{07}\u{07}B1\u{07}B2\u{07}\u{07}\u{07}After\r";
+        let doc = build_doc_with("Before\r", unicode, 0, Some(&[7, 15]));
+        let text = parse_doc_from(doc).unwrap();
+        assert_eq!(text, "Before\n| A1 |  | A3 |\n| B1 | B2 |  |\nAfter");
+    }
+
+    #[test]
+    fn test_doc_rejects_non_word_streams() {
+        let mut compound = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        compound
+            .create_stream("/WordDocument")
+            .unwrap()
+            .write_all(&[0u8; 64])
+            .unwrap();
+        let mut cursor = compound.into_inner();
+        cursor.set_position(0);
+        assert!(parse_doc_from(cursor).is_err());
+    }
+
+    #[test]
+    fn test_doc_rejects_word_95_and_reversed_pieces() {
+        let mut word = vec![0u8; 64];
+        word[0..2].copy_from_slice(&WORD_IDENT.to_le_bytes());
+        word[2..4].copy_from_slice(&0x0068u16.to_le_bytes());
+        let Err(BlitzParseError::UnsupportedFileType { format, .. }) = Fib::parse(&word) else {
+            panic!("Word 95 FIB was accepted");
+        };
+        assert!(format.contains("Word 6.0/95"));
+
+        let pieces = [Piece {
+            cp_start: 10,
+            cp_end: 4,
+            fc: 0,
+        }];
+        assert!(decode_pieces(&[0; 64], &pieces, 20).is_err());
+    }
+
+    #[test]
+    fn test_doc_errors_name_their_part() {
+        let encrypted = build_doc_with_flags("Secret\r", "", F_ENCRYPTED);
+        assert!(matches!(
+            parse_doc_from(encrypted),
+            Err(BlitzParseError::Encrypted { format: "doc", .. })
+        ));
+
+        // The FIB points at a 1Table stream the file doesn't have.
+        let no_table = build_doc_with_flags("Text\r", "", F_WHICH_TBL_STM);
+        match parse_doc_from(no_table) {
+            Err(BlitzParseError::MissingPart { format, context }) => {
+                assert_eq!(format, "doc");
+                assert_eq!(context.part.as_deref(), Some("1Table"));
+            }
+            other => panic!("expected MissingPart, got {:?}", other),
+        }
+
+        let mut word = Vec::new();
+        let mut compound = cfb::CompoundFile::open(build_doc("Text\r", "")).unwrap();
+        compound
+            .open_stream("/WordDocument")
+            .unwrap()
+            .read_to_end(&mut word)
+            .unwrap();
+        let fc_clx = 32 + 2 + 28 + 2 + 88 + 2 + 33 * 8;
+        word[fc_clx..fc_clx + 4].copy_from_slice(&0x1000u32.to_le_bytes());
+        compound
+            .create_stream("/WordDocument")
+            .unwrap()
+            .write_all(&word)
+            .unwrap();
+        let mut cursor = compound.into_inner();
+        cursor.set_position(0);
+        match parse_doc_from(cursor) {
+            Err(BlitzParseError::CorruptContainer { context, .. }) => {
+                assert_eq!(context.part.as_deref(), Some("0Table"));
+                assert_eq!(context.offset, Some(0x1000));
+            }
+            other => panic!("expected CorruptContainer, got {:?}", other),
+        }
+    }
+}
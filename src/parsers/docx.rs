@@ -1,140 +1,902 @@
-use crate::errors::BlitzParseError;
-use quick_xml::events::Event;
+use crate::document::{ParsedDocument, Partial, Strategy, Warning, WarningKind};
+use crate::errors::{zip_error, BlitzParseError, ErrorContext};
+use crate::options::ExtractOptions;
+use crate::provenance::{bytes_to_chars, Locator, Provenance};
+use crate::tree::{
+    locate_blocks, push_span, trim_spans, Block, BlockKind, DocumentTree, Footnote, Span,
+};
+use quick_xml::events::{BytesStart, Event};
 use quick_xml::Reader;
+use std::collections::HashMap;
 use std::io::Cursor;
-use std::io::Read;
+use std::io::{Read, Seek, Write};
+
+const DOCUMENT_XML: &str = "word/document.xml";
+
+/// Bytes of document.xml parsed between progress reports.
+const PROGRESS_INTERVAL: u64 = 256 * 1024;
 
 pub fn parse_docx(path: &str) -> Result<String, BlitzParseError> {
+    crate::input::with_file_bytes(path, parse_docx_bytes)
+}
+
+/// Parse a DOCX held in memory, e.g. an email attachment.
+pub fn parse_docx_bytes(bytes: &[u8]) -> Result<String, BlitzParseError> {
+    parse_docx_bytes_with(bytes, &ExtractOptions::default())
+}
+
+/// Malformed XML in document.xml ends the text; what was read before it is
+/// returned in a `PartialSuccess` error.
+pub fn parse_docx_bytes_with(
+    bytes: &[u8],
+    options: &ExtractOptions,
+) -> Result<String, BlitzParseError> {
+    let (document, failures) = read_docx(bytes, options)?;
+    document.into_text(failures)
+}
+
+/// Parse a DOCX, recording whether the fast path or the docx-rs fallback
+/// read it, and why the fast path failed.
+///
+/// Malformed XML in document.xml doesn't trigger the fallback, which can't
+/// read it either; the text before the error is returned with a `Skipped`
+/// warning instead.
+pub fn read_docx_bytes_with(
+    bytes: &[u8],
+    options: &ExtractOptions,
+) -> Result<ParsedDocument, BlitzParseError> {
+    read_docx(bytes, options).map(|(document, failures)| document.with_failures(&failures))
+}
+
+fn read_docx(bytes: &[u8], options: &ExtractOptions) -> Result<Partial, BlitzParseError> {
     // Try the ultra-fast ZIP extraction approach first
-    match parse_docx_zip_direct(path) {
-        Ok(text) => Ok(text),
-        Err(_) => {
-            // Fallback to docx-rs if ZIP approach fails
-            parse_docx_fallback(path)
+    match zip_direct(Cursor::new(bytes), options) {
+        Ok(partial) => Ok(partial),
+        Err(e @ (BlitzParseError::LimitExceeded { .. } | BlitzParseError::Cancelled)) => Err(e),
+        // Fallback to docx-rs if ZIP approach fails
+        Err(e) => {
+            if is_encrypted_package(bytes) {
+                return Err(BlitzParseError::Encrypted {
+                    format: "docx",
+                    context: ErrorContext::default(),
+                    source: Some(Box::new(e)),
+                });
+            }
+            // docx-rs inflates every part unchecked, so hand it a copy whose
+            // parts were read within the limits.
+            let bounded = match repack(Cursor::new(bytes), options) {
+                Ok(bounded) => bounded,
+                Err(
+                    limit @ (BlitzParseError::LimitExceeded { .. } | BlitzParseError::Cancelled),
+                ) => return Err(limit),
+                Err(_) => return Err(e),
+            };
+            // If docx-rs can't read it either, the fast path's error says why.
+            let text = match fallback(&bounded) {
+                Ok(text) => text,
+                Err(_) => return Err(e),
+            };
+            let document = ParsedDocument {
+                text,
+                warnings: vec![Warning::from_error(WarningKind::Fallback, &e)],
+                strategy: Strategy::Fallback,
+                ..Default::default()
+            };
+            Ok((document, Vec::new()))
         }
     }
 }
 
-/// Ultra-fast DOCX parser using direct ZIP extraction
-fn parse_docx_zip_direct(path: &str) -> Result<String, BlitzParseError> {
-    use std::fs::File;
-    use std::io::BufReader;
+/// Password-protected DOCX files are not ZIPs but OLE2 compound files
+/// holding an `EncryptionInfo` stream and the encrypted package.
+pub(crate) fn is_encrypted_package(bytes: &[u8]) -> bool {
+    cfb::CompoundFile::open(Cursor::new(bytes)).is_ok_and(|file| file.exists("/EncryptionInfo"))
+}
 
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let mut archive = zip::ZipArchive::new(reader)
-        .map_err(|e| BlitzParseError::Docx(format!("Failed to open ZIP: {}", e)))?;
+fn zip_direct<R: Read + Seek>(
+    reader: R,
+    options: &ExtractOptions,
+) -> Result<Partial, BlitzParseError> {
+    let mut archive = zip::ZipArchive::new(reader).map_err(|e| zip_error("docx", None, e))?;
 
     // Extract document.xml directly - this is where the text lives
-    let mut document_xml = archive
-        .by_name("word/document.xml")
-        .map_err(|e| BlitzParseError::Docx(format!("No document.xml found: {}", e)))?;
+    let document_xml = archive
+        .by_name(DOCUMENT_XML)
+        .map_err(|e| zip_error("docx", Some(DOCUMENT_XML), e))?;
 
-    let mut xml_content = String::with_capacity(document_xml.size() as usize);
-    document_xml.read_to_string(&mut xml_content)?;
+    let bytes = options.read_entry(document_xml)?;
+    let xml_content = String::from_utf8(bytes).map_err(|e| BlitzParseError::Encoding {
+        encoding: "UTF-8",
+        context: ErrorContext::part(DOCUMENT_XML).at(e.utf8_error().valid_up_to() as u64),
+    })?;
 
     // Parse XML and extract text using fast string operations
-    extract_text_from_xml(&xml_content)
+    read_xml_with(&xml_content, options)
+}
+
+/// Copy the package into an uncompressed ZIP, reading every part through
+/// `ExtractOptions::read_entry` so the sizes declared in its headers are never
+/// trusted.
+fn repack<R: Read + Seek>(reader: R, options: &ExtractOptions) -> Result<Vec<u8>, BlitzParseError> {
+    let mut archive = zip::ZipArchive::new(reader).map_err(|e| zip_error("docx", None, e))?;
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let stored =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    for i in 0..archive.len() {
+        options.checkpoint()?;
+        let entry = archive
+            .by_index(i)
+            .map_err(|e| zip_error("docx", None, e))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let data = options.read_entry(entry).map_err(|e| match e {
+            BlitzParseError::LimitExceeded { kind, .. } => BlitzParseError::LimitExceeded {
+                kind,
+                context: ErrorContext::part(name.clone()),
+            },
+            e => e,
+        })?;
+        writer
+            .start_file(name.as_str(), stored)
+            .and_then(|_| writer.write_all(&data).map_err(Into::into))
+            .map_err(|e| zip_error("docx", Some(&name), e))?;
+    }
+    let bytes = writer
+        .finish()
+        .map_err(|e| zip_error("docx", None, e))?
+        .into_inner();
+    Ok(bytes)
 }
 
 /// Extract text from document.xml using simple string operations
 pub fn extract_text_from_xml(xml: &str) -> Result<String, BlitzParseError> {
-    let mut reader = Reader::from_reader(Cursor::new(xml));
-    reader.config_mut().trim_text(true);
+    extract_text_from_xml_with(xml, &ExtractOptions::default())
+}
 
-    let mut buf = Vec::new();
-    let mut result = String::new();
+/// Like `extract_text_from_xml`, enforcing the XML depth, element and deadline
+/// limits and reporting the bytes of XML parsed.
+pub fn extract_text_from_xml_with(
+    xml: &str,
+    options: &ExtractOptions,
+) -> Result<String, BlitzParseError> {
+    let (document, failures) = read_xml_with(xml, options)?;
+    document.into_text(failures)
+}
 
-    let mut in_paragraph = false;
-    let mut in_table = false;
-    let mut in_row = false;
-    let mut in_cell = false;
+/// Read document.xml into one line per paragraph or table row, each with
+/// the paragraph it came from as provenance. Malformed XML ends the read,
+/// keeping what came before it.
+fn read_xml_with(xml: &str, options: &ExtractOptions) -> Result<Partial, BlitzParseError> {
+    let mut budget = options.xml_budget();
+    let total = xml.len() as u64;
+    let mut next_report = PROGRESS_INTERVAL;
+    let mut reader = Reader::from_str(xml);
+    let mut text = TextBuilder::default();
 
     loop {
-        match reader.read_event_into(&mut buf) {
+        let event = match reader.read_event() {
+            Ok(event) => event,
+            Err(e) => {
+                // Keep what was read before the malformed XML.
+                let failure = BlitzParseError::CorruptContainer {
+                    format: "docx",
+                    context: ErrorContext::part(DOCUMENT_XML).at(reader.error_position()),
+                    source: Some(Box::new(e)),
+                };
+                return Ok((text.finish(), vec![failure]));
+            }
+        };
+        match &event {
+            Event::Start(_) => budget.open()?,
+            Event::Empty(_) => {
+                budget.open()?;
+                budget.close();
+            }
+            Event::End(_) => budget.close(),
+            Event::Eof => options.report("docx", "bytes", total, Some(total)),
+            _ => {}
+        }
+        if reader.buffer_position() >= next_report {
+            options.report("docx", "bytes", reader.buffer_position(), Some(total));
+            next_report = reader.buffer_position() + PROGRESS_INTERVAL;
+        }
+        if matches!(event, Event::Eof) {
+            return Ok((text.finish(), Vec::new()));
+        }
+        text.event(&event);
+    }
+}
+
+/// The plain text of document.xml, built one XML event at a time so that
+/// `docx_to_tree` can locate its blocks in the same pass.
+#[derive(Default)]
+struct TextBuilder {
+    lines: Lines,
+    // Text read since the last paragraph outside a table ended.
+    paragraph: String,
+    // Paragraphs started so far, and the first of the current table row.
+    paragraphs: usize,
+    row_paragraph: usize,
+
+    // Table rows are written as `| a | b |` lines, like the DOC and RTF
+    // parsers do; nested tables are flattened into the enclosing cell.
+    table_depth: usize,
+    cells: Vec<String>,
+    cell: String,
+}
+
+impl TextBuilder {
+    fn event(&mut self, event: &Event) {
+        match event {
             // --- Structural elements ---
-            Ok(Event::Start(e)) => match e.name().as_ref() {
-                b"w:p" => {
-                    in_paragraph = true;
-                }
-                b"w:tbl" => {
-                    in_table = true;
-                    result.push('\n'); // separate from body text
+            Event::Start(e) | Event::Empty(e) if e.name().as_ref() == b"w:p" => {
+                self.paragraphs += 1;
+            }
+            Event::Start(e) if e.name().as_ref() == b"w:tbl" => {
+                self.table_depth += 1;
+                if self.table_depth == 1 {
+                    // Separate from body text.
+                    self.end_paragraph();
                 }
-                b"w:tr" => {
-                    in_row = true;
-                    result.push('|'); // start markdown row
+            }
+            Event::Start(e) if e.name().as_ref() == b"w:tr" && self.table_depth == 1 => {
+                self.row_paragraph = self.paragraphs;
+            }
+
+            Event::End(e) => match (e.name().as_ref(), self.table_depth) {
+                (b"w:p", 0) => self.end_paragraph(),
+                (b"w:p" | b"w:tc" | b"w:tr", 2..) => self.cell.push(' '),
+                (b"w:p", 1) => self.cell.push(' '),
+                (b"w:tc", 1) => {
+                    let cell = self.cell.split_whitespace().collect::<Vec<_>>().join(" ");
+                    self.cells.push(cell);
+                    self.cell.clear();
                 }
-                b"w:tc" => {
-                    in_cell = true;
-                    result.push(' ');
+                (b"w:tr", 1) => {
+                    if self.cells.iter().any(|c| !c.is_empty()) {
+                        let row = format!("| {} |", self.cells.join(" | "));
+                        self.lines.push(&row, self.row_paragraph);
+                    }
+                    self.cells.clear();
                 }
+                (b"w:tbl", _) => self.table_depth = self.table_depth.saturating_sub(1),
                 _ => {}
             },
 
-            Ok(Event::End(e)) => match e.name().as_ref() {
-                b"w:p" => {
-                    if in_paragraph && !in_table {
-                        result.push('\n'); // normal paragraph newline
+            // --- Text extraction ---
+            Event::Text(e) => {
+                let text = e.unescape().unwrap_or_default();
+                // Whitespace-only text between tags is layout, not content.
+                let text = text.trim_matches(|c| matches!(c, ' ' | '\t' | '\r' | '\n'));
+                if text.is_empty() {
+                    return;
+                }
+                let out = if self.table_depth > 0 {
+                    &mut self.cell
+                } else {
+                    &mut self.paragraph
+                };
+                out.push_str(text);
+                out.push(' ');
+            }
+            _ => {}
+        }
+    }
+
+    fn end_paragraph(&mut self) {
+        self.lines
+            .push(&self.paragraph, self.paragraphs.saturating_sub(1));
+        self.paragraph.clear();
+    }
+
+    fn finish(mut self) -> ParsedDocument {
+        self.end_paragraph();
+        self.lines.into_document()
+    }
+}
+
+/// Text as trimmed, non-empty lines, with the paragraph of each, in bytes.
+#[derive(Default)]
+struct Lines {
+    text: String,
+    provenance: Vec<Provenance>,
+}
+
+impl Lines {
+    fn into_document(mut self) -> ParsedDocument {
+        bytes_to_chars(&self.text, &mut self.provenance);
+        ParsedDocument {
+            text: self.text,
+            provenance: self.provenance,
+            ..Default::default()
+        }
+    }
+
+    fn push(&mut self, text: &str, paragraph: usize) {
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if !self.text.is_empty() {
+                self.text.push('\n');
+            }
+            let start = self.text.len();
+            self.text.push_str(line);
+            self.provenance.push(Provenance {
+                start,
+                end: self.text.len(),
+                locator: Locator::Docx {
+                    part: DOCUMENT_XML.to_string(),
+                    paragraph,
+                    last_paragraph: None,
+                },
+            });
+        }
+    }
+}
+
+/// Read a DOCX into blocks. Headings and code blocks come from paragraph
+/// styles, lists from numbering, emphasis from `w:b`/`w:i` run properties,
+/// and links and footnotes from their parts.
+pub fn docx_to_tree(
+    bytes: &[u8],
+    options: &ExtractOptions,
+) -> Result<DocumentTree, BlitzParseError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| {
+        if is_encrypted_package(bytes) {
+            BlitzParseError::Encrypted {
+                format: "docx",
+                context: ErrorContext::default(),
+                source: Some(Box::new(e)),
+            }
+        } else {
+            zip_error("docx", None, e)
+        }
+    })?;
+    let Some(document) = read_part(&mut archive, DOCUMENT_XML, options)? else {
+        return Err(BlitzParseError::MissingPart {
+            format: "docx",
+            context: ErrorContext::part(DOCUMENT_XML),
+        });
+    };
+
+    let mut parts = DocxParts::default();
+    if let Some(xml) = read_part(&mut archive, "word/styles.xml", options)? {
+        parts.read_styles(&xml, options)?;
+    }
+    if let Some(xml) = read_part(&mut archive, "word/numbering.xml", options)? {
+        parts.read_numbering(&xml, options)?;
+    }
+    if let Some(xml) = read_part(&mut archive, "word/_rels/document.xml.rels", options)? {
+        parts.read_links(&xml, options)?;
+    }
+
+    let mut footnotes = Vec::new();
+    if let Some(xml) = read_part(&mut archive, "word/footnotes.xml", options)? {
+        footnotes = read_footnotes(&xml, &parts, options)?;
+    }
+
+    // The text is built alongside the blocks so each block can be given its
+    // offsets in what `extract_text` returns.
+    let mut body = BlockBuilder::new(&parts, DOCUMENT_XML);
+    let mut text = TextBuilder::default();
+    walk_xml(&document, DOCUMENT_XML, options, |event| {
+        body.event(event);
+        text.event(event);
+        Ok(())
+    })?;
+    let mut tree = DocumentTree::new(body.finish());
+    locate_blocks(&mut tree.blocks, &text.finish().provenance);
+    tree.footnotes = footnotes;
+    Ok(tree)
+}
+
+/// Read a part of the package as UTF-8, or `None` if it isn't there.
+fn read_part<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+    options: &ExtractOptions,
+) -> Result<Option<String>, BlitzParseError> {
+    let entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(zip_error("docx", Some(name), e)),
+    };
+    let bytes = options.read_entry(entry)?;
+    String::from_utf8(bytes)
+        .map(Some)
+        .map_err(|e| BlitzParseError::Encoding {
+            encoding: "UTF-8",
+            context: ErrorContext::part(name).at(e.utf8_error().valid_up_to() as u64),
+        })
+}
+
+/// Feed every event of `xml` to `handle`, within the XML limits.
+fn walk_xml(
+    xml: &str,
+    part: &str,
+    options: &ExtractOptions,
+    mut handle: impl FnMut(&Event) -> Result<(), BlitzParseError>,
+) -> Result<(), BlitzParseError> {
+    let mut budget = options.xml_budget();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| BlitzParseError::CorruptContainer {
+                format: "docx",
+                context: ErrorContext::part(part).at(reader.error_position()),
+                source: Some(Box::new(e)),
+            })?;
+        match &event {
+            Event::Start(_) => budget.open()?,
+            Event::Empty(_) => {
+                budget.open()?;
+                budget.close();
+            }
+            Event::End(_) => budget.close(),
+            Event::Eof => return Ok(()),
+            _ => {}
+        }
+        handle(&event)?;
+    }
+}
+
+fn attribute(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.as_ref() == name)
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
+
+/// Whether an on/off property such as `<w:b/>` or `<w:b w:val="0"/>` is on.
+fn toggle(e: &BytesStart) -> bool {
+    !matches!(
+        attribute(e, b"w:val").as_deref(),
+        Some("0" | "false" | "off" | "none")
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StyleKind {
+    Heading(usize),
+    Code,
+}
+
+impl StyleKind {
+    /// Classify a style by its name, or by its ID when styles.xml is missing.
+    fn from_name(name: &str) -> Option<StyleKind> {
+        let name = name.to_lowercase();
+        if name == "title" {
+            return Some(StyleKind::Heading(1));
+        }
+        if let Some(level) = name.strip_prefix("heading") {
+            return level.trim().parse().ok().map(StyleKind::Heading);
+        }
+        if name.contains("code") || name.contains("preformatted") || name.contains("macro") {
+            return Some(StyleKind::Code);
+        }
+        None
+    }
+}
+
+/// What the supporting parts of a package say about document.xml.
+#[derive(Default)]
+struct DocxParts {
+    /// Paragraph and character styles by ID.
+    styles: HashMap<String, StyleKind>,
+    /// Whether each list level is ordered, by `numId` and `ilvl`.
+    ordered: HashMap<(String, usize), bool>,
+    /// Hyperlink targets by relationship ID.
+    links: HashMap<String, String>,
+}
+
+impl DocxParts {
+    fn style(&self, id: &str) -> Option<StyleKind> {
+        self.styles
+            .get(id)
+            .copied()
+            .or_else(|| StyleKind::from_name(id))
+    }
+
+    fn read_styles(&mut self, xml: &str, options: &ExtractOptions) -> Result<(), BlitzParseError> {
+        let mut id = None;
+        walk_xml(xml, "word/styles.xml", options, |event| {
+            match event {
+                Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+                    b"w:style" => id = attribute(e, b"w:styleId"),
+                    b"w:name" => {
+                        let kind = attribute(e, b"w:val").and_then(|n| StyleKind::from_name(&n));
+                        if let (Some(id), Some(kind)) = (&id, kind) {
+                            self.styles.insert(id.clone(), kind);
+                        }
+                    }
+                    b"w:outlineLvl" => {
+                        let level = attribute(e, b"w:val").and_then(|v| v.parse::<usize>().ok());
+                        if let (Some(id), Some(level @ 0..=8)) = (&id, level) {
+                            self.styles
+                                .entry(id.clone())
+                                .or_insert(StyleKind::Heading(level + 1));
+                        }
                     }
-                    in_paragraph = false;
+                    _ => {}
+                },
+                Event::End(e) if e.name().as_ref() == b"w:style" => id = None,
+                _ => {}
+            }
+            Ok(())
+        })
+    }
+
+    fn read_numbering(
+        &mut self,
+        xml: &str,
+        options: &ExtractOptions,
+    ) -> Result<(), BlitzParseError> {
+        // abstractNumId -> ilvl -> ordered, and numId -> abstractNumId.
+        let mut formats: HashMap<String, HashMap<usize, bool>> = HashMap::new();
+        let mut instances: Vec<(String, String)> = Vec::new();
+        let (mut abstract_id, mut level, mut num_id) = (None, 0, None);
+        walk_xml(xml, "word/numbering.xml", options, |event| {
+            if let Event::Start(e) | Event::Empty(e) = event {
+                match e.name().as_ref() {
+                    b"w:abstractNum" => abstract_id = attribute(e, b"w:abstractNumId"),
+                    b"w:lvl" => {
+                        level = attribute(e, b"w:ilvl")
+                            .and_then(|v| v.parse().ok())
+                            .unwrap_or(0)
+                    }
+                    b"w:numFmt" => {
+                        if let Some(id) = &abstract_id {
+                            let format = attribute(e, b"w:val").unwrap_or_default();
+                            formats
+                                .entry(id.clone())
+                                .or_default()
+                                .insert(level, !matches!(format.as_str(), "bullet" | "none"));
+                        }
+                    }
+                    b"w:num" => num_id = attribute(e, b"w:numId"),
+                    b"w:abstractNumId" => {
+                        if let (Some(num), Some(id)) = (&num_id, attribute(e, b"w:val")) {
+                            instances.push((num.clone(), id));
+                        }
+                    }
+                    _ => {}
                 }
-                b"w:tbl" => {
-                    in_table = false;
-                    result.push('\n'); // table ends
+            }
+            Ok(())
+        })?;
+        for (num, id) in instances {
+            for (level, ordered) in formats.get(&id).into_iter().flatten() {
+                self.ordered.insert((num.clone(), *level), *ordered);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_links(&mut self, xml: &str, options: &ExtractOptions) -> Result<(), BlitzParseError> {
+        walk_xml(xml, "word/_rels/document.xml.rels", options, |event| {
+            if let Event::Start(e) | Event::Empty(e) = event {
+                let is_link = attribute(e, b"Type").is_some_and(|t| t.ends_with("/hyperlink"));
+                if let (true, Some(id), Some(target)) =
+                    (is_link, attribute(e, b"Id"), attribute(e, b"Target"))
+                {
+                    self.links.insert(id, target);
                 }
-                b"w:tr" => {
-                    if in_row {
-                        result.push_str("|\n"); // close markdown row
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Footnotes with their content flattened to one paragraph each. Separator
+/// and continuation notes (IDs below 1) are skipped.
+fn read_footnotes(
+    xml: &str,
+    parts: &DocxParts,
+    options: &ExtractOptions,
+) -> Result<Vec<Footnote>, BlitzParseError> {
+    let mut footnotes = Vec::new();
+    let mut current: Option<(String, BlockBuilder)> = None;
+    walk_xml(xml, "word/footnotes.xml", options, |event| {
+        match event {
+            Event::Start(e) if e.name().as_ref() == b"w:footnote" => {
+                current = attribute(e, b"w:id")
+                    .filter(|id| id.parse::<i64>().is_ok_and(|id| id > 0))
+                    .map(|id| (id, BlockBuilder::new(parts, "word/footnotes.xml")));
+            }
+            Event::End(e) if e.name().as_ref() == b"w:footnote" => {
+                if let Some((id, builder)) = current.take() {
+                    let mut spans = Vec::new();
+                    for block in builder.finish() {
+                        let content = match block.kind {
+                            BlockKind::Heading { spans, .. }
+                            | BlockKind::Paragraph { spans }
+                            | BlockKind::ListItem { spans, .. } => spans,
+                            BlockKind::Code { text, .. } => vec![Span::plain(text)],
+                            BlockKind::Table { .. } => continue,
+                        };
+                        if !spans.is_empty() {
+                            push_span(&mut spans, Span::plain(" "));
+                        }
+                        spans.extend(content);
                     }
-                    in_row = false;
+                    footnotes.push(Footnote { label: id, spans });
                 }
-                b"w:tc" => {
-                    if in_cell {
-                        result.push(' ');
-                        result.push('|'); // close cell
-                    }
-                    in_cell = false;
+            }
+            _ => {
+                if let Some((_, builder)) = &mut current {
+                    builder.event(event);
                 }
-                _ => {}
-            },
+            }
+        }
+        Ok(())
+    })?;
+    Ok(footnotes)
+}
 
-            // --- Text extraction ---
-            Ok(Event::Text(e)) => {
+/// Builds blocks from the events of a WordprocessingML body.
+struct BlockBuilder<'a> {
+    parts: &'a DocxParts,
+    part: &'static str,
+    blocks: Vec<Block>,
+    spans: Vec<Span>,
+    /// Paragraphs started so far, for locating blocks.
+    paragraphs: usize,
+    style_id: Option<String>,
+    style: Option<StyleKind>,
+    /// `numId` and `ilvl` of a list paragraph.
+    numbering: (Option<String>, usize),
+    in_run: bool,
+    in_text: bool,
+    run: Span,
+    link: Option<String>,
+    /// Nesting of tables; only the outermost becomes a table block.
+    table_depth: usize,
+    /// Index of the first paragraph of the outermost table.
+    table_start: usize,
+    rows: Vec<Vec<Vec<Span>>>,
+}
+
+impl<'a> BlockBuilder<'a> {
+    fn new(parts: &'a DocxParts, part: &'static str) -> Self {
+        BlockBuilder {
+            parts,
+            part,
+            blocks: Vec::new(),
+            spans: Vec::new(),
+            paragraphs: 0,
+            style_id: None,
+            style: None,
+            numbering: (None, 0),
+            in_run: false,
+            in_text: false,
+            run: Span::default(),
+            link: None,
+            table_depth: 0,
+            table_start: 0,
+            rows: Vec::new(),
+        }
+    }
+
+    fn block(&self, kind: BlockKind, paragraph: usize, style: Option<String>) -> Block {
+        Block {
+            style,
+            source: Some(Locator::Docx {
+                part: self.part.to_string(),
+                paragraph,
+                last_paragraph: None,
+            }),
+            ..Block::from(kind)
+        }
+    }
+
+    fn event(&mut self, event: &Event) {
+        match event {
+            Event::Start(e) => self.start(e),
+            Event::Empty(e) => {
+                self.start(e);
+                self.end(e.name().as_ref());
+            }
+            Event::End(e) => self.end(e.name().as_ref()),
+            Event::Text(e) if self.in_text => {
                 let text = e.unescape().unwrap_or_default();
-                result.push_str(&text);
-                if in_table && in_cell {
-                    result.push(' '); // spacing inside cell
-                } else {
-                    result.push(' ');
-                }
+                self.push_text(&text);
             }
+            _ => {}
+        }
+    }
 
-            Ok(Event::Eof) => break,
+    fn start(&mut self, e: &BytesStart) {
+        match e.name().as_ref() {
+            b"w:p" => {
+                self.paragraphs += 1;
+                self.style_id = None;
+                self.style = None;
+                self.numbering = (None, 0);
+            }
+            b"w:pStyle" => {
+                self.style_id = attribute(e, b"w:val");
+                self.style = self.style_id.as_deref().and_then(|id| self.parts.style(id));
+            }
+            b"w:ilvl" => {
+                self.numbering.1 = attribute(e, b"w:val")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0);
+            }
+            // numId 0 removes numbering inherited from the style.
+            b"w:numId" => self.numbering.0 = attribute(e, b"w:val").filter(|id| id != "0"),
+            b"w:r" => {
+                self.in_run = true;
+                self.run = Span {
+                    link: self.link.clone(),
+                    ..Default::default()
+                };
+            }
+            b"w:b" if self.in_run => self.run.bold = toggle(e),
+            b"w:i" if self.in_run => self.run.italic = toggle(e),
+            b"w:rStyle" if self.in_run => {
+                let style = attribute(e, b"w:val").and_then(|id| self.parts.style(&id));
+                self.run.code |= style == Some(StyleKind::Code);
+            }
+            b"w:rFonts" if self.in_run => {
+                let font = attribute(e, b"w:ascii").unwrap_or_default().to_lowercase();
+                self.run.code |= MONOSPACE_FONTS.iter().any(|f| font.contains(f));
+            }
+            b"w:t" => self.in_text = self.in_run,
+            b"w:tab" if self.in_run => self.push_text("\t"),
+            b"w:br" | b"w:cr" if self.in_run => self.push_text("\n"),
+            b"w:hyperlink" => {
+                self.link = attribute(e, b"r:id")
+                    .and_then(|id| self.parts.links.get(&id).cloned())
+                    .or_else(|| attribute(e, b"w:anchor").map(|a| format!("#{}", a)));
+            }
+            b"w:footnoteReference" => {
+                if let Some(id) = attribute(e, b"w:id") {
+                    self.spans.push(Span {
+                        footnote: Some(id),
+                        ..Default::default()
+                    });
+                }
+            }
+            b"w:tbl" => {
+                self.table_depth += 1;
+                if self.table_depth == 1 {
+                    self.table_start = self.paragraphs;
+                }
+            }
+            b"w:tr" if self.table_depth == 1 => self.rows.push(Vec::new()),
+            b"w:tc" if self.table_depth == 1 => {
+                if let Some(row) = self.rows.last_mut() {
+                    row.push(Vec::new());
+                }
+            }
+            _ => {}
+        }
+    }
 
-            Err(e) => return Err(BlitzParseError::Docx(format!("XML error: {e}"))),
+    fn end(&mut self, name: &[u8]) {
+        match name {
+            b"w:p" => self.end_paragraph(),
+            b"w:r" => self.in_run = false,
+            b"w:t" => self.in_text = false,
+            b"w:hyperlink" => self.link = None,
+            b"w:tbl" => {
+                self.table_depth = self.table_depth.saturating_sub(1);
+                if self.table_depth == 0 {
+                    let rows: Vec<_> = std::mem::take(&mut self.rows)
+                        .into_iter()
+                        .filter(|row| !row.is_empty())
+                        .collect();
+                    if !rows.is_empty() {
+                        let mut table =
+                            self.block(BlockKind::Table { rows }, self.table_start, None);
+                        extend_source(&mut table, self.paragraphs.saturating_sub(1));
+                        self.blocks.push(table);
+                    }
+                }
+            }
             _ => {}
         }
+    }
 
-        buf.clear();
+    fn push_text(&mut self, text: &str) {
+        let span = Span {
+            text: text.to_string(),
+            ..self.run.clone()
+        };
+        push_span(&mut self.spans, span);
     }
 
-    // Normalize whitespace
-    let result = result
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty())
-        .collect::<Vec<_>>()
-        .join("\n");
+    fn end_paragraph(&mut self) {
+        let mut spans = std::mem::take(&mut self.spans);
+        let empty = spans
+            .iter()
+            .all(|s| s.footnote.is_none() && s.text.trim().is_empty());
+        if empty {
+            return;
+        }
 
-    Ok(result)
+        if self.table_depth > 0 {
+            // Paragraphs of a cell, including those of nested tables, are
+            // lines of that cell.
+            if let Some(cell) = self.rows.last_mut().and_then(|row| row.last_mut()) {
+                trim_spans(&mut spans);
+                if !cell.is_empty() {
+                    cell.push(Span::plain("\n"));
+                }
+                cell.extend(spans);
+            }
+            return;
+        }
+
+        let paragraph = self.paragraphs - 1;
+        let style = self.style_id.clone();
+        if self.style == Some(StyleKind::Code) {
+            let text: String = spans.iter().map(|s| s.text.as_str()).collect();
+            // Consecutive code paragraphs are lines of one block.
+            if let Some(block) = self
+                .blocks
+                .last_mut()
+                .filter(|block| matches!(block.kind, BlockKind::Code { .. }))
+            {
+                if let BlockKind::Code { text: code, .. } = &mut block.kind {
+                    code.push('\n');
+                    code.push_str(&text);
+                }
+                extend_source(block, paragraph);
+            } else {
+                let kind = BlockKind::Code {
+                    language: None,
+                    text,
+                };
+                self.blocks.push(self.block(kind, paragraph, style));
+            }
+            return;
+        }
+
+        trim_spans(&mut spans);
+        let kind = match (self.style, &self.numbering.0) {
+            (Some(StyleKind::Heading(level)), _) => BlockKind::Heading { level, spans },
+            (_, Some(num)) => BlockKind::ListItem {
+                depth: self.numbering.1,
+                ordered: self
+                    .parts
+                    .ordered
+                    .get(&(num.clone(), self.numbering.1))
+                    .copied()
+                    .unwrap_or(false),
+                spans,
+            },
+            _ => BlockKind::Paragraph { spans },
+        };
+        self.blocks.push(self.block(kind, paragraph, style));
+    }
+
+    fn finish(self) -> Vec<Block> {
+        self.blocks
+    }
 }
 
+/// Extend a block's locator to run to `paragraph`.
+fn extend_source(block: &mut Block, paragraph: usize) {
+    if let Some(Locator::Docx {
+        paragraph: first,
+        last_paragraph,
+        ..
+    }) = &mut block.source
+    {
+        *last_paragraph = (paragraph > *first).then_some(paragraph);
+    }
+}
+
+/// Fonts whose runs are rendered as inline code.
+const MONOSPACE_FONTS: &[&str] = &["courier", "consolas", "menlo", "monaco", "source code"];
+
 /// Fallback using docx-rs (much slower but more robust)
-fn parse_docx_fallback(path: &str) -> Result<String, BlitzParseError> {
-    let content = std::fs::read(path)?;
-    let docx = docx_rs::read_docx(&content)
-        .map_err(|_| BlitzParseError::Docx("Failed to read DOCX file".to_string()))?;
+fn fallback(content: &[u8]) -> Result<String, BlitzParseError> {
+    let docx = docx_rs::read_docx(content)
+        .map_err(|e| BlitzParseError::corrupt("docx", ErrorContext::default(), e))?;
 
     // Simplified, fast extraction
     let mut result = String::with_capacity(content.len() / 8);
@@ -0,0 +1,901 @@
+use crate::document::{Warning, WarningKind};
+use crate::errors::{BlitzParseError, ErrorContext};
+use crate::options::ExtractOptions;
+use crate::parsers::html::html_to_text;
+use base64::Engine;
+use encoding_rs::Encoding;
+use std::collections::HashMap;
+
+/// MIME entities nested deeper than this are not unpacked. Multipart
+/// containers and attached messages each add a level, including messages
+/// reached again through attachment extraction.
+const MAX_NESTING: usize = 32;
+
+/// A single address from a `From`, `To` or `Cc` header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mailbox {
+    pub name: Option<String>,
+    pub address: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct EmailAttachment {
+    pub filename: Option<String>,
+    pub content_type: String,
+    /// Extracted text, if the attachment is a supported format.
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EmailMessage {
+    pub from: Vec<Mailbox>,
+    pub to: Vec<Mailbox>,
+    pub cc: Vec<Mailbox>,
+    pub subject: Option<String>,
+    pub date: Option<String>,
+    pub message_id: Option<String>,
+    pub in_reply_to: Option<String>,
+    pub references: Vec<String>,
+    pub body: String,
+    pub attachments: Vec<EmailAttachment>,
+}
+
+impl EmailMessage {
+    /// Headers, body and attachment text as one plain-text document.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        let headers = [
+            ("From", format_mailboxes(&self.from)),
+            ("To", format_mailboxes(&self.to)),
+            ("Cc", format_mailboxes(&self.cc)),
+            ("Subject", self.subject.clone().unwrap_or_default()),
+            ("Date", self.date.clone().unwrap_or_default()),
+        ];
+        for (name, value) in headers {
+            if !value.is_empty() {
+                out.push_str(&format!("{}: {}\n", name, value));
+            }
+        }
+        out.push('\n');
+        out.push_str(self.body.trim());
+        for attachment in &self.attachments {
+            if let Some(text) = &attachment.text {
+                let name = attachment.filename.as_deref().unwrap_or("attachment");
+                out.push_str(&format!("\n\n[Attachment: {}]\n", name));
+                out.push_str(text.trim());
+            }
+        }
+        out
+    }
+
+    /// Header fields as the flat key/value properties stored on the message node.
+    pub fn to_map(&self) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        let fields = [
+            ("from", format_mailboxes(&self.from)),
+            ("to", format_mailboxes(&self.to)),
+            ("cc", format_mailboxes(&self.cc)),
+            ("subject", self.subject.clone().unwrap_or_default()),
+            ("date", self.date.clone().unwrap_or_default()),
+            ("message_id", self.message_id.clone().unwrap_or_default()),
+        ];
+        for (key, value) in fields {
+            if !value.is_empty() {
+                map.insert(key.to_string(), value);
+            }
+        }
+        map
+    }
+}
+
+pub fn parse_eml(path: &str) -> Result<String, BlitzParseError> {
+    Ok(read_eml(path)?.text())
+}
+
+pub fn read_eml(path: &str) -> Result<EmailMessage, BlitzParseError> {
+    read_eml_with(path, &ExtractOptions::default())
+}
+
+pub fn read_eml_with(
+    path: &str,
+    options: &ExtractOptions,
+) -> Result<EmailMessage, BlitzParseError> {
+    let bytes = std::fs::read(path)?;
+    parse_email_bytes_with(&bytes, options)
+}
+
+pub fn parse_mbox(path: &str) -> Result<String, BlitzParseError> {
+    Ok(read_mbox(path)?.text())
+}
+
+pub fn read_mbox(path: &str) -> Result<Mbox, BlitzParseError> {
+    read_mbox_with(path, &ExtractOptions::default())
+}
+
+pub fn read_mbox_with(path: &str, options: &ExtractOptions) -> Result<Mbox, BlitzParseError> {
+    let bytes = std::fs::read(path)?;
+    parse_mbox_bytes_with(&bytes, options)
+}
+
+/// The messages of an mbox file.
+#[derive(Debug, Clone, Default)]
+pub struct Mbox {
+    pub messages: Vec<EmailMessage>,
+    /// Messages that could not be parsed and were left out.
+    pub warnings: Vec<Warning>,
+}
+
+impl Mbox {
+    /// Every message's text, separated by blank lines.
+    pub fn text(&self) -> String {
+        self.messages
+            .iter()
+            .map(EmailMessage::text)
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// Split an mbox file on its `From ` separator lines and parse each message.
+/// A message that fails to parse is recorded in `warnings` and the rest are
+/// still read.
+pub fn parse_mbox_bytes(bytes: &[u8]) -> Result<Mbox, BlitzParseError> {
+    parse_mbox_bytes_with(bytes, &ExtractOptions::default())
+}
+
+/// Like `parse_mbox_bytes`, passing `options` on to attachment extraction and
+/// checking the deadline and cancellation between messages.
+pub fn parse_mbox_bytes_with(
+    bytes: &[u8],
+    options: &ExtractOptions,
+) -> Result<Mbox, BlitzParseError> {
+    let mut mbox = Mbox::default();
+    let mut current: Vec<u8> = Vec::new();
+    let mut started = false;
+
+    for line in bytes.split_inclusive(|&b| b == b'\n') {
+        if line.starts_with(b"From ") {
+            if started && !current.is_empty() {
+                options.checkpoint()?;
+                mbox.push(&current, options);
+            }
+            current.clear();
+            started = true;
+            continue;
+        }
+        // mboxrd quoting: `>From ` lines had one `>` added on write.
+        if line.starts_with(b">") && trim_quotes(line).starts_with(b"From ") {
+            current.extend_from_slice(&line[1..]);
+        } else {
+            current.extend_from_slice(line);
+        }
+    }
+    if started && !current.is_empty() {
+        mbox.push(&current, options);
+    }
+    if !started {
+        return Err(BlitzParseError::MissingPart {
+            format: "mbox",
+            context: ErrorContext::part("`From ` separator"),
+        });
+    }
+    Ok(mbox)
+}
+
+impl Mbox {
+    fn push(&mut self, bytes: &[u8], options: &ExtractOptions) {
+        // Messages are numbered from 1 in file order, counting failed ones.
+        let number = self.messages.len() + self.warnings.len() + 1;
+        match parse_email_bytes_with(bytes, options) {
+            Ok(message) => self.messages.push(message),
+            Err(e) => self.warnings.push(Warning {
+                kind: WarningKind::Skipped,
+                message: e.to_string(),
+                context: ErrorContext::part(format!("message {}", number)),
+            }),
+        }
+    }
+}
+
+fn trim_quotes(line: &[u8]) -> &[u8] {
+    let quotes = line.iter().take_while(|&&b| b == b'>').count();
+    &line[quotes..]
+}
+
+/// Parse a single RFC 5322 message.
+pub fn parse_email_bytes(bytes: &[u8]) -> Result<EmailMessage, BlitzParseError> {
+    parse_email_bytes_with(bytes, &ExtractOptions::default())
+}
+
+/// Like `parse_email_bytes`, extracting attachments within the limits set in
+/// `options`.
+pub fn parse_email_bytes_with(
+    bytes: &[u8],
+    options: &ExtractOptions,
+) -> Result<EmailMessage, BlitzParseError> {
+    parse_message(bytes, options.nesting, options)
+}
+
+fn parse_message(
+    bytes: &[u8],
+    depth: usize,
+    options: &ExtractOptions,
+) -> Result<EmailMessage, BlitzParseError> {
+    let part = Part::parse(bytes);
+    if part.headers.is_empty() {
+        return Err(BlitzParseError::MissingPart {
+            format: "eml",
+            context: ErrorContext::part("message headers"),
+        });
+    }
+
+    let mut message = EmailMessage {
+        from: part.header("from").map(parse_mailboxes).unwrap_or_default(),
+        to: part.header("to").map(parse_mailboxes).unwrap_or_default(),
+        cc: part.header("cc").map(parse_mailboxes).unwrap_or_default(),
+        subject: part.header("subject").map(decode_encoded_words),
+        date: part.header("date").map(|d| d.trim().to_string()),
+        message_id: part
+            .header("message-id")
+            .and_then(|v| parse_msg_ids(v).pop()),
+        in_reply_to: part
+            .header("in-reply-to")
+            .and_then(|v| parse_msg_ids(v).pop()),
+        references: part
+            .header("references")
+            .map(parse_msg_ids)
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    let mut walk = Walk {
+        options,
+        plain: None,
+        html: None,
+        attachments: Vec::new(),
+    };
+    walk.visit(&part, depth);
+    message.body = match (walk.plain, walk.html) {
+        (Some(plain), _) => plain,
+        (None, Some(html)) => html_to_text(&html),
+        (None, None) => String::new(),
+    };
+    message.attachments = walk.attachments;
+    Ok(message)
+}
+
+/// A MIME entity: its unfolded headers and raw body.
+struct Part<'a> {
+    headers: Vec<(String, String)>,
+    body: &'a [u8],
+}
+
+impl<'a> Part<'a> {
+    fn parse(bytes: &'a [u8]) -> Part<'a> {
+        let (head, body) = split_header_body(bytes);
+        let head = String::from_utf8_lossy(head);
+
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in head.lines() {
+            if line.starts_with([' ', '\t']) {
+                // Folded continuation of the previous header.
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+            } else if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+            }
+        }
+        Part { headers, body }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Lower-cased MIME type and its parameters, defaulting to `text/plain`.
+    fn content_type(&self) -> (String, HashMap<String, String>) {
+        match self.header("content-type") {
+            Some(value) => {
+                let (mime, params) = parse_header_params(value);
+                (mime.to_ascii_lowercase(), params)
+            }
+            None => ("text/plain".to_string(), HashMap::new()),
+        }
+    }
+
+    fn decoded_body(&self) -> Vec<u8> {
+        let encoding = self
+            .header("content-transfer-encoding")
+            .unwrap_or("7bit")
+            .trim()
+            .to_ascii_lowercase();
+        match encoding.as_str() {
+            "base64" => decode_base64(self.body),
+            "quoted-printable" => decode_quoted_printable(self.body, false),
+            _ => self.body.to_vec(),
+        }
+    }
+
+    fn filename(&self) -> Option<String> {
+        let from_disposition = self
+            .header("content-disposition")
+            .map(|v| parse_header_params(v).1)
+            .and_then(|params| params.get("filename").cloned());
+        from_disposition
+            .or_else(|| self.content_type().1.get("name").cloned())
+            .map(|name| decode_encoded_words(&name))
+    }
+
+    fn is_attachment(&self) -> bool {
+        self.header("content-disposition").is_some_and(|v| {
+            v.trim_start()
+                .to_ascii_lowercase()
+                .starts_with("attachment")
+        })
+    }
+}
+
+/// Collects the message body candidates and attachments while walking the MIME tree.
+struct Walk<'a> {
+    options: &'a ExtractOptions,
+    plain: Option<String>,
+    html: Option<String>,
+    attachments: Vec<EmailAttachment>,
+}
+
+impl Walk<'_> {
+    fn visit(&mut self, part: &Part, depth: usize) {
+        let (mime, params) = part.content_type();
+
+        if mime.starts_with("multipart/") {
+            let Some(boundary) = params.get("boundary") else {
+                return;
+            };
+            if depth >= MAX_NESTING {
+                return;
+            }
+            // Alternatives are all visited; the body prefers text/plain over text/html.
+            for child in split_multipart(part.body, boundary.as_bytes()) {
+                self.visit(&Part::parse(child), depth + 1);
+            }
+            return;
+        }
+
+        if mime == "message/rfc822" {
+            let filename = part.filename();
+            let text = if depth < MAX_NESTING {
+                parse_message(&part.decoded_body(), depth + 1, self.options)
+                    .ok()
+                    .map(|m| m.text())
+            } else {
+                None
+            };
+            self.attachments.push(EmailAttachment {
+                filename,
+                content_type: mime,
+                text,
+            });
+            return;
+        }
+
+        let filename = part.filename();
+        let is_body_text = !part.is_attachment() && filename.is_none();
+        if is_body_text && (mime == "text/plain" || mime == "text/html") {
+            let text = decode_charset(&part.decoded_body(), params.get("charset"));
+            let slot = if mime == "text/plain" {
+                &mut self.plain
+            } else {
+                &mut self.html
+            };
+            match slot {
+                Some(existing) => {
+                    existing.push('\n');
+                    existing.push_str(&text);
+                }
+                None => *slot = Some(text),
+            }
+            return;
+        }
+
+        // Attachments are parsed as documents nested one level further down.
+        let text = if depth < MAX_NESTING {
+            attachment_text(
+                part,
+                &mime,
+                filename.as_deref(),
+                params.get("charset"),
+                &self.options.nested(depth + 1),
+            )
+        } else {
+            None
+        };
+        self.attachments.push(EmailAttachment {
+            filename,
+            content_type: mime,
+            text,
+        });
+    }
+}
+
+/// Run the attachment through `extract_text` based on its file extension.
+fn attachment_text(
+    part: &Part,
+    mime: &str,
+    filename: Option<&str>,
+    charset: Option<&String>,
+    options: &ExtractOptions,
+) -> Option<String> {
+    let body = part.decoded_body();
+    let extension = filename
+        .and_then(|f| std::path::Path::new(f).extension())
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+
+    // `extract_text_from_bytes_with` normalizes; the other paths are normalized here.
+    let mut text = match extension {
+        Some(ext) => return crate::extract_text_from_bytes_with(&body, &ext, options).ok(),
+        None if mime.starts_with("text/") => decode_charset(&body, charset),
+        None => {
+            // Unnamed attachments fall back to the parser registered for their MIME type.
+            let parser = crate::registry::ParserRegistry::global()
+                .read()
+                .unwrap()
+                .for_mime_type(mime)?;
+            let source = crate::registry::Source {
+                bytes: &body,
+                path: None,
+                extension: None,
+                options,
+            };
+            parser.parse(&source).ok()?.text
+        }
+    };
+    options.normalize_text(&mut text);
+    Some(text)
+}
+
+fn split_header_body(bytes: &[u8]) -> (&[u8], &[u8]) {
+    for (i, window) in bytes.windows(2).enumerate() {
+        if window == b"\n\n" {
+            return (&bytes[..i], &bytes[i + 2..]);
+        }
+        if window == b"\n\r" && bytes.get(i + 2) == Some(&b'\n') {
+            return (&bytes[..i], &bytes[i + 3..]);
+        }
+    }
+    (bytes, &[])
+}
+
+/// Split a multipart body into its parts, dropping preamble and epilogue.
+fn split_multipart<'a>(body: &'a [u8], boundary: &[u8]) -> Vec<&'a [u8]> {
+    let mut delimiter = b"--".to_vec();
+    delimiter.extend_from_slice(boundary);
+
+    let mut parts = Vec::new();
+    let mut part_start: Option<usize> = None;
+    let mut pos = 0;
+    for line in body.split_inclusive(|&b| b == b'\n') {
+        let line_start = pos;
+        pos += line.len();
+
+        let trimmed = line.trim_ascii_end();
+        if !trimmed.starts_with(&delimiter) {
+            continue;
+        }
+        let rest = &trimmed[delimiter.len()..];
+        if !rest.is_empty() && rest != b"--" {
+            continue;
+        }
+
+        if let Some(start) = part_start {
+            // The line break before a delimiter belongs to the delimiter.
+            let mut end = line_start;
+            if end > start && body[end - 1] == b'\n' {
+                end -= 1;
+                if end > start && body[end - 1] == b'\r' {
+                    end -= 1;
+                }
+            }
+            parts.push(&body[start..end]);
+        }
+        if rest == b"--" {
+            return parts;
+        }
+        part_start = Some(pos);
+    }
+    // Unterminated multipart: keep the trailing part.
+    if let Some(start) = part_start {
+        parts.push(&body[start..]);
+    }
+    parts
+}
+
+/// Split `type/subtype; key=value; key="quoted value"` into the value and its parameters.
+fn parse_header_params(value: &str) -> (String, HashMap<String, String>) {
+    let mut segments = split_outside_quotes(value, ';').into_iter();
+    let main = segments.next().unwrap_or_default().trim().to_string();
+
+    let mut params = HashMap::new();
+    for segment in segments {
+        let Some((key, val)) = segment.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let val = val.trim().trim_matches('"').to_string();
+        // RFC 2231 extended parameters: `filename*=utf-8''na%C3%AFve.pdf`.
+        if let Some(base) = key.strip_suffix('*') {
+            params.insert(base.to_string(), decode_rfc2231(&val));
+        } else {
+            params.insert(key, val);
+        }
+    }
+    (main, params)
+}
+
+fn decode_rfc2231(value: &str) -> String {
+    let mut pieces = value.splitn(3, '\'');
+    let (Some(charset), Some(_language), Some(encoded)) =
+        (pieces.next(), pieces.next(), pieces.next())
+    else {
+        return value.to_string();
+    };
+    let bytes = percent_decode(encoded.as_bytes());
+    decode_charset(&bytes, Some(&charset.to_string()))
+}
+
+fn split_outside_quotes(value: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut angle_depth = 0usize;
+    for c in value.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '<' if !in_quotes => angle_depth += 1,
+            '>' if !in_quotes => angle_depth = angle_depth.saturating_sub(1),
+            _ => {}
+        }
+        if c == separator && !in_quotes && angle_depth == 0 {
+            parts.push(std::mem::take(&mut current));
+        } else {
+            current.push(c);
+        }
+    }
+    parts.push(current);
+    parts
+}
+
+fn parse_mailboxes(value: &str) -> Vec<Mailbox> {
+    split_outside_quotes(value, ',')
+        .iter()
+        .filter_map(|entry| {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                return None;
+            }
+            match (entry.rfind('<'), entry.rfind('>')) {
+                (Some(open), Some(close)) if open < close => {
+                    let name = decode_encoded_words(entry[..open].trim().trim_matches('"'));
+                    Some(Mailbox {
+                        name: (!name.is_empty()).then_some(name),
+                        address: entry[open + 1..close].trim().to_string(),
+                    })
+                }
+                _ => Some(Mailbox {
+                    name: None,
+                    address: entry.trim_matches(['<', '>']).to_string(),
+                }),
+            }
+        })
+        .collect()
+}
+
+fn format_mailboxes(mailboxes: &[Mailbox]) -> String {
+    mailboxes
+        .iter()
+        .map(|m| match &m.name {
+            Some(name) => format!("{} <{}>", name, m.address),
+            None => m.address.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Extract `<id@host>` tokens from Message-ID, In-Reply-To or References.
+fn parse_msg_ids(value: &str) -> Vec<String> {
+    value
+        .split('<')
+        .skip(1)
+        .filter_map(|s| s.split_once('>').map(|(id, _)| id.trim().to_string()))
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+/// Decode RFC 2047 encoded words such as `=?UTF-8?B?SGVsbG8=?=`.
+pub fn decode_encoded_words(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    // Whitespace between two adjacent encoded words is not significant.
+    let mut pending_space = String::new();
+    let mut last_was_encoded = false;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find("=?") else {
+            out.push_str(&pending_space);
+            out.push_str(rest);
+            break;
+        };
+        let decoded = rest[start + 2..]
+            .split_once("?")
+            .and_then(|(charset, tail)| {
+                let (encoding, tail) = tail.split_once('?')?;
+                let (text, after) = tail.split_once("?=")?;
+                let bytes = match encoding.to_ascii_uppercase().as_str() {
+                    "B" => decode_base64(text.as_bytes()),
+                    "Q" => decode_quoted_printable(text.as_bytes(), true),
+                    _ => return None,
+                };
+                // Strip an RFC 2231 language suffix: `UTF-8*en`.
+                let charset = charset.split('*').next().unwrap_or(charset).to_string();
+                Some((decode_charset(&bytes, Some(&charset)), after))
+            });
+
+        let before = &rest[..start];
+        match decoded {
+            Some((text, after)) => {
+                if !(last_was_encoded && before.trim().is_empty()) {
+                    out.push_str(&pending_space);
+                    out.push_str(before);
+                }
+                pending_space.clear();
+                out.push_str(&text);
+                last_was_encoded = true;
+                rest = after;
+                // Hold back whitespace until we know whether another word follows.
+                let trimmed = rest.trim_start();
+                pending_space.push_str(&rest[..rest.len() - trimmed.len()]);
+                rest = trimmed;
+            }
+            None => {
+                out.push_str(&pending_space);
+                pending_space.clear();
+                out.push_str(&rest[..start + 2]);
+                last_was_encoded = false;
+                rest = &rest[start + 2..];
+            }
+        }
+    }
+    out
+}
+
+fn decode_base64(input: &[u8]) -> Vec<u8> {
+    let cleaned: Vec<u8> = input
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    base64::engine::general_purpose::STANDARD
+        .decode(&cleaned)
+        .or_else(|_| base64::engine::general_purpose::STANDARD_NO_PAD.decode(&cleaned))
+        .unwrap_or_default()
+}
+
+/// Decode quoted-printable; `header` enables the RFC 2047 `_` = space rule.
+fn decode_quoted_printable(input: &[u8], header: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'=' => {
+                let hex = input
+                    .get(i + 1..i + 3)
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok());
+                if let Some(byte) = hex {
+                    out.push(byte);
+                    i += 3;
+                } else if input.get(i + 1..i + 3) == Some(b"\r\n") {
+                    i += 3; // soft line break
+                } else if input.get(i + 1) == Some(&b'\n') {
+                    i += 2; // soft line break
+                } else {
+                    out.push(b'=');
+                    i += 1;
+                }
+            }
+            b'_' if header => {
+                out.push(b' ');
+                i += 1;
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn percent_decode(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        let hex = (input[i] == b'%')
+            .then(|| input.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(input[i]);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Decode bytes in the declared charset; undeclared text is UTF-8 if valid, else Windows-1252.
+fn decode_charset(bytes: &[u8], charset: Option<&String>) -> String {
+    let encoding = charset.and_then(|c| Encoding::for_label(c.trim().as_bytes()));
+    match encoding {
+        Some(encoding) => encoding.decode_without_bom_handling(bytes).0.into_owned(),
+        None => match std::str::from_utf8(bytes) {
+            Ok(text) => text.to_string(),
+            Err(_) => encoding_rs::WINDOWS_1252
+                .decode_without_bom_handling(bytes)
+                .0
+                .into_owned(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MULTIPART: &str = "From: =?UTF-8?Q?Ren=C3=A9e_Dupont?= <renee@example.com>\r
+To: \"Smith, John\" <john@example.com>, jane@example.com\r
+Cc: Ops <ops@example.com>\r
+Subject: =?UTF-8?B?UmU6IFF1YXJ0ZXJseQ==?=\r
+ =?UTF-8?Q?_r=C3=A9sum=C3=A9?=\r
+Date: Tue, 3 Sep 2024 10:00:00 +0000\r
+Message-ID: <b@example.com>\r
+In-Reply-To: <a@example.com>\r
+MIME-Version: 1.0\r
+Content-Type: multipart/mixed; boundary=\"outer\"\r
+\r
+preamble\r
+--outer\r
+Content-Type: multipart/alternative; boundary=inner\r
+\r
+--inner\r
+Content-Type: text/plain; charset=utf-8\r
+Content-Transfer-Encoding: quoted-printable\r
+\r
+Caf=C3=A9 numbers are =\r
+attached.\r
+--inner\r
+Content-Type: text/html\r
+\r
+<p>HTML version</p>\r
+--inner--\r
+--outer\r
+Content-Type: text/plain; name=\"notes.txt\"\r
+Content-Disposition: attachment; filename=\"notes.txt\"\r
+Content-Transfer-Encoding: base64\r
+\r
+QXR0YWNoZWQgbm90ZXMu\r
+--outer\r
+Content-Type: image/png\r
+Content-Disposition: attachment; filename=\"logo.png\"\r
+Content-Transfer-Encoding: base64\r
+\r
+iVBORw0KGgo=\r
+--outer--\r
+";
+
+    #[test]
+    fn test_parse_multipart_email() {
+        let message = parse_email_bytes(MULTIPART.as_bytes()).unwrap();
+
+        assert_eq!(
+            message.from,
+            vec![Mailbox {
+                name: Some("Renée Dupont".to_string()),
+                address: "renee@example.com".to_string()
+            }]
+        );
+        assert_eq!(message.to.len(), 2);
+        assert_eq!(message.to[0].name.as_deref(), Some("Smith, John"));
+        assert_eq!(message.to[1].address, "jane@example.com");
+        assert_eq!(message.subject.as_deref(), Some("Re: Quarterly résumé"));
+        assert_eq!(message.message_id.as_deref(), Some("b@example.com"));
+        assert_eq!(message.in_reply_to.as_deref(), Some("a@example.com"));
+
+        assert_eq!(message.body, "Café numbers are attached.");
+        assert_eq!(message.attachments.len(), 2);
+        assert_eq!(
+            message.attachments[0].text.as_deref(),
+            Some("Attached notes.")
+        );
+        assert_eq!(message.attachments[1].text, None);
+
+        // Attachments are extracted within the caller's limits.
+        let options = ExtractOptions {
+            max_input_bytes: Some(4),
+            ..Default::default()
+        };
+        let message = parse_email_bytes_with(MULTIPART.as_bytes(), &options).unwrap();
+        assert_eq!(message.attachments[0].text, None);
+    }
+
+    /// A message whose only content is `inner`, wrapped `levels` times in
+    /// multipart containers or attached as `.eml` files.
+    fn nest(inner: &str, levels: usize, attach: bool) -> String {
+        (0..levels).fold(inner.to_string(), |child, level| {
+            let part = if attach {
+                format!(
+                    "Content-Type: application/octet-stream\n\
+Content-Disposition: attachment; filename=\"m{level}.eml\"\n\n{child}"
+                )
+            } else {
+                child
+            };
+            format!(
+                "Subject: level {level}\n\
+Content-Type: multipart/mixed; boundary=\"b{level}\"\n\n--b{level}\n{part}\n--b{level}--\n"
+            )
+        })
+    }
+
+    #[test]
+    fn test_nesting_is_capped_across_parts_and_attachments() {
+        let inner = "Content-Type: text/plain\n\nbottom";
+        let shallow = parse_email_bytes(nest(inner, 3, false).as_bytes()).unwrap();
+        assert_eq!(shallow.body, "bottom");
+        let deep = parse_email_bytes(nest(inner, 500, false).as_bytes()).unwrap();
+        assert_eq!(deep.body, "");
+
+        let leaf = "Subject: leaf\n\nbottom";
+        let shallow = parse_email_bytes(nest(leaf, 3, true).as_bytes()).unwrap();
+        assert!(shallow.text().contains("bottom"));
+        // Each attached message re-enters through the registry, which must
+        // not reset the depth.
+        let deep = parse_email_bytes(nest(leaf, 40, true).as_bytes()).unwrap();
+        assert!(!deep.text().contains("bottom"));
+    }
+
+    #[test]
+    fn test_html_only_email_and_mbox() {
+        let mbox = "From renee@example.com Tue Sep  3 10:00:00 2024\n\
+Subject: First\n\
+Content-Type: text/html\n\
+\n\
+<html><body><p>Hello <b>there</b></p></body></html>\n\
+\n\
+From jane@example.com Tue Sep  3 11:00:00 2024\n\
+Subject: Second\n\
+\n\
+>From the archive.\n\
+From broken@example.com Tue Sep  3 12:00:00 2024\n\
+no headers at all\n";
+        let mbox = parse_mbox_bytes(mbox.as_bytes()).unwrap();
+        assert_eq!(mbox.warnings.len(), 1);
+        assert_eq!(mbox.warnings[0].context.part.as_deref(), Some("message 3"));
+        let messages = mbox.messages;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].body, "Hello there");
+        assert_eq!(messages[1].subject.as_deref(), Some("Second"));
+        assert_eq!(messages[1].body.trim(), "From the archive.");
+    }
+}
@@ -0,0 +1,713 @@
+use crate::document::{Warning, WarningKind};
+use crate::errors::{zip_error, BlitzParseError, ErrorContext};
+use crate::graph_builder::models::Section;
+use crate::options::ExtractOptions;
+use crate::parsers::html::{html_to_blocks, html_to_text};
+use crate::tree::DocumentTree;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek};
+
+/// Points to the OPF package document.
+const CONTAINER_XML: &str = "META-INF/container.xml";
+
+/// Dublin Core metadata declared in the OPF package document.
+#[derive(Debug, Clone, Default)]
+pub struct EpubMetadata {
+    pub title: Option<String>,
+    pub creators: Vec<String>,
+    pub language: Option<String>,
+    pub identifier: Option<String>,
+}
+
+impl EpubMetadata {
+    /// Metadata as the flat key/value map consumed by `GraphBuilder`.
+    pub fn to_map(&self) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        if let Some(title) = &self.title {
+            map.insert("title".to_string(), title.clone());
+        }
+        if !self.creators.is_empty() {
+            map.insert("creator".to_string(), self.creators.join(", "));
+        }
+        if let Some(language) = &self.language {
+            map.insert("language".to_string(), language.clone());
+        }
+        if let Some(identifier) = &self.identifier {
+            map.insert("identifier".to_string(), identifier.clone());
+        }
+        map
+    }
+}
+
+/// A single table-of-contents entry, from the EPUB 3 nav document or the EPUB 2 NCX.
+#[derive(Debug, Clone)]
+pub struct TocEntry {
+    pub title: String,
+    /// Archive path of the target content document, without fragment.
+    pub href: String,
+    /// Nesting depth, 0 for top-level entries.
+    pub level: usize,
+}
+
+/// The text of one content document, in spine order.
+#[derive(Debug, Clone)]
+pub struct EpubChapter {
+    pub href: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EpubBook {
+    pub metadata: EpubMetadata,
+    pub toc: Vec<TocEntry>,
+    pub chapters: Vec<EpubChapter>,
+    /// Spine items that could not be read and were left out.
+    pub warnings: Vec<Warning>,
+}
+
+impl EpubBook {
+    /// All chapter text in reading order.
+    pub fn text(&self) -> String {
+        self.chapters
+            .iter()
+            .map(|c| c.text.as_str())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Chapters grouped under their table-of-contents entries.
+    ///
+    /// A chapter is attached to the first TOC entry pointing at it; chapters the
+    /// TOC does not mention are appended to the preceding section.
+    pub fn sections(&self) -> Vec<Section> {
+        let mut sections: Vec<Section> = Vec::new();
+        for chapter in &self.chapters {
+            let mut entries = self.toc.iter().filter(|t| t.href == chapter.href);
+            match entries.next() {
+                Some(first) => {
+                    sections.push(Section {
+                        title: first.title.clone(),
+                        level: first.level,
+                        content: chapter.text.clone(),
+                    });
+                    // Further entries into the same file become empty sub-headings.
+                    sections.extend(entries.map(|t| Section {
+                        title: t.title.clone(),
+                        level: t.level,
+                        content: String::new(),
+                    }));
+                }
+                None => match sections.last_mut() {
+                    Some(last) => {
+                        if !last.content.is_empty() {
+                            last.content.push('\n');
+                        }
+                        last.content.push_str(&chapter.text);
+                    }
+                    None => sections.push(Section {
+                        title: chapter.href.clone(),
+                        level: 0,
+                        content: chapter.text.clone(),
+                    }),
+                },
+            }
+        }
+        sections
+    }
+}
+
+pub fn parse_epub(path: &str) -> Result<String, BlitzParseError> {
+    Ok(read_epub(path)?.text())
+}
+
+pub fn parse_epub_bytes(bytes: &[u8]) -> Result<String, BlitzParseError> {
+    Ok(read_epub_from(std::io::Cursor::new(bytes))?.text())
+}
+
+pub fn read_epub(path: &str) -> Result<EpubBook, BlitzParseError> {
+    read_epub_with(path, &ExtractOptions::default())
+}
+
+pub fn read_epub_with(path: &str, options: &ExtractOptions) -> Result<EpubBook, BlitzParseError> {
+    let file = std::fs::File::open(path)?;
+    read_epub_from_with(std::io::BufReader::new(file), options)
+}
+
+/// Read an EPUB container from any seekable source.
+pub fn read_epub_from<R: Read + Seek>(reader: R) -> Result<EpubBook, BlitzParseError> {
+    read_epub_from_with(reader, &ExtractOptions::default())
+}
+
+/// Like `read_epub_from`, bounding each member by `max_entry_bytes` and
+/// checking the deadline and cancellation between chapters.
+pub fn read_epub_from_with<R: Read + Seek>(
+    reader: R,
+    options: &ExtractOptions,
+) -> Result<EpubBook, BlitzParseError> {
+    let mut chapters = Vec::new();
+    let mut book = read_book(reader, options, |href, xhtml| {
+        chapters.push(EpubChapter {
+            text: html_to_text(xhtml),
+            href,
+        })
+    })?;
+    book.chapters = chapters;
+    Ok(book)
+}
+
+/// Read a book into the blocks of its chapters, in spine order.
+pub fn epub_to_tree(
+    bytes: &[u8],
+    options: &ExtractOptions,
+) -> Result<DocumentTree, BlitzParseError> {
+    let mut blocks = Vec::new();
+    let book = read_book(Cursor::new(bytes), options, |_, xhtml| {
+        blocks.extend(html_to_blocks(xhtml))
+    })?;
+    let mut tree = DocumentTree::new(blocks);
+    tree.warnings = book.warnings;
+    Ok(tree)
+}
+
+/// Read a book's metadata and table of contents, handing each spine item's
+/// path and XHTML to `chapter`. The returned book has no chapters.
+fn read_book<R: Read + Seek>(
+    reader: R,
+    options: &ExtractOptions,
+    mut chapter: impl FnMut(String, &str),
+) -> Result<EpubBook, BlitzParseError> {
+    let mut archive = zip::ZipArchive::new(reader).map_err(|e| zip_error("epub", None, e))?;
+
+    let container = read_entry(&mut archive, CONTAINER_XML, options)?;
+    let opf_path = find_rootfile(&container)?;
+    let opf = read_entry(&mut archive, &opf_path, options)?;
+    let package = parse_opf(&opf, &opf_path)?;
+
+    let toc = if let Some(nav) = package.nav_href.as_deref() {
+        let xml = read_entry(&mut archive, nav, options)?;
+        parse_nav(&xml, base_dir(nav))
+    } else if let Some(ncx) = package.ncx_href.as_deref() {
+        let xml = read_entry(&mut archive, ncx, options)?;
+        parse_ncx(&xml, base_dir(ncx))
+    } else {
+        Vec::new()
+    };
+
+    let mut warnings = package.warnings;
+    for href in package.spine {
+        options.checkpoint()?;
+        let xhtml = match read_entry(&mut archive, &href, options) {
+            Ok(xhtml) => xhtml,
+            Err(e @ (BlitzParseError::LimitExceeded { .. } | BlitzParseError::Cancelled)) => {
+                return Err(e)
+            }
+            // Spine items missing from the archive are reported rather than failing the book.
+            Err(e) => {
+                warnings.push(Warning::from_error(WarningKind::Skipped, &e));
+                continue;
+            }
+        };
+        chapter(href, &xhtml);
+    }
+
+    Ok(EpubBook {
+        metadata: package.metadata,
+        toc,
+        chapters: Vec::new(),
+        warnings,
+    })
+}
+
+fn read_entry<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+    options: &ExtractOptions,
+) -> Result<String, BlitzParseError> {
+    let entry = archive
+        .by_name(name)
+        .map_err(|e| zip_error("epub", Some(name), e))?;
+    let bytes = options.read_entry(entry)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Locate the OPF package document via `META-INF/container.xml`.
+fn find_rootfile(container: &str) -> Result<String, BlitzParseError> {
+    let mut reader = Reader::from_str(container);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.local_name().as_ref() == b"rootfile" => {
+                if let Some(path) = attr(&e, b"full-path") {
+                    return Ok(path);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                let context = ErrorContext::part(CONTAINER_XML).at(reader.error_position());
+                return Err(BlitzParseError::corrupt("epub", context, e));
+            }
+            _ => {}
+        }
+    }
+    Err(BlitzParseError::MissingPart {
+        format: "epub",
+        context: ErrorContext::part(format!("{} rootfile", CONTAINER_XML)),
+    })
+}
+
+struct Package {
+    metadata: EpubMetadata,
+    spine: Vec<String>,
+    nav_href: Option<String>,
+    ncx_href: Option<String>,
+    /// Spine entries with no manifest item.
+    warnings: Vec<Warning>,
+}
+
+fn parse_opf(opf: &str, opf_path: &str) -> Result<Package, BlitzParseError> {
+    let base = base_dir(opf_path);
+    let mut reader = Reader::from_str(opf);
+    reader.config_mut().trim_text(true);
+
+    let mut metadata = EpubMetadata::default();
+    let mut unique_id = None;
+    let mut identifiers: Vec<(Option<String>, String)> = Vec::new();
+    // id -> (href, media-type)
+    let mut manifest: HashMap<String, (String, String)> = HashMap::new();
+    let mut spine_ids = Vec::new();
+    let mut ncx_id = None;
+    let mut nav_href = None;
+
+    let mut in_metadata = false;
+    let mut current_dc: Option<(Vec<u8>, Option<String>)> = None;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match e.local_name().as_ref() {
+                b"package" => unique_id = attr(&e, b"unique-identifier"),
+                b"metadata" => in_metadata = true,
+                b"spine" => ncx_id = attr(&e, b"toc"),
+                // `<item ...></item>` is as valid as the usual empty form.
+                b"item" => read_item(&e, base, &mut manifest, &mut nav_href),
+                b"itemref" => spine_ids.extend(attr(&e, b"idref")),
+                name if in_metadata => current_dc = Some((name.to_vec(), attr(&e, b"id"))),
+                _ => {}
+            },
+            Ok(Event::Empty(e)) => match e.local_name().as_ref() {
+                b"item" => read_item(&e, base, &mut manifest, &mut nav_href),
+                b"itemref" => spine_ids.extend(attr(&e, b"idref")),
+                _ => {}
+            },
+            Ok(Event::Text(t)) => {
+                if let Some((name, id)) = &current_dc {
+                    let value = t.unescape().unwrap_or_default().trim().to_string();
+                    if value.is_empty() {
+                        continue;
+                    }
+                    match name.as_slice() {
+                        b"title" if metadata.title.is_none() => metadata.title = Some(value),
+                        b"creator" => metadata.creators.push(value),
+                        b"language" if metadata.language.is_none() => {
+                            metadata.language = Some(value)
+                        }
+                        b"identifier" => identifiers.push((id.clone(), value)),
+                        _ => {}
+                    }
+                }
+            }
+            Ok(Event::End(e)) => match e.local_name().as_ref() {
+                b"metadata" => {
+                    in_metadata = false;
+                    current_dc = None;
+                }
+                _ => current_dc = None,
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                let context = ErrorContext::part(opf_path).at(reader.error_position());
+                return Err(BlitzParseError::corrupt("epub", context, e));
+            }
+            _ => {}
+        }
+    }
+
+    // Prefer the identifier the package declares as unique.
+    metadata.identifier = identifiers
+        .iter()
+        .find(|(id, _)| id.is_some() && *id == unique_id)
+        .or(identifiers.first())
+        .map(|(_, value)| value.clone());
+
+    let mut spine = Vec::with_capacity(spine_ids.len());
+    let mut warnings = Vec::new();
+    for id in spine_ids {
+        match manifest.get(&id) {
+            Some((href, _)) => spine.push(href.clone()),
+            None => warnings.push(Warning::from_error(
+                WarningKind::Skipped,
+                &BlitzParseError::MissingPart {
+                    format: "epub",
+                    context: ErrorContext::part(format!("spine item {id}")),
+                },
+            )),
+        }
+    }
+
+    let ncx_href = ncx_id
+        .and_then(|id| manifest.get(&id).map(|(href, _)| href.clone()))
+        .or_else(|| {
+            manifest
+                .values()
+                .find(|(_, media_type)| media_type == "application/x-dtbncx+xml")
+                .map(|(href, _)| href.clone())
+        });
+
+    Ok(Package {
+        metadata,
+        spine,
+        nav_href,
+        ncx_href,
+        warnings,
+    })
+}
+
+/// Add a manifest `<item>` to `manifest`, noting it if it is the nav document.
+fn read_item(
+    e: &BytesStart,
+    base: &str,
+    manifest: &mut HashMap<String, (String, String)>,
+    nav_href: &mut Option<String>,
+) {
+    let (Some(id), Some(href)) = (attr(e, b"id"), attr(e, b"href")) else {
+        return;
+    };
+    let href = resolve_href(base, &href);
+    let properties = attr(e, b"properties").unwrap_or_default();
+    if properties.split_whitespace().any(|p| p == "nav") {
+        *nav_href = Some(href.clone());
+    }
+    let media_type = attr(e, b"media-type").unwrap_or_default();
+    manifest.insert(id, (href, media_type));
+}
+
+/// Parse the `<nav epub:type="toc">` list of an EPUB 3 navigation document.
+fn parse_nav(xml: &str, base: &str) -> Vec<TocEntry> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().check_end_names = false;
+
+    let mut entries = Vec::new();
+    let mut nav_depth = 0usize;
+    let mut in_toc = false;
+    let mut list_depth = 0usize;
+    let mut current: Option<(String, String)> = None;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match e.local_name().as_ref() {
+                b"nav" => {
+                    nav_depth += 1;
+                    if !in_toc && attr(&e, b"type").is_some_and(|t| t.contains("toc")) {
+                        in_toc = true;
+                        nav_depth = 1;
+                    }
+                }
+                b"ol" if in_toc => list_depth += 1,
+                b"a" | b"span" if in_toc => {
+                    let href = attr(&e, b"href")
+                        .map(|h| resolve_href(base, &h))
+                        .unwrap_or_default();
+                    current = Some((String::new(), href));
+                }
+                _ => {}
+            },
+            Ok(Event::Text(t)) => {
+                if let Some((title, _)) = current.as_mut() {
+                    title.push_str(&t.unescape().unwrap_or_default());
+                }
+            }
+            Ok(Event::End(e)) => match e.local_name().as_ref() {
+                b"nav" if in_toc => {
+                    nav_depth -= 1;
+                    if nav_depth == 0 {
+                        in_toc = false;
+                    }
+                }
+                b"ol" if in_toc => list_depth = list_depth.saturating_sub(1),
+                b"a" | b"span" => {
+                    if let Some((title, href)) = current.take() {
+                        entries.push(TocEntry {
+                            title: collapse_whitespace(&title),
+                            href,
+                            level: list_depth.saturating_sub(1),
+                        });
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    entries
+}
+
+/// Parse the `navMap` of an EPUB 2 NCX document.
+fn parse_ncx(xml: &str, base: &str) -> Vec<TocEntry> {
+    let mut reader = Reader::from_str(xml);
+
+    // Entries are pushed when their label is complete and patched with the
+    // `content/@src` that follows it.
+    let mut entries: Vec<TocEntry> = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
+    let mut in_label = false;
+    let mut label = String::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match e.local_name().as_ref() {
+                b"navPoint" => {
+                    stack.push(entries.len());
+                    entries.push(TocEntry {
+                        title: String::new(),
+                        href: String::new(),
+                        level: stack.len() - 1,
+                    });
+                }
+                b"navLabel" => {
+                    in_label = true;
+                    label.clear();
+                }
+                _ => {}
+            },
+            Ok(Event::Empty(e)) if e.local_name().as_ref() == b"content" => {
+                if let (Some(&idx), Some(src)) = (stack.last(), attr(&e, b"src")) {
+                    entries[idx].href = resolve_href(base, &src);
+                }
+            }
+            Ok(Event::Text(t)) if in_label => label.push_str(&t.unescape().unwrap_or_default()),
+            Ok(Event::End(e)) => match e.local_name().as_ref() {
+                b"navPoint" => {
+                    stack.pop();
+                }
+                b"navLabel" => {
+                    in_label = false;
+                    if let Some(&idx) = stack.last() {
+                        entries[idx].title = collapse_whitespace(&label);
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    entries
+}
+
+fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+fn base_dir(path: &str) -> &str {
+    path.rfind('/').map(|i| &path[..=i]).unwrap_or("")
+}
+
+/// Resolve a manifest or TOC href against the directory of the referencing file,
+/// dropping any fragment and percent-encoding.
+fn resolve_href(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+    let href = percent_decode(href);
+    let mut parts: Vec<&str> = base.split('/').filter(|p| !p.is_empty()).collect();
+    for part in href.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(b) = std::str::from_utf8(&bytes[i + 1..i + 3])
+                .ok()
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+            {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::write::FileOptions;
+
+    fn build_epub(files: &[(&str, &str)]) -> Cursor<Vec<u8>> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        let mut cursor = writer.finish().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    const CONTAINER: &str = r#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#;
+
+    const OPF: &str = r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" unique-identifier="bookid" version="3.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="isbn">978-0-00-000000-0</dc:identifier>
+    <dc:identifier id="bookid">urn:uuid:1234</dc:identifier>
+    <dc:title>Rust in Depth</dc:title>
+    <dc:creator>Ada Lovelace</dc:creator>
+    <dc:creator>Alan Turing</dc:creator>
+    <dc:language>en</dc:language>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="c1" href="text/ch%201.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c2" href="text/ch2.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c3" href="text/notes.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine>
+    <itemref idref="c2"/>
+    <itemref idref="c1"/>
+    <itemref idref="c3"/>
+  </spine>
+</package>"#;
+
+    const NAV: &str = r#"<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<body><nav epub:type="toc"><ol>
+  <li><a href="text/ch2.xhtml">Ownership</a>
+    <ol><li><a href="text/ch2.xhtml#borrow">Borrowing</a></li></ol></li>
+  <li><a href="text/ch%201.xhtml">Lifetimes</a></li>
+</ol></nav></body></html>"#;
+
+    #[test]
+    fn test_read_epub_in_spine_order_with_toc() {
+        let epub = build_epub(&[
+            ("mimetype", "application/epub+zip"),
+            ("META-INF/container.xml", CONTAINER),
+            ("OEBPS/content.opf", OPF),
+            ("OEBPS/nav.xhtml", NAV),
+            (
+                "OEBPS/text/ch 1.xhtml",
+                "<html><body><h1>Lifetimes</h1><p>Second in spine.</p></body></html>",
+            ),
+            (
+                "OEBPS/text/ch2.xhtml",
+                "<html><body><h1>Ownership</h1><p>First in spine.</p></body></html>",
+            ),
+            (
+                "OEBPS/text/notes.xhtml",
+                "<html><body><p>Endnotes.</p></body></html>",
+            ),
+        ]);
+
+        let tree = epub_to_tree(epub.get_ref(), &ExtractOptions::default()).unwrap();
+        let headings: Vec<_> = tree
+            .blocks
+            .iter()
+            .filter_map(|block| match &block.kind {
+                crate::tree::BlockKind::Heading { level, spans } => {
+                    Some((*level, spans[0].text.as_str()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(headings, vec![(1, "Ownership"), (1, "Lifetimes")]);
+        assert_eq!(tree.blocks.len(), 5);
+
+        let book = read_epub_from(epub).unwrap();
+
+        assert_eq!(book.metadata.title.as_deref(), Some("Rust in Depth"));
+        assert_eq!(book.metadata.creators, vec!["Ada Lovelace", "Alan Turing"]);
+        assert_eq!(book.metadata.language.as_deref(), Some("en"));
+        assert_eq!(book.metadata.identifier.as_deref(), Some("urn:uuid:1234"));
+
+        assert_eq!(
+            book.text(),
+            "Ownership\nFirst in spine.\nLifetimes\nSecond in spine.\nEndnotes."
+        );
+
+        let toc: Vec<_> = book
+            .toc
+            .iter()
+            .map(|t| (t.title.as_str(), t.level))
+            .collect();
+        assert_eq!(
+            toc,
+            vec![("Ownership", 0), ("Borrowing", 1), ("Lifetimes", 0)]
+        );
+
+        let sections = book.sections();
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0].title, "Ownership");
+        assert_eq!(sections[1].title, "Borrowing");
+        assert_eq!(sections[1].level, 1);
+        assert_eq!(sections[2].title, "Lifetimes");
+        assert_eq!(
+            sections[2].content,
+            "Lifetimes\nSecond in spine.\nEndnotes."
+        );
+    }
+
+    #[test]
+    fn test_ncx_toc() {
+        let ncx = r#"<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/"><navMap>
+  <navPoint id="p1"><navLabel><text>Part One</text></navLabel><content src="a.html"/>
+    <navPoint id="p2"><navLabel><text>Chapter 1</text></navLabel><content src="b.html#x"/></navPoint>
+  </navPoint>
+</navMap></ncx>"#;
+        let toc = parse_ncx(ncx, "OPS/");
+        assert_eq!(toc.len(), 2);
+        assert_eq!(toc[0].title, "Part One");
+        assert_eq!(toc[0].href, "OPS/a.html");
+        assert_eq!(toc[1].level, 1);
+        assert_eq!(toc[1].href, "OPS/b.html");
+    }
+
+    #[test]
+    fn test_opf_items_with_end_tags_and_dangling_spine_entries() {
+        let opf = r#"<package><manifest>
+  <item id="c1" href="a.xhtml" media-type="application/xhtml+xml"></item>
+  <item id="c2" href="b.xhtml" media-type="application/xhtml+xml"/>
+</manifest><spine><itemref idref="c1"></itemref><itemref idref="gone"/><itemref idref="c2"/></spine></package>"#;
+        let package = parse_opf(opf, "OPS/content.opf").unwrap();
+        assert_eq!(package.spine, vec!["OPS/a.xhtml", "OPS/b.xhtml"]);
+        assert_eq!(package.warnings.len(), 1);
+        assert_eq!(
+            package.warnings[0].context.part.as_deref(),
+            Some("spine item gone")
+        );
+    }
+}
@@ -0,0 +1,403 @@
+use crate::tree::{push_span, trim_spans, Block, BlockKind, Span};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// Elements whose content is never visible text.
+const SKIPPED_ELEMENTS: &[&[u8]] = &[b"head", b"script", b"style", b"template", b"svg", b"math"];
+
+/// Elements that start a new line of output.
+const BLOCK_ELEMENTS: &[&[u8]] = &[
+    b"p",
+    b"div",
+    b"br",
+    b"li",
+    b"tr",
+    b"h1",
+    b"h2",
+    b"h3",
+    b"h4",
+    b"h5",
+    b"h6",
+    b"blockquote",
+    b"pre",
+    b"section",
+    b"article",
+    b"aside",
+    b"header",
+    b"footer",
+    b"table",
+    b"ul",
+    b"ol",
+    b"dl",
+    b"dt",
+    b"dd",
+    b"figure",
+    b"figcaption",
+    b"hr",
+];
+
+/// Extract visible text from (X)HTML markup.
+///
+/// The reader is deliberately lenient: mismatched end tags and unknown
+/// entities are tolerated so that slightly broken markup still yields text.
+pub fn html_to_text(html: &str) -> String {
+    let mut reader = Reader::from_str(html);
+    let config = reader.config_mut();
+    config.trim_text(false);
+    config.check_end_names = false;
+    config.allow_unmatched_ends = true;
+
+    let mut result = String::with_capacity(html.len() / 2);
+    let mut skip_depth = 0usize;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = e.local_name();
+                let name = name.as_ref().to_ascii_lowercase();
+                if skip_depth > 0 || SKIPPED_ELEMENTS.contains(&name.as_slice()) {
+                    skip_depth += 1;
+                } else if BLOCK_ELEMENTS.contains(&name.as_slice()) {
+                    result.push('\n');
+                } else if name == b"td" || name == b"th" {
+                    result.push('\t');
+                }
+            }
+            Ok(Event::Empty(e)) => {
+                let name = e.local_name();
+                let name = name.as_ref().to_ascii_lowercase();
+                if skip_depth == 0 && BLOCK_ELEMENTS.contains(&name.as_slice()) {
+                    result.push('\n');
+                }
+            }
+            Ok(Event::End(e)) => {
+                if skip_depth > 0 {
+                    skip_depth -= 1;
+                } else {
+                    let name = e.local_name();
+                    let name = name.as_ref().to_ascii_lowercase();
+                    if BLOCK_ELEMENTS.contains(&name.as_slice()) {
+                        result.push('\n');
+                    }
+                }
+            }
+            Ok(Event::Text(e)) if skip_depth == 0 => {
+                let text = e
+                    .unescape()
+                    .map(|t| t.into_owned())
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&e).into_owned());
+                push_collapsed(&mut result, &text);
+            }
+            Ok(Event::CData(e)) if skip_depth == 0 => {
+                push_collapsed(&mut result, &String::from_utf8_lossy(&e));
+            }
+            Ok(Event::Eof) => break,
+            // Keep whatever was read before the markup became unreadable.
+            Err(_) => break,
+            _ => {}
+        }
+    }
+
+    // Normalize whitespace
+    result
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Append text, collapsing runs of whitespace the way a browser would.
+fn push_collapsed(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if c.is_whitespace() && c != '\u{a0}' {
+            if !out.ends_with([' ', '\n', '\t']) {
+                out.push(' ');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+/// Convert (X)HTML into Markdown blocks: headings, paragraphs, nested
+/// lists, tables, preformatted code, and bold, italic, code and link spans.
+pub fn html_to_blocks(html: &str) -> Vec<Block> {
+    let mut reader = Reader::from_str(html);
+    let config = reader.config_mut();
+    config.trim_text(false);
+    config.check_end_names = false;
+    config.allow_unmatched_ends = true;
+
+    let mut state = BlockState::default();
+    let mut skip_depth = 0usize;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = e.local_name().as_ref().to_ascii_lowercase();
+                if skip_depth > 0 || SKIPPED_ELEMENTS.contains(&name.as_slice()) {
+                    skip_depth += 1;
+                } else {
+                    state.start(&name, &e);
+                }
+            }
+            Ok(Event::Empty(e)) if skip_depth == 0 => {
+                let name = e.local_name().as_ref().to_ascii_lowercase();
+                state.start(&name, &e);
+                state.end(&name);
+            }
+            Ok(Event::End(e)) => {
+                if skip_depth > 0 {
+                    skip_depth -= 1;
+                } else {
+                    state.end(&e.local_name().as_ref().to_ascii_lowercase());
+                }
+            }
+            Ok(Event::Text(e)) if skip_depth == 0 => {
+                let text = e
+                    .unescape()
+                    .map(|t| t.into_owned())
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&e).into_owned());
+                state.text(&text);
+            }
+            Ok(Event::CData(e)) if skip_depth == 0 => state.text(&String::from_utf8_lossy(&e)),
+            Ok(Event::Eof) => break,
+            // Keep whatever was read before the markup became unreadable.
+            Err(_) => break,
+            _ => {}
+        }
+    }
+    state.end(b"table");
+    state.flush();
+    state.blocks
+}
+
+/// Where `html_to_blocks` is in the document, and what it has produced.
+#[derive(Default)]
+struct BlockState {
+    blocks: Vec<Block>,
+    spans: Vec<Span>,
+    bold: usize,
+    italic: usize,
+    code: usize,
+    links: Vec<Option<String>>,
+    heading: Option<usize>,
+    /// Whether each open list is ordered.
+    lists: Vec<bool>,
+    in_item: bool,
+    /// Text of an open `pre`, kept verbatim, and its language.
+    pre: Option<(String, Option<String>)>,
+    table: Option<Vec<Vec<Vec<Span>>>>,
+}
+
+impl BlockState {
+    fn start(&mut self, name: &[u8], e: &BytesStart) {
+        match name {
+            b"h1" | b"h2" | b"h3" | b"h4" | b"h5" | b"h6" => {
+                self.flush();
+                self.heading = Some((name[1] - b'0') as usize);
+            }
+            b"ul" | b"ol" => {
+                self.flush();
+                self.lists.push(name == b"ol");
+            }
+            b"li" => {
+                self.flush();
+                self.in_item = true;
+            }
+            b"pre" => {
+                self.flush();
+                self.pre = Some((String::new(), None));
+            }
+            b"table" => {
+                self.flush();
+                self.table.get_or_insert_with(Vec::new);
+            }
+            b"tr" => {
+                if let Some(rows) = &mut self.table {
+                    rows.push(Vec::new());
+                }
+            }
+            b"td" | b"th" => {
+                self.flush();
+                if let Some(rows) = &mut self.table {
+                    if rows.is_empty() {
+                        rows.push(Vec::new());
+                    }
+                    rows.last_mut().unwrap().push(Vec::new());
+                }
+            }
+            b"b" | b"strong" => self.bold += 1,
+            b"i" | b"em" => self.italic += 1,
+            b"code" | b"kbd" | b"samp" | b"tt" => {
+                self.code += 1;
+                if let Some((_, language @ None)) = &mut self.pre {
+                    *language = attribute(e, b"class").and_then(|class| {
+                        class
+                            .split_whitespace()
+                            .find_map(|c| c.strip_prefix("language-").map(str::to_string))
+                    });
+                }
+            }
+            b"a" => self.links.push(attribute(e, b"href")),
+            b"br" => self.text_span("\n"),
+            _ if BLOCK_ELEMENTS.contains(&name) => self.flush(),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, name: &[u8]) {
+        match name {
+            b"h1" | b"h2" | b"h3" | b"h4" | b"h5" | b"h6" => {
+                self.flush();
+                self.heading = None;
+            }
+            b"ul" | b"ol" => {
+                self.flush();
+                self.lists.pop();
+            }
+            b"li" => {
+                self.flush();
+                self.in_item = false;
+            }
+            b"pre" => {
+                if let Some((text, language)) = self.pre.take() {
+                    if !text.trim().is_empty() {
+                        self.blocks.push(BlockKind::Code { language, text }.into());
+                    }
+                }
+            }
+            b"td" | b"th" => self.flush(),
+            b"table" => {
+                self.flush();
+                if let Some(rows) = self.table.take() {
+                    let rows: Vec<_> = rows.into_iter().filter(|row| !row.is_empty()).collect();
+                    if !rows.is_empty() {
+                        self.blocks.push(BlockKind::Table { rows }.into());
+                    }
+                }
+            }
+            b"b" | b"strong" => self.bold = self.bold.saturating_sub(1),
+            b"i" | b"em" => self.italic = self.italic.saturating_sub(1),
+            b"code" | b"kbd" | b"samp" | b"tt" => self.code = self.code.saturating_sub(1),
+            b"a" => {
+                self.links.pop();
+            }
+            _ if BLOCK_ELEMENTS.contains(&name) => self.flush(),
+            _ => {}
+        }
+    }
+
+    /// Append text, collapsing whitespace the way a browser would.
+    fn text(&mut self, text: &str) {
+        if let Some((pre, _)) = &mut self.pre {
+            pre.push_str(text);
+            return;
+        }
+        let mut collapsed = String::with_capacity(text.len());
+        let mut space = self
+            .spans
+            .last()
+            .is_none_or(|s| s.text.ends_with([' ', '\n']));
+        for c in text.chars() {
+            if c.is_whitespace() && c != '\u{a0}' {
+                if !space {
+                    collapsed.push(' ');
+                }
+                space = true;
+            } else {
+                collapsed.push(c);
+                space = false;
+            }
+        }
+        if !collapsed.is_empty() {
+            self.text_span(&collapsed);
+        }
+    }
+
+    fn text_span(&mut self, text: &str) {
+        let span = Span {
+            text: text.to_string(),
+            bold: self.bold > 0,
+            italic: self.italic > 0,
+            code: self.code > 0,
+            link: self.links.last().cloned().flatten(),
+            footnote: None,
+        };
+        push_span(&mut self.spans, span);
+    }
+
+    /// End the current block: table cell content is kept in the cell, and
+    /// anything else becomes a heading, list item or paragraph.
+    fn flush(&mut self) {
+        let mut spans = std::mem::take(&mut self.spans);
+        if spans.iter().all(|s| s.text.trim().is_empty()) {
+            return;
+        }
+        trim_spans(&mut spans);
+
+        if let Some(cell) = self
+            .table
+            .as_mut()
+            .and_then(|rows| rows.last_mut())
+            .and_then(|row| row.last_mut())
+        {
+            if !cell.is_empty() {
+                cell.push(Span::plain("\n"));
+            }
+            cell.extend(spans);
+        } else if let Some(level) = self.heading {
+            self.blocks.push(BlockKind::Heading { level, spans }.into());
+        } else if self.in_item && !self.lists.is_empty() {
+            self.blocks.push(
+                BlockKind::ListItem {
+                    depth: self.lists.len() - 1,
+                    ordered: *self.lists.last().unwrap(),
+                    spans,
+                }
+                .into(),
+            );
+        } else {
+            self.blocks.push(BlockKind::Paragraph { spans }.into());
+        }
+    }
+}
+
+fn attribute(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_text_skips_scripts_and_breaks_blocks() {
+        let html = "<html><head><title>T</title></head><body>\
+                    <h1>Heading</h1><p>First &amp; <b>bold</b>\n  text.</p>\
+                    <script>var x = 1;</script><p>Second&nbsp;para<br/>line</p></body></html>";
+        let text = html_to_text(html);
+        assert_eq!(text, "Heading\nFirst & bold text.\nSecond\u{a0}para\nline");
+    }
+
+    #[test]
+    fn test_html_to_markdown_blocks() {
+        let html = "<h2>Install</h2><p>Run <code>make</code> or see <a href=\"/docs\"><b>the docs</b></a>.</p>\
+                    <ul><li>One<ul><li>Nested</li></ul></li><li>Two</li></ul>\
+                    <table><tr><th>Key</th><th>Value</th></tr><tr><td>a</td><td><p>x</p><p>y</p></td></tr></table>\
+                    <pre><code class=\"language-sh\">make  install\n</code></pre>";
+        let markdown = crate::markdown::render(&html_to_blocks(html), &[]);
+        assert_eq!(
+            markdown,
+            "## Install\n\n\
+             Run `make` or see [**the docs**](/docs).\n\n\
+             - One\n   - Nested\n- Two\n\n\
+             | Key | Value |\n| --- | --- |\n| a | x<br>y |\n\n\
+             ```sh\nmake  install\n```"
+        );
+    }
+}
@@ -1,3 +1,16 @@
+pub mod archive;
+pub mod code;
+pub mod csv;
+pub mod doc;
 pub mod docx;
+pub mod email;
+pub mod epub;
+pub mod html;
+pub mod notebook;
 pub mod pdf;
+pub mod rtf;
+pub mod sniff;
+pub mod structured;
+pub mod subtitle;
 pub mod txt;
+pub mod xlsx;
@@ -0,0 +1,293 @@
+use crate::errors::{BlitzParseError, ErrorContext};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Notebook-level metadata from the `metadata` object.
+#[derive(Debug, Clone, Default)]
+pub struct NotebookMetadata {
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    /// Kernel language, from `kernelspec.language` or `language_info.name`.
+    pub language: Option<String>,
+    pub language_version: Option<String>,
+    pub kernel_name: Option<String>,
+    pub kernel_display_name: Option<String>,
+    /// Format version, e.g. `4.5`.
+    pub nbformat: String,
+}
+
+impl NotebookMetadata {
+    /// Metadata as the flat key/value map consumed by `GraphBuilder`.
+    /// Kernel details are left out so they stay properties of the Document.
+    pub fn to_map(&self) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        if let Some(title) = &self.title {
+            map.insert("title".to_string(), title.clone());
+        }
+        if !self.authors.is_empty() {
+            map.insert("author".to_string(), self.authors.join(", "));
+        }
+        if let Some(language) = &self.language {
+            map.insert("language".to_string(), language.clone());
+        }
+        map
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotebookCell {
+    /// `markdown`, `code` or `raw`.
+    pub cell_type: String,
+    pub source: String,
+    pub execution_count: Option<u64>,
+    /// Text outputs of a code cell: streams and `text/markdown` or `text/plain`
+    /// results. Images and other binary outputs are skipped.
+    pub outputs: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Notebook {
+    pub metadata: NotebookMetadata,
+    pub cells: Vec<NotebookCell>,
+}
+
+impl Notebook {
+    /// Cells in order separated by blank lines, optionally followed by their outputs.
+    pub fn text(&self, include_outputs: bool) -> String {
+        let mut parts = Vec::new();
+        for cell in &self.cells {
+            if !cell.source.trim().is_empty() {
+                parts.push(cell.source.trim_end().to_string());
+            }
+            if include_outputs {
+                parts.extend(cell.outputs.iter().map(|o| o.trim_end().to_string()));
+            }
+        }
+        parts.join("\n\n")
+    }
+}
+
+pub fn parse_ipynb(path: &str) -> Result<String, BlitzParseError> {
+    Ok(read_ipynb(path)?.text(false))
+}
+
+pub fn parse_ipynb_bytes(bytes: &[u8]) -> Result<String, BlitzParseError> {
+    Ok(read_ipynb_bytes(bytes)?.text(false))
+}
+
+pub fn read_ipynb(path: &str) -> Result<Notebook, BlitzParseError> {
+    let bytes = std::fs::read(path)?;
+    read_ipynb_bytes(&bytes)
+}
+
+/// Parse notebook JSON. Handles nbformat 4 and the older nbformat 3 layout,
+/// where cells live under `worksheets` and code is stored as `input`.
+pub fn read_ipynb_bytes(bytes: &[u8]) -> Result<Notebook, BlitzParseError> {
+    let root: Value = serde_json::from_slice(bytes)
+        .map_err(|e| BlitzParseError::corrupt("ipynb", ErrorContext::default(), e))?;
+    if !root.is_object() {
+        return Err(BlitzParseError::corrupt(
+            "ipynb",
+            ErrorContext::default(),
+            "Notebook must be a JSON object",
+        ));
+    }
+
+    let cells = match root.get("cells") {
+        Some(cells) => cells.as_array().cloned().unwrap_or_default(),
+        None => root["worksheets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|sheet| sheet["cells"].as_array().cloned().unwrap_or_default())
+            .collect(),
+    };
+
+    Ok(Notebook {
+        metadata: read_metadata(&root),
+        cells: cells.iter().filter_map(read_cell).collect(),
+    })
+}
+
+fn read_metadata(root: &Value) -> NotebookMetadata {
+    let meta = &root["metadata"];
+    let kernel = &meta["kernelspec"];
+    let language_info = &meta["language_info"];
+
+    let authors = match &meta["authors"] {
+        Value::Array(authors) => authors
+            .iter()
+            .filter_map(|a| a["name"].as_str().or(a.as_str()))
+            .map(str::to_string)
+            .collect(),
+        Value::String(author) => vec![author.clone()],
+        _ => Vec::new(),
+    };
+    let nbformat = match (root["nbformat"].as_u64(), root["nbformat_minor"].as_u64()) {
+        (Some(major), Some(minor)) => format!("{}.{}", major, minor),
+        (Some(major), None) => major.to_string(),
+        _ => String::new(),
+    };
+
+    NotebookMetadata {
+        title: string_at(&meta["title"]),
+        authors,
+        language: string_at(&kernel["language"]).or_else(|| string_at(&language_info["name"])),
+        language_version: string_at(&language_info["version"]),
+        kernel_name: string_at(&kernel["name"]),
+        kernel_display_name: string_at(&kernel["display_name"]),
+        nbformat,
+    }
+}
+
+fn read_cell(cell: &Value) -> Option<NotebookCell> {
+    let cell_type = match cell["cell_type"].as_str()? {
+        // nbformat 3 headings are markdown in later versions.
+        "heading" => {
+            let level = cell["level"].as_u64().unwrap_or(1).clamp(1, 6) as usize;
+            let source = multiline(cell.get("source")?);
+            return Some(NotebookCell {
+                cell_type: "markdown".to_string(),
+                source: format!("{} {}", "#".repeat(level), source),
+                execution_count: None,
+                outputs: Vec::new(),
+            });
+        }
+        other => other.to_string(),
+    };
+    let source = cell
+        .get("source")
+        .or_else(|| cell.get("input"))
+        .map(multiline)
+        .unwrap_or_default();
+    let execution_count = cell["execution_count"]
+        .as_u64()
+        .or_else(|| cell["prompt_number"].as_u64());
+    let outputs = cell["outputs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(read_output)
+        .filter(|text| !text.trim().is_empty())
+        .collect();
+
+    Some(NotebookCell {
+        cell_type,
+        source,
+        execution_count,
+        outputs,
+    })
+}
+
+fn read_output(output: &Value) -> Option<String> {
+    match output["output_type"].as_str()? {
+        "stream" => output.get("text").map(multiline),
+        "execute_result" | "display_data" | "pyout" => {
+            // nbformat 3 keeps MIME data on the output itself, keyed `text`.
+            let data = output.get("data").unwrap_or(output);
+            ["text/markdown", "text/plain", "text"]
+                .iter()
+                .find_map(|key| data.get(*key))
+                .map(multiline)
+        }
+        _ => None,
+    }
+}
+
+/// Notebook strings are stored either whole or as a list of lines.
+fn multiline(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+fn string_at(value: &Value) -> Option<String> {
+    value
+        .as_str()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+      "nbformat": 4,
+      "nbformat_minor": 5,
+      "metadata": {
+        "title": "Sales analysis",
+        "authors": [{"name": "Ada"}, {"name": "Grace"}],
+        "kernelspec": {"name": "python3", "display_name": "Python 3", "language": "python"},
+        "language_info": {"name": "python", "version": "3.11.4"}
+      },
+      "cells": [
+        {"cell_type": "markdown", "metadata": {}, "source": ["# Sales\n", "Quarterly numbers."]},
+        {"cell_type": "code", "execution_count": 3, "metadata": {},
+         "source": "df.describe()",
+         "outputs": [
+           {"output_type": "stream", "name": "stdout", "text": ["loading\n", "done\n"]},
+           {"output_type": "execute_result", "execution_count": 3,
+            "data": {"text/plain": ["   count\n", "0  42"], "text/html": "<table></table>"}},
+           {"output_type": "display_data",
+            "data": {"image/png": "iVBORw0KGgo=", "text/plain": "<Figure size 640x480>"}},
+           {"output_type": "error", "ename": "ValueError", "evalue": "x", "traceback": []}
+         ]},
+        {"cell_type": "raw", "metadata": {}, "source": "raw text"}
+      ]
+    }"##;
+
+    #[test]
+    fn test_cells_outputs_and_metadata() {
+        let notebook = read_ipynb_bytes(NOTEBOOK.as_bytes()).unwrap();
+        let meta = &notebook.metadata;
+        assert_eq!(meta.language.as_deref(), Some("python"));
+        assert_eq!(meta.language_version.as_deref(), Some("3.11.4"));
+        assert_eq!(meta.kernel_display_name.as_deref(), Some("Python 3"));
+        assert_eq!(meta.nbformat, "4.5");
+        assert_eq!(meta.to_map()["author"], "Ada, Grace");
+
+        let types: Vec<_> = notebook
+            .cells
+            .iter()
+            .map(|c| c.cell_type.as_str())
+            .collect();
+        assert_eq!(types, vec!["markdown", "code", "raw"]);
+        assert_eq!(notebook.cells[0].source, "# Sales\nQuarterly numbers.");
+        let code = &notebook.cells[1];
+        assert_eq!(code.execution_count, Some(3));
+        assert_eq!(
+            code.outputs,
+            vec![
+                "loading\ndone\n",
+                "   count\n0  42",
+                "<Figure size 640x480>"
+            ]
+        );
+
+        assert_eq!(
+            notebook.text(false),
+            "# Sales\nQuarterly numbers.\n\ndf.describe()\n\nraw text"
+        );
+        assert!(notebook
+            .text(true)
+            .contains("df.describe()\n\nloading\ndone"));
+    }
+
+    #[test]
+    fn test_nbformat3_worksheets() {
+        let json = r#"{"nbformat": 3, "metadata": {}, "worksheets": [{"cells": [
+            {"cell_type": "heading", "level": 2, "source": ["Intro"]},
+            {"cell_type": "code", "input": ["print(1)"], "prompt_number": 1,
+             "outputs": [{"output_type": "pyout", "text": ["1"]}]}
+        ]}]}"#;
+        let notebook = read_ipynb_bytes(json.as_bytes()).unwrap();
+        assert_eq!(notebook.cells[0].source, "## Intro");
+        assert_eq!(notebook.cells[1].source, "print(1)");
+        assert_eq!(notebook.cells[1].execution_count, Some(1));
+        assert_eq!(notebook.cells[1].outputs, vec!["1"]);
+    }
+}
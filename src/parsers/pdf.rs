@@ -1,6 +1,476 @@
-use crate::errors::BlitzParseError;
+use crate::document::{ParsedDocument, Partial, Warning, WarningKind};
+use crate::errors::{BlitzParseError, ErrorContext};
+use crate::options::ExtractOptions;
+use crate::provenance::{bytes_to_chars, Locator, Provenance};
+use crate::tree::{locate_blocks, Block, BlockKind, DocumentTree, Span};
 
 pub fn parse_pdf(path: &str) -> Result<String, BlitzParseError> {
-    let bytes = std::fs::read(path)?;
-    pdf_extract::extract_text_from_mem(&bytes).map_err(|e| BlitzParseError::Pdf(e.to_string()))
+    crate::input::with_file_bytes(path, parse_pdf_bytes)
+}
+
+pub fn parse_pdf_bytes(bytes: &[u8]) -> Result<String, BlitzParseError> {
+    parse_pdf_bytes_with(bytes, &ExtractOptions::default())
+}
+
+/// Extract a PDF page by page, enforcing `max_pages` up front, checking the
+/// deadline and cancellation between pages and reporting each page done.
+///
+/// A page that fails to extract doesn't stop the others; the text of the
+/// rest is returned in a `PartialSuccess` error.
+pub fn parse_pdf_bytes_with(
+    bytes: &[u8],
+    options: &ExtractOptions,
+) -> Result<String, BlitzParseError> {
+    let (document, failures) = read_pages(bytes, options)?;
+    document.into_text(failures)
+}
+
+/// Like `parse_pdf_bytes_with`, with each paragraph's page and the box
+/// around it as provenance. Paragraphs are separated by blank lines and
+/// never span pages. Pages that fail to extract become `Skipped` warnings.
+pub fn read_pdf_bytes_with(
+    bytes: &[u8],
+    options: &ExtractOptions,
+) -> Result<ParsedDocument, BlitzParseError> {
+    read_pages(bytes, options).map(|(document, failures)| document.with_failures(&failures))
+}
+
+/// Every page that can be extracted, failing only if none can.
+fn read_pages(bytes: &[u8], options: &ExtractOptions) -> Result<Partial, BlitzParseError> {
+    let doc = load_document(bytes)?;
+    lay_out(&doc, options, |_, _, _| {})
+}
+
+/// Lay every page of `doc` out into one text, with the provenance of each
+/// paragraph. `page_done` is called with each page that extracted whole,
+/// the layout so far and the byte offset where that page's text begins.
+fn lay_out(
+    doc: &pdf_extract::Document,
+    options: &ExtractOptions,
+    mut page_done: impl FnMut(u32, &Layout, usize),
+) -> Result<Partial, BlitzParseError> {
+    let pages = doc.get_pages();
+    options.check_pages(pages.len())?;
+
+    let mut layout = Layout::default();
+    let mut provenance = Vec::new();
+    let mut failures = Vec::new();
+    let total = pages.len() as u64;
+    for (done, page) in pages.keys().enumerate() {
+        options.checkpoint()?;
+        let (first_line, page_start) = (layout.lines.len(), layout.text.len());
+        let result = pdf_extract::output_doc_page(doc, &mut layout, *page);
+        layout.end_line();
+        // A page that failed part way keeps the text it had, as before.
+        for (start, end, bbox) in layout.paragraphs(first_line) {
+            provenance.push(Provenance {
+                start,
+                end,
+                locator: Locator::Pdf {
+                    page: *page,
+                    bbox: Some(bbox),
+                },
+            });
+        }
+        match result {
+            Ok(()) => page_done(*page, &layout, page_start),
+            Err(e) => failures.push(BlitzParseError::CorruptContainer {
+                format: "pdf",
+                context: ErrorContext::part(format!("page {}", page)),
+                source: Some(Box::new(e)),
+            }),
+        }
+        options.report("pdf", "pages", done as u64 + 1, Some(total));
+    }
+
+    if !failures.is_empty() && failures.len() as u64 == total {
+        return Err(failures.swap_remove(0));
+    }
+    let text = layout.text;
+    bytes_to_chars(&text, &mut provenance);
+    let document = ParsedDocument {
+        text,
+        provenance,
+        ..Default::default()
+    };
+    Ok((document, failures))
+}
+
+/// The text of each page of a PDF in turn, laid out as `lay_out` lays out
+/// the whole document, so that the pages concatenate to its text.
+pub(crate) struct PageTexts {
+    doc: pdf_extract::Document,
+    pages: std::vec::IntoIter<u32>,
+    layout: Layout,
+    total: u64,
+    done: u64,
+    options: ExtractOptions,
+}
+
+impl PageTexts {
+    /// Enforces `max_pages` up front.
+    pub(crate) fn new(
+        doc: pdf_extract::Document,
+        options: &ExtractOptions,
+    ) -> Result<PageTexts, BlitzParseError> {
+        let pages: Vec<u32> = doc.get_pages().keys().copied().collect();
+        options.check_pages(pages.len())?;
+        Ok(PageTexts {
+            doc,
+            total: pages.len() as u64,
+            pages: pages.into_iter(),
+            layout: Layout::default(),
+            done: 0,
+            options: options.clone(),
+        })
+    }
+}
+
+impl Iterator for PageTexts {
+    type Item = Result<String, BlitzParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let page = self.pages.next()?;
+        let result = pdf_extract::output_doc_page(&self.doc, &mut self.layout, page);
+        self.layout.end_line();
+        // Only the text is needed; the position carries on to the next page.
+        let text = std::mem::take(&mut self.layout.text);
+        self.layout.lines.clear();
+        self.layout.line_start = 0;
+        self.done += 1;
+        self.options
+            .report("pdf", "pages", self.done, Some(self.total));
+        Some(
+            result
+                .map(|_| text)
+                .map_err(|e| BlitzParseError::CorruptContainer {
+                    format: "pdf",
+                    context: ErrorContext::part(format!("page {}", page)),
+                    source: Some(Box::new(e)),
+                }),
+        )
+    }
+}
+
+/// Text laid out exactly as `pdf_extract::PlainTextOutput` lays it out,
+/// along with the box around each line of it.
+struct Layout {
+    text: String,
+    /// Byte range of each line and the box around its characters, as
+    /// `[x0, y0, x1, y1]` in PDF user space.
+    lines: Vec<(usize, usize, [f64; 4])>,
+    line_start: usize,
+    bbox: Option<[f64; 4]>,
+    /// Page height, for the top-down coordinates the layout works in.
+    height: f64,
+    last_end: f64,
+    last_y: f64,
+    first_char: bool,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        // The starting state of `PlainTextOutput`.
+        Layout {
+            text: String::new(),
+            lines: Vec::new(),
+            line_start: 0,
+            bbox: None,
+            height: 0.,
+            last_end: 100000.,
+            last_y: 0.,
+            first_char: false,
+        }
+    }
+}
+
+impl Layout {
+    fn end_line(&mut self) {
+        if let Some(bbox) = self.bbox.take() {
+            self.lines.push((self.line_start, self.text.len(), bbox));
+        }
+        self.line_start = self.text.len();
+    }
+
+    fn newline(&mut self) {
+        self.end_line();
+        self.text.push('\n');
+        self.line_start = self.text.len();
+    }
+
+    /// Paragraphs of the lines from `first_line` on: runs of lines with no
+    /// blank line between them, trimmed, with the union of their boxes.
+    fn paragraphs(&self, first_line: usize) -> Vec<(usize, usize, [f64; 4])> {
+        let mut paragraphs: Vec<(usize, usize, [f64; 4])> = Vec::new();
+        let mut previous_end = None;
+        for &(start, end, bbox) in &self.lines[first_line..] {
+            let line = &self.text[start..end];
+            if line.trim().is_empty() {
+                previous_end = None;
+                continue;
+            }
+            let start = start + (line.len() - line.trim_start().len());
+            let end = start + line.trim().len();
+            let joined = previous_end
+                .is_some_and(|previous| self.text[previous..start].matches('\n').count() < 2);
+            match paragraphs.last_mut() {
+                Some(paragraph) if joined => {
+                    paragraph.1 = end;
+                    paragraph.2 = union(paragraph.2, bbox);
+                }
+                _ => paragraphs.push((start, end, bbox)),
+            }
+            previous_end = Some(end);
+        }
+        paragraphs
+    }
+
+    /// The box of each line of `text[from..].lines()`, if it has characters.
+    fn line_boxes(&self, from: usize) -> Vec<Option<[f64; 4]>> {
+        let mut boxes = Vec::new();
+        let mut lines = self
+            .lines
+            .iter()
+            .skip_while(|(line_start, _, _)| *line_start < from)
+            .peekable();
+        let mut start = from;
+        for line in self.text[from..].split('\n') {
+            boxes.push(
+                lines
+                    .next_if(|(line_start, _, _)| *line_start == start)
+                    .map(|line| line.2),
+            );
+            start += line.len() + 1;
+        }
+        boxes
+    }
+}
+
+fn union(a: [f64; 4], b: [f64; 4]) -> [f64; 4] {
+    [
+        a[0].min(b[0]),
+        a[1].min(b[1]),
+        a[2].max(b[2]),
+        a[3].max(b[3]),
+    ]
+}
+
+impl pdf_extract::OutputDev for Layout {
+    fn begin_page(
+        &mut self,
+        _page_num: u32,
+        media_box: &pdf_extract::MediaBox,
+        _: Option<(f64, f64, f64, f64)>,
+    ) -> Result<(), pdf_extract::OutputError> {
+        self.end_line();
+        self.height = media_box.ury - media_box.lly;
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), pdf_extract::OutputError> {
+        Ok(())
+    }
+
+    fn output_character(
+        &mut self,
+        trm: &pdf_extract::Transform,
+        width: f64,
+        _spacing: f64,
+        font_size: f64,
+        char: &str,
+    ) -> Result<(), pdf_extract::OutputError> {
+        // The side of the square with the area of the transformed font size.
+        let size = ((font_size * trm.m11 + font_size * trm.m21)
+            * (font_size * trm.m12 + font_size * trm.m22))
+            .sqrt();
+        let (x, y) = (trm.m31, self.height - trm.m32);
+        if self.first_char {
+            if (y - self.last_y).abs() > size * 1.5 {
+                self.newline();
+            }
+            // Moved left and down.
+            if x < self.last_end && (y - self.last_y).abs() > size * 0.5 {
+                self.newline();
+            }
+            if x > self.last_end + size * 0.1 {
+                self.text.push(' ');
+            }
+        }
+        self.text.push_str(char);
+        self.first_char = false;
+        self.last_y = y;
+        self.last_end = x + width * size;
+
+        let (x0, x1) = (x.min(self.last_end), x.max(self.last_end));
+        let (y0, y1) = (trm.m32.min(trm.m32 + size), trm.m32.max(trm.m32 + size));
+        if [x0, y0, x1, y1].iter().all(|v| v.is_finite()) {
+            let bbox = [x0, y0, x1, y1];
+            self.bbox = Some(self.bbox.map_or(bbox, |b| union(b, bbox)));
+        }
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), pdf_extract::OutputError> {
+        self.first_char = true;
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), pdf_extract::OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), pdf_extract::OutputError> {
+        Ok(())
+    }
+}
+
+/// Read a PDF into paragraphs, with headings taken from its outline
+/// (bookmarks). A line of a page matching an outline title becomes that
+/// heading; a title not found on its page is put at the top of the page.
+/// Blocks are located by page and the box around their lines.
+///
+/// Pages that fail to extract are skipped with a warning, unless all of
+/// them do.
+pub fn pdf_to_tree(
+    bytes: &[u8],
+    options: &ExtractOptions,
+) -> Result<DocumentTree, BlitzParseError> {
+    let doc = load_document(bytes)?;
+    // Documents without an outline are rendered as paragraphs only.
+    let outline = doc.get_toc().map(|toc| toc.toc).unwrap_or_default();
+
+    // Pages are laid out as `read_pdf_bytes_with` lays them out, so each
+    // block can be given its offsets in that text.
+    let mut blocks = Vec::new();
+    let (document, failures) = lay_out(&doc, options, |page, layout, page_start| {
+        let headings: Vec<_> = outline
+            .iter()
+            .filter(|entry| entry.page == page as usize)
+            .map(|entry| (entry.level, entry.title.trim()))
+            .collect();
+        let start = blocks.len();
+        page_blocks(
+            &layout.text[page_start..],
+            &layout.line_boxes(page_start),
+            &headings,
+            &mut blocks,
+        );
+        for block in &mut blocks[start..] {
+            if let Some(Locator::Pdf { page: p, .. }) = &mut block.source {
+                *p = page;
+            }
+        }
+    })?;
+    locate_blocks(&mut blocks, &document.provenance);
+
+    let mut tree = DocumentTree::new(blocks);
+    tree.warnings = failures
+        .iter()
+        .map(|e| Warning::from_error(WarningKind::Skipped, e))
+        .collect();
+    Ok(tree)
+}
+
+/// Split a page into paragraphs at blank lines, turning lines that match an
+/// outline title into headings. `boxes` holds the box of each line; blocks
+/// are located by the union of theirs, with page 0 for the caller to set.
+fn page_blocks(
+    text: &str,
+    boxes: &[Option<[f64; 4]>],
+    headings: &[(usize, &str)],
+    blocks: &mut Vec<Block>,
+) {
+    let collapse = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+    let located = |kind: BlockKind, bbox: Option<[f64; 4]>| Block {
+        source: Some(Locator::Pdf { page: 0, bbox }),
+        ..Block::from(kind)
+    };
+    let heading = |level: usize, title: &str| BlockKind::Heading {
+        level,
+        spans: vec![Span::plain(title)],
+    };
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    let mut found = vec![false; headings.len()];
+    let mut line_headings = vec![None; lines.len()];
+    for (i, (level, title)) in headings.iter().enumerate() {
+        let title = collapse(title);
+        let line =
+            (0..lines.len()).find(|&l| line_headings[l].is_none() && collapse(lines[l]) == title);
+        if let Some(l) = line {
+            line_headings[l] = Some(*level);
+            found[i] = true;
+        }
+    }
+    for (i, (level, title)) in headings.iter().enumerate() {
+        if !found[i] {
+            blocks.push(located(heading(*level, title), None));
+        }
+    }
+
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut bbox: Option<[f64; 4]> = None;
+    let flush =
+        |paragraph: &mut Vec<&str>, bbox: &mut Option<[f64; 4]>, blocks: &mut Vec<Block>| {
+            if !paragraph.is_empty() {
+                let spans = vec![Span::plain(paragraph.join("\n"))];
+                blocks.push(located(BlockKind::Paragraph { spans }, bbox.take()));
+                paragraph.clear();
+            }
+        };
+    for (i, (line, level)) in lines.iter().zip(&line_headings).enumerate() {
+        let line_box = boxes.get(i).copied().flatten();
+        if let Some(level) = level {
+            flush(&mut paragraph, &mut bbox, blocks);
+            blocks.push(located(heading(*level, line), line_box));
+        } else if line.is_empty() {
+            flush(&mut paragraph, &mut bbox, blocks);
+        } else {
+            paragraph.push(line);
+            bbox = match (bbox, line_box) {
+                (Some(a), Some(b)) => Some(union(a, b)),
+                (a, b) => a.or(b),
+            };
+        }
+    }
+    flush(&mut paragraph, &mut bbox, blocks);
+}
+
+/// Load a PDF, decrypting it if it only has an empty user password.
+pub(crate) fn load_document(bytes: &[u8]) -> Result<pdf_extract::Document, BlitzParseError> {
+    let mut doc =
+        pdf_extract::Document::load_mem(bytes).map_err(|e| BlitzParseError::CorruptContainer {
+            format: "pdf",
+            context: ErrorContext::default(),
+            source: Some(Box::new(e)),
+        })?;
+    if doc.is_encrypted() {
+        // Many PDFs are encrypted with an empty user password.
+        doc.decrypt("").map_err(|e| BlitzParseError::Encrypted {
+            format: "pdf",
+            context: ErrorContext::default(),
+            source: Some(Box::new(e)),
+        })?;
+    }
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outline_titles_become_headings() {
+        let mut blocks = Vec::new();
+        let text = "Intro  Text\nbody line\n\nnext para\n";
+        page_blocks(
+            text,
+            &[],
+            &[(1, "Chapter 1"), (2, "Intro Text")],
+            &mut blocks,
+        );
+        assert_eq!(
+            crate::markdown::render(&blocks, &[]),
+            "# Chapter 1\n\n## Intro  Text\n\nbody line\n\nnext para"
+        );
+    }
 }
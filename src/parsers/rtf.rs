@@ -0,0 +1,428 @@
+use crate::errors::{BlitzParseError, ErrorContext};
+use encoding_rs::{Encoding, WINDOWS_1252};
+
+pub fn parse_rtf(path: &str) -> Result<String, BlitzParseError> {
+    let bytes = std::fs::read(path)?;
+    rtf_to_text(&bytes)
+}
+
+/// Destinations whose content is never part of the document text.
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "fonttbl",
+    "colortbl",
+    "stylesheet",
+    "listtable",
+    "listoverridetable",
+    "revtbl",
+    "rsidtbl",
+    "filetbl",
+    "info",
+    "pict",
+    "object",
+    "themedata",
+    "colorschememapping",
+    "datastore",
+    "latentstyles",
+    "xmlnstbl",
+    "generator",
+    "fldinst",
+    "header",
+    "headerl",
+    "headerr",
+    "headerf",
+    "footer",
+    "footerl",
+    "footerr",
+    "footerf",
+];
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    GroupStart,
+    GroupEnd,
+    ControlWord(&'a str, Option<i32>),
+    ControlSymbol(u8),
+    Hex(u8),
+    Text(u8),
+}
+
+/// Splits RTF input into tokens. `\binN` payloads are skipped here, since
+/// they are the only construct that cannot be tokenized without context.
+struct Tokenizer<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Tokenizer { input, pos: 0 }
+    }
+
+    fn next_token(&mut self) -> Option<Token<'a>> {
+        loop {
+            let byte = *self.input.get(self.pos)?;
+            self.pos += 1;
+            return Some(match byte {
+                b'{' => Token::GroupStart,
+                b'}' => Token::GroupEnd,
+                b'\\' => self.control(),
+                // Raw line breaks are formatting only.
+                b'\r' | b'\n' => continue,
+                _ => Token::Text(byte),
+            });
+        }
+    }
+
+    fn control(&mut self) -> Token<'a> {
+        let Some(&first) = self.input.get(self.pos) else {
+            return Token::ControlSymbol(b'\\');
+        };
+
+        if !first.is_ascii_alphabetic() {
+            self.pos += 1;
+            if first == b'\'' {
+                let hex = self.input.get(self.pos..self.pos + 2).and_then(|h| {
+                    std::str::from_utf8(h)
+                        .ok()
+                        .and_then(|h| u8::from_str_radix(h, 16).ok())
+                });
+                if let Some(value) = hex {
+                    self.pos += 2;
+                    return Token::Hex(value);
+                }
+            }
+            // `\` followed by a line break is an implicit `\par`.
+            if first == b'\n' || first == b'\r' {
+                return Token::ControlWord("par", None);
+            }
+            return Token::ControlSymbol(first);
+        }
+
+        let start = self.pos;
+        while self
+            .input
+            .get(self.pos)
+            .is_some_and(u8::is_ascii_alphabetic)
+        {
+            self.pos += 1;
+        }
+        // Control words are ASCII letters, so this cannot fail.
+        let name = std::str::from_utf8(&self.input[start..self.pos]).unwrap_or_default();
+
+        let param_start = self.pos;
+        if self.input.get(self.pos) == Some(&b'-') {
+            self.pos += 1;
+        }
+        while self.input.get(self.pos).is_some_and(u8::is_ascii_digit) {
+            self.pos += 1;
+        }
+        let param = std::str::from_utf8(&self.input[param_start..self.pos])
+            .ok()
+            .and_then(|p| p.parse::<i32>().ok());
+        if param.is_none() {
+            self.pos = param_start;
+        }
+
+        // A single space delimits the control word and is not part of the text.
+        if self.input.get(self.pos) == Some(&b' ') {
+            self.pos += 1;
+        }
+
+        if name == "bin" {
+            let len = param.unwrap_or(0).max(0) as usize;
+            self.pos = (self.pos + len).min(self.input.len());
+        }
+
+        Token::ControlWord(name, param)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct GroupState {
+    skip: bool,
+    /// Number of fallback characters that follow each `\uN`.
+    uc: usize,
+}
+
+struct RtfConverter {
+    encoding: &'static Encoding,
+    output: String,
+    /// Raw codepage bytes awaiting decoding, so multi-byte sequences stay intact.
+    pending: Vec<u8>,
+    /// Fallback characters still to be dropped after a `\uN`.
+    skip_fallback: usize,
+    /// The high half of a surrogate pair, waiting for the `\uN` with the low half.
+    high_surrogate: Option<u32>,
+    in_table: bool,
+    row_cells: Vec<String>,
+    cell: String,
+}
+
+impl RtfConverter {
+    fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let (text, _) = self.encoding.decode_without_bom_handling(&self.pending);
+        let target = if self.in_table {
+            &mut self.cell
+        } else {
+            &mut self.output
+        };
+        target.push_str(&text);
+        self.pending.clear();
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.flush();
+        if self.high_surrogate.take().is_some() {
+            self.push_target('\u{fffd}');
+        }
+        if self.in_table {
+            self.cell.push_str(s);
+        } else {
+            self.output.push_str(s);
+        }
+    }
+
+    fn push_byte(&mut self, byte: u8) {
+        if self.skip_fallback > 0 {
+            self.skip_fallback -= 1;
+        } else {
+            if self.high_surrogate.take().is_some() {
+                self.push_str("\u{fffd}");
+            }
+            self.pending.push(byte);
+        }
+    }
+
+    /// Write the character of a `\uN` code unit, pairing UTF-16 surrogates
+    /// as RTF writes characters outside the Basic Multilingual Plane.
+    fn push_unicode(&mut self, code: u32) {
+        let c = match code {
+            0xd800..=0xdbff => {
+                if self.high_surrogate.replace(code).is_some() {
+                    self.flush();
+                    self.push_target('\u{fffd}');
+                }
+                return;
+            }
+            0xdc00..=0xdfff => match self.high_surrogate.take() {
+                Some(high) => char::from_u32(0x10000 + ((high - 0xd800) << 10) + (code - 0xdc00)),
+                None => None,
+            },
+            _ => char::from_u32(code),
+        };
+        self.push_str(c.unwrap_or('\u{fffd}').encode_utf8(&mut [0; 4]));
+    }
+
+    fn push_target(&mut self, c: char) {
+        if self.in_table {
+            self.cell.push(c);
+        } else {
+            self.output.push(c);
+        }
+    }
+
+    fn end_cell(&mut self) {
+        self.flush();
+        let cell = std::mem::take(&mut self.cell);
+        self.row_cells.push(cell.trim().to_string());
+    }
+
+    fn end_row(&mut self) {
+        self.flush();
+        if !self.cell.trim().is_empty() {
+            self.end_cell();
+        }
+        if !self.row_cells.is_empty() {
+            self.output.push_str("\n| ");
+            self.output.push_str(&self.row_cells.join(" | "));
+            self.output.push_str(" |\n");
+        }
+        self.row_cells.clear();
+        self.cell.clear();
+        self.in_table = false;
+    }
+}
+
+/// Convert RTF bytes into plain text.
+///
+/// Paragraphs become lines and table rows are rendered as `| a | b |`,
+/// matching the DOCX parser's output.
+pub fn rtf_to_text(bytes: &[u8]) -> Result<String, BlitzParseError> {
+    if !bytes.trim_ascii_start().starts_with(b"{\\rtf") {
+        return Err(BlitzParseError::MissingPart {
+            format: "rtf",
+            context: ErrorContext::part("{\\rtf header"),
+        });
+    }
+
+    let mut tokenizer = Tokenizer::new(bytes);
+    let mut converter = RtfConverter {
+        encoding: WINDOWS_1252,
+        output: String::with_capacity(bytes.len() / 2),
+        pending: Vec::new(),
+        skip_fallback: 0,
+        high_surrogate: None,
+        in_table: false,
+        row_cells: Vec::new(),
+        cell: String::new(),
+    };
+    let mut stack: Vec<GroupState> = Vec::new();
+    let mut state = GroupState { skip: false, uc: 1 };
+    // Set right after `{` so the first control word can mark a destination.
+    let mut at_group_start = false;
+
+    while let Some(token) = tokenizer.next_token() {
+        let group_start = std::mem::replace(&mut at_group_start, false);
+        match token {
+            Token::GroupStart => {
+                converter.flush();
+                converter.skip_fallback = 0;
+                stack.push(state);
+                at_group_start = true;
+            }
+            Token::GroupEnd => {
+                converter.flush();
+                converter.skip_fallback = 0;
+                // Tolerate unbalanced closing braces.
+                state = stack.pop().unwrap_or(state);
+            }
+            _ if state.skip => {}
+            Token::Text(byte) | Token::Hex(byte) => converter.push_byte(byte),
+            Token::ControlSymbol(symbol) => match symbol {
+                // `{\*\dest ...}` marks an ignorable destination.
+                b'*' if group_start => state.skip = true,
+                b'~' => converter.push_str("\u{a0}"),
+                b'_' => converter.push_str("\u{2011}"),
+                b'\\' | b'{' | b'}' => converter.push_byte(symbol),
+                _ => {}
+            },
+            Token::ControlWord(name, param) => {
+                if group_start && SKIPPED_DESTINATIONS.contains(&name) {
+                    state.skip = true;
+                    continue;
+                }
+                match name {
+                    "ansicpg" => {
+                        converter.flush();
+                        converter.encoding = codepage_encoding(param.unwrap_or(1252));
+                    }
+                    "uc" => state.uc = param.unwrap_or(1).max(0) as usize,
+                    "u" => {
+                        if let Some(code) = param {
+                            // Values above 32767 are written as negative numbers.
+                            let code = if code < 0 { code + 65536 } else { code } as u32;
+                            converter.push_unicode(code);
+                            converter.skip_fallback = state.uc;
+                        }
+                    }
+                    "par" | "line" | "sect" | "page" => {
+                        if converter.in_table {
+                            converter.push_str(" ");
+                        } else {
+                            converter.push_str("\n");
+                        }
+                    }
+                    "tab" => converter.push_str("\t"),
+                    "trowd" | "intbl" => {
+                        converter.flush();
+                        converter.in_table = true;
+                    }
+                    "cell" => {
+                        converter.in_table = true;
+                        converter.end_cell();
+                    }
+                    "row" => converter.end_row(),
+                    "emdash" => converter.push_str("\u{2014}"),
+                    "endash" => converter.push_str("\u{2013}"),
+                    "bullet" => converter.push_str("\u{2022}"),
+                    "lquote" => converter.push_str("\u{2018}"),
+                    "rquote" => converter.push_str("\u{2019}"),
+                    "ldblquote" => converter.push_str("\u{201c}"),
+                    "rdblquote" => converter.push_str("\u{201d}"),
+                    _ => {}
+                }
+            }
+        }
+    }
+    converter.flush();
+    if converter.high_surrogate.take().is_some() {
+        converter.push_target('\u{fffd}');
+    }
+    if !converter.row_cells.is_empty() {
+        converter.end_row();
+    }
+
+    // Normalize whitespace
+    let result = converter
+        .output
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(result)
+}
+
+/// Map a Windows codepage number from `\ansicpg` to an encoding.
+fn codepage_encoding(codepage: i32) -> &'static Encoding {
+    let label = match codepage {
+        874 | 1250..=1258 => format!("windows-{codepage}"),
+        932 => "shift_jis".to_string(),
+        936 => "gbk".to_string(),
+        949 => "euc-kr".to_string(),
+        950 => "big5".to_string(),
+        10000 => "macintosh".to_string(),
+        20866 => "koi8-r".to_string(),
+        65001 => "utf-8".to_string(),
+        _ => "windows-1252".to_string(),
+    };
+    Encoding::for_label(label.as_bytes()).unwrap_or(WINDOWS_1252)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rtf_paragraphs_escapes_and_destinations() {
+        let rtf = br"{\rtf1\ansi\ansicpg1252\deff0{\fonttbl{\f0\fswiss Helvetica;}}
+{\colortbl;\red0\green0\blue0;}{\*\generator Riched20;}
+{\info{\title Secret title}}
+\pard Caf\'e9 \b bold\b0  text.\par
+Unicode: \u8364?\uc2\u8211\'96\'96 done.\par
+{\pict\pngblip 89504e47}Visible\par}";
+        let text = rtf_to_text(rtf).unwrap();
+        assert_eq!(text, "Café bold text.\nUnicode: €– done.\nVisible");
+    }
+
+    #[test]
+    fn test_rtf_surrogate_pairs() {
+        let rtf = br"{\rtf1\ansi Smile \u-10179?\u-8704? and \u55357?x}";
+        assert_eq!(rtf_to_text(rtf).unwrap(), "Smile \u{1f600} and \u{fffd}x");
+    }
+
+    #[test]
+    fn test_rtf_tables() {
+        let rtf = br"{\rtf1\ansi Before\par
+\trowd\cellx1000\cellx2000\intbl A1\cell A2\cell\row
+\trowd\cellx1000\cellx2000\intbl B1\cell B2\cell\row
+\pard After\par}";
+        let text = rtf_to_text(rtf).unwrap();
+        assert_eq!(text, "Before\n| A1 | A2 |\n| B1 | B2 |\nAfter");
+    }
+
+    #[test]
+    fn test_rtf_codepage_escapes() {
+        let rtf = br"{\rtf1\ansi\ansicpg1251 \'cf\'f0\'e8\'e2\'e5\'f2}";
+        assert_eq!(rtf_to_text(rtf).unwrap(), "Привет");
+    }
+
+    #[test]
+    fn test_rejects_non_rtf() {
+        assert!(rtf_to_text(b"plain text").is_err());
+    }
+}
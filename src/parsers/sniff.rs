@@ -0,0 +1,48 @@
+use std::io::Cursor;
+
+const CFB_SIGNATURE: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+/// Guess a file's format from its leading bytes, returning the extension
+/// `extract_text_from_bytes` expects. Used when a file has no usable extension.
+pub fn sniff_format(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"%PDF-") {
+        return Some("pdf");
+    }
+    if bytes.trim_ascii_start().starts_with(b"{\\rtf") {
+        return Some("rtf");
+    }
+    if bytes.starts_with(CFB_SIGNATURE) {
+        return Some("doc");
+    }
+    if bytes.starts_with(&[0x1F, 0x8B]) {
+        return Some("gz");
+    }
+    if bytes.get(257..262) == Some(b"ustar") {
+        return Some("tar");
+    }
+    if bytes.starts_with(b"PK\x03\x04") {
+        return Some(sniff_zip(bytes));
+    }
+    // Treat NUL-free UTF-8 as plain text.
+    if !bytes.contains(&0) && std::str::from_utf8(bytes).is_ok() {
+        return Some("txt");
+    }
+    None
+}
+
+/// Tell OOXML and EPUB packages apart from plain ZIP archives.
+fn sniff_zip(bytes: &[u8]) -> &'static str {
+    let Ok(archive) = zip::ZipArchive::new(Cursor::new(bytes)) else {
+        return "zip";
+    };
+    let mut names = archive.file_names();
+    if names.any(|n| n == "word/document.xml") {
+        "docx"
+    } else if archive.file_names().any(|n| n == "xl/workbook.xml") {
+        "xlsx"
+    } else if archive.file_names().any(|n| n == "META-INF/container.xml") {
+        "epub"
+    } else {
+        "zip"
+    }
+}
@@ -0,0 +1,660 @@
+use crate::errors::{BlitzParseError, ErrorContext};
+use crate::options::ExtractOptions;
+use crate::parsers::csv::format_record;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Controls how JSON, JSONL and XML are flattened into `path: value` text.
+///
+/// Paths are written JSON-pointer style (`/user/address/city`) for both JSON
+/// and XML, where attributes appear as `@name` segments. A `*` segment matches
+/// any key, and array indices may be omitted: `/users/name` matches
+/// `/users/3/name`. Patterns match whole subtrees, so `/user` covers
+/// everything below it.
+#[derive(Debug, Clone, Default)]
+pub struct FlattenOptions {
+    /// If non-empty, only values under one of these paths are kept.
+    pub include: Vec<String>,
+    /// Values under any of these paths are dropped.
+    pub exclude: Vec<String>,
+    /// Treat each element of the array (JSON) or each matching element (XML)
+    /// at this path as one record, rendered on its own line. `"/"` is the root.
+    pub records_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// A flattened leaf: its path and scalar value.
+type Leaf = (Vec<Segment>, String);
+
+/// The include and exclude paths of `FlattenOptions`, split into segments once.
+struct Patterns {
+    include: Vec<Vec<String>>,
+    exclude: Vec<Vec<String>>,
+}
+
+impl Patterns {
+    fn new(options: &FlattenOptions) -> Self {
+        let split = |patterns: &[String]| patterns.iter().map(|p| split_pointer(p)).collect();
+        Patterns {
+            include: split(&options.include),
+            exclude: split(&options.exclude),
+        }
+    }
+}
+
+/// Flatten a `.json`, `.jsonl`/`.ndjson` or `.xml` file with default options.
+pub fn parse_structured(path: &str) -> Result<String, BlitzParseError> {
+    extract_structured(path, &FlattenOptions::default())
+}
+
+/// Flatten a `.json`, `.jsonl`/`.ndjson` or `.xml` file with explicit options.
+pub fn extract_structured(path: &str, options: &FlattenOptions) -> Result<String, BlitzParseError> {
+    extract_structured_with(path, options, &ExtractOptions::default())
+}
+
+/// Like `extract_structured`, enforcing the limits `flatten_bytes_with` does.
+pub fn extract_structured_with(
+    path: &str,
+    options: &FlattenOptions,
+    limits: &ExtractOptions,
+) -> Result<String, BlitzParseError> {
+    let bytes = std::fs::read(path)?;
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    flatten_bytes_with(&bytes, extension, options, limits)
+}
+
+pub fn flatten_bytes(
+    bytes: &[u8],
+    extension: &str,
+    options: &FlattenOptions,
+) -> Result<String, BlitzParseError> {
+    flatten_bytes_with(bytes, extension, options, &ExtractOptions::default())
+}
+
+/// Like `flatten_bytes`, enforcing the XML depth, element and deadline limits.
+pub fn flatten_bytes_with(
+    bytes: &[u8],
+    extension: &str,
+    options: &FlattenOptions,
+    limits: &ExtractOptions,
+) -> Result<String, BlitzParseError> {
+    let records = flatten(bytes, extension, options, limits)?;
+    Ok(render(&records, options))
+}
+
+/// Flattened records as `(path, value)` pairs, for building record nodes.
+pub fn flatten_records(
+    bytes: &[u8],
+    extension: &str,
+    options: &FlattenOptions,
+) -> Result<Vec<Vec<(String, String)>>, BlitzParseError> {
+    let records = flatten(bytes, extension, options, &ExtractOptions::default())?;
+    Ok(records
+        .into_iter()
+        .map(|leaves| {
+            leaves
+                .into_iter()
+                .map(|(path, value)| (display_path(&path), value))
+                .collect()
+        })
+        .collect())
+}
+
+fn flatten(
+    bytes: &[u8],
+    extension: &str,
+    options: &FlattenOptions,
+    limits: &ExtractOptions,
+) -> Result<Vec<Vec<Leaf>>, BlitzParseError> {
+    let patterns = Patterns::new(options);
+    match extension.to_lowercase().as_str() {
+        "json" => Ok(flatten_json(&parse_json_value(bytes)?, options, &patterns)),
+        "jsonl" | "ndjson" => flatten_jsonl(bytes, options, &patterns),
+        "xml" => flatten_xml(bytes, options, &patterns, limits),
+        other => Err(BlitzParseError::unsupported(other)),
+    }
+}
+
+fn parse_json_value(bytes: &[u8]) -> Result<Value, BlitzParseError> {
+    let (text, _, _) = encoding_rs::UTF_8.decode(bytes);
+    serde_json::from_str(&text)
+        .map_err(|e| BlitzParseError::corrupt("json", ErrorContext::default(), e))
+}
+
+/// Records are rendered one per line; without a record path every leaf gets its own line.
+fn render(records: &[Vec<Leaf>], options: &FlattenOptions) -> String {
+    let lines: Vec<String> = if options.records_at.is_some() {
+        records
+            .iter()
+            .map(|leaves| {
+                let record: Vec<(String, String)> = leaves
+                    .iter()
+                    .map(|(path, value)| (display_path(path), value.clone()))
+                    .collect();
+                format_record(&record)
+            })
+            .filter(|line| !line.is_empty())
+            .collect()
+    } else {
+        records
+            .iter()
+            .flatten()
+            .map(|(path, value)| format!("{}: {}", display_path(path), value))
+            .collect()
+    };
+    lines.join("\n")
+}
+
+fn flatten_json(value: &Value, options: &FlattenOptions, patterns: &Patterns) -> Vec<Vec<Leaf>> {
+    match &options.records_at {
+        Some(pointer) => {
+            let pointer = if pointer == "/" { "" } else { pointer.as_str() };
+            match value.pointer(pointer) {
+                Some(Value::Array(items)) => items
+                    .iter()
+                    .map(|item| {
+                        let mut leaves = Vec::new();
+                        flatten_value(item, &mut Vec::new(), patterns, &mut leaves);
+                        leaves
+                    })
+                    .collect(),
+                Some(other) => {
+                    let mut leaves = Vec::new();
+                    flatten_value(other, &mut Vec::new(), patterns, &mut leaves);
+                    vec![leaves]
+                }
+                None => Vec::new(),
+            }
+        }
+        None => {
+            let mut leaves = Vec::new();
+            flatten_value(value, &mut Vec::new(), patterns, &mut leaves);
+            vec![leaves]
+        }
+    }
+}
+
+/// JSON Lines: every line is its own record, numbered when not in record mode.
+fn flatten_jsonl(
+    bytes: &[u8],
+    options: &FlattenOptions,
+    patterns: &Patterns,
+) -> Result<Vec<Vec<Leaf>>, BlitzParseError> {
+    let (text, _, _) = encoding_rs::UTF_8.decode(bytes);
+    let mut records = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = serde_json::from_str(line).map_err(|e| {
+            BlitzParseError::corrupt(
+                "jsonl",
+                ErrorContext::part(format!("line {}", number + 1)),
+                e,
+            )
+        })?;
+        let mut leaves = Vec::new();
+        let mut path = if options.records_at.is_some() {
+            Vec::new()
+        } else {
+            vec![Segment::Index(records.len())]
+        };
+        flatten_value(&value, &mut path, patterns, &mut leaves);
+        records.push(leaves);
+    }
+    Ok(records)
+}
+
+fn flatten_value(value: &Value, path: &mut Vec<Segment>, patterns: &Patterns, out: &mut Vec<Leaf>) {
+    if is_excluded(path, patterns) || !may_include(path, patterns) {
+        return;
+    }
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                path.push(Segment::Key(key.clone()));
+                flatten_value(child, path, patterns, out);
+                path.pop();
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                path.push(Segment::Index(i));
+                flatten_value(child, path, patterns, out);
+                path.pop();
+            }
+        }
+        Value::Null => {}
+        Value::String(s) => push_leaf(path, s.clone(), patterns, out),
+        scalar => push_leaf(path, scalar.to_string(), patterns, out),
+    }
+}
+
+fn push_leaf(path: &[Segment], value: String, patterns: &Patterns, out: &mut Vec<Leaf>) {
+    let value = value.trim();
+    if value.is_empty() || !is_included(path, patterns) {
+        return;
+    }
+    out.push((path.to_vec(), value.to_string()));
+}
+
+/// A minimal element tree, enough for flattening.
+#[derive(Default)]
+struct XmlElement {
+    name: String,
+    attributes: Vec<(String, String)>,
+    text: String,
+    children: Vec<XmlElement>,
+}
+
+fn flatten_xml(
+    bytes: &[u8],
+    options: &FlattenOptions,
+    patterns: &Patterns,
+    limits: &ExtractOptions,
+) -> Result<Vec<Vec<Leaf>>, BlitzParseError> {
+    let root = parse_xml_tree(bytes, limits)?;
+    let records = match &options.records_at {
+        Some(pointer) => {
+            let pattern = split_pointer(pointer);
+            let mut matches = Vec::new();
+            collect_matching(
+                &root,
+                &mut vec![Segment::Key(root.name.clone())],
+                &pattern,
+                &mut matches,
+            );
+            matches
+                .into_iter()
+                .map(|element| {
+                    let mut leaves = Vec::new();
+                    flatten_element_contents(element, &mut Vec::new(), patterns, &mut leaves);
+                    leaves
+                })
+                .collect()
+        }
+        None => {
+            let mut leaves = Vec::new();
+            let mut path = vec![Segment::Key(root.name.clone())];
+            flatten_element_contents(&root, &mut path, patterns, &mut leaves);
+            vec![leaves]
+        }
+    };
+    Ok(records)
+}
+
+fn parse_xml_tree(bytes: &[u8], limits: &ExtractOptions) -> Result<XmlElement, BlitzParseError> {
+    let mut budget = limits.xml_budget();
+    let (text, _, _) = encoding_rs::UTF_8.decode(bytes);
+    let mut reader = Reader::from_str(&text);
+    reader.config_mut().trim_text(true);
+
+    // The bottom of the stack is a synthetic document node.
+    let mut stack: Vec<XmlElement> = vec![XmlElement::default()];
+    loop {
+        let event = reader.read_event().map_err(|e| {
+            BlitzParseError::corrupt(
+                "xml",
+                ErrorContext::default().at(reader.error_position()),
+                e,
+            )
+        })?;
+        match event {
+            Event::Start(e) => {
+                budget.open()?;
+                stack.push(element_from(&e));
+            }
+            Event::Empty(e) => {
+                budget.open()?;
+                budget.close();
+                let element = element_from(&e);
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(element);
+                }
+            }
+            Event::End(_) if stack.len() > 1 => {
+                budget.close();
+                let element = stack.pop().unwrap_or_default();
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(element);
+                }
+            }
+            Event::Text(t) => {
+                if let Some(current) = stack.last_mut() {
+                    let text = t.unescape().unwrap_or_default();
+                    if !current.text.is_empty() {
+                        current.text.push(' ');
+                    }
+                    current.text.push_str(text.trim());
+                }
+            }
+            Event::CData(t) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(String::from_utf8_lossy(&t).trim());
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let document = stack.into_iter().next().unwrap_or_default();
+    document
+        .children
+        .into_iter()
+        .next()
+        .ok_or_else(|| BlitzParseError::MissingPart {
+            format: "xml",
+            context: ErrorContext::part("root element"),
+        })
+}
+
+fn element_from(e: &quick_xml::events::BytesStart) -> XmlElement {
+    XmlElement {
+        name: String::from_utf8_lossy(e.name().as_ref()).into_owned(),
+        attributes: e
+            .attributes()
+            .flatten()
+            .map(|a| {
+                (
+                    String::from_utf8_lossy(a.key.as_ref()).into_owned(),
+                    a.unescape_value()
+                        .map(|v| v.into_owned())
+                        .unwrap_or_default(),
+                )
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Find elements whose path matches `pattern` exactly (indices optional).
+fn collect_matching<'a>(
+    element: &'a XmlElement,
+    path: &mut Vec<Segment>,
+    pattern: &[String],
+    out: &mut Vec<&'a XmlElement>,
+) {
+    match match_pattern(pattern, path) {
+        PatternMatch::Mismatch => return,
+        PatternMatch::Covered(_) if path_matches(pattern, path) => {
+            out.push(element);
+            return;
+        }
+        PatternMatch::Covered(_) => return,
+        PatternMatch::Partial => {}
+    }
+    for_each_child(element, path, |child, path| {
+        collect_matching(child, path, pattern, out)
+    });
+}
+
+/// Visit children, adding an index segment when siblings share a name.
+fn for_each_child<'a>(
+    element: &'a XmlElement,
+    path: &mut Vec<Segment>,
+    mut visit: impl FnMut(&'a XmlElement, &mut Vec<Segment>),
+) {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for child in &element.children {
+        *counts.entry(child.name.as_str()).or_default() += 1;
+    }
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for child in &element.children {
+        let repeated = counts[child.name.as_str()] > 1;
+        let index = seen.entry(child.name.as_str()).or_default();
+        path.push(Segment::Key(child.name.clone()));
+        if repeated {
+            path.push(Segment::Index(*index));
+        }
+        visit(child, path);
+        if repeated {
+            path.pop();
+        }
+        path.pop();
+        *index += 1;
+    }
+}
+
+fn flatten_element_contents(
+    element: &XmlElement,
+    path: &mut Vec<Segment>,
+    patterns: &Patterns,
+    out: &mut Vec<Leaf>,
+) {
+    if is_excluded(path, patterns) || !may_include(path, patterns) {
+        return;
+    }
+    for (name, value) in &element.attributes {
+        // Namespace declarations are markup, not data.
+        if name == "xmlns" || name.starts_with("xmlns:") {
+            continue;
+        }
+        path.push(Segment::Key(format!("@{}", name)));
+        if !is_excluded(path, patterns) {
+            push_leaf(path, value.clone(), patterns, out);
+        }
+        path.pop();
+    }
+    push_leaf(path, element.text.clone(), patterns, out);
+    for_each_child(element, path, |child, path| {
+        flatten_element_contents(child, path, patterns, out)
+    });
+}
+
+fn split_pointer(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+/// How far a path pattern gets along a concrete path.
+enum PatternMatch {
+    /// A segment contradicts the pattern.
+    Mismatch,
+    /// The path ended first; something below it could still match.
+    Partial,
+    /// The whole pattern matched; the value is the number of path segments used.
+    Covered(usize),
+}
+
+/// Walk `pattern` along `path`. Index segments in the path are skipped when
+/// the pattern does not name them, so `/users/name` matches `/users/3/name`.
+fn match_pattern(pattern: &[String], path: &[Segment]) -> PatternMatch {
+    let mut s = 0;
+    for pat in pattern {
+        loop {
+            let Some(segment) = path.get(s) else {
+                return PatternMatch::Partial;
+            };
+            s += 1;
+            match segment {
+                Segment::Key(key) if pat == "*" || pat == key => break,
+                Segment::Key(_) => return PatternMatch::Mismatch,
+                Segment::Index(i) if pat == "*" || pat.parse::<usize>().ok() == Some(*i) => break,
+                // The pattern names a different index.
+                Segment::Index(_) if pat.parse::<usize>().is_ok() => return PatternMatch::Mismatch,
+                Segment::Index(_) => continue,
+            }
+        }
+    }
+    PatternMatch::Covered(s)
+}
+
+/// True when `path` is exactly the element the pattern names, ignoring a trailing index.
+fn path_matches(pattern: &[String], path: &[Segment]) -> bool {
+    match match_pattern(pattern, path) {
+        PatternMatch::Covered(used) => path[used..].iter().all(|s| matches!(s, Segment::Index(_))),
+        _ => false,
+    }
+}
+
+/// True when `path` lies at or below `pattern`.
+fn pattern_covers(pattern: &[String], path: &[Segment]) -> bool {
+    matches!(match_pattern(pattern, path), PatternMatch::Covered(_))
+}
+
+fn is_excluded(path: &[Segment], patterns: &Patterns) -> bool {
+    patterns
+        .exclude
+        .iter()
+        .any(|pattern| pattern_covers(pattern, path))
+}
+
+fn is_included(path: &[Segment], patterns: &Patterns) -> bool {
+    patterns.include.is_empty()
+        || patterns
+            .include
+            .iter()
+            .any(|pattern| pattern_covers(pattern, path))
+}
+
+/// Whether descending into `path` could still reach an included value.
+fn may_include(path: &[Segment], patterns: &Patterns) -> bool {
+    patterns.include.is_empty()
+        || patterns
+            .include
+            .iter()
+            .any(|pattern| !matches!(match_pattern(pattern, path), PatternMatch::Mismatch))
+}
+
+/// Human-readable path: `users[3].address.city`, `feed.entry[0].@id`.
+fn display_path(path: &[Segment]) -> String {
+    let mut out = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) => {
+                if !out.is_empty() {
+                    out.push('.');
+                }
+                out.push_str(key);
+            }
+            Segment::Index(i) => out.push_str(&format!("[{}]", i)),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"{
+        "users": [
+            {"name": "Ada", "address": {"city": "London"}, "tags": ["math", "poetry"], "token": "x1"},
+            {"name": "Alan", "address": {"city": "Wilmslow"}, "active": true, "token": "x2"}
+        ],
+        "count": 2
+    }"#;
+
+    #[test]
+    fn test_flatten_json_paths() {
+        let text = flatten_bytes(JSON.as_bytes(), "json", &FlattenOptions::default()).unwrap();
+        assert!(text.contains("users[0].name: Ada"));
+        assert!(text.contains("users[0].tags[1]: poetry"));
+        assert!(text.contains("users[1].active: true"));
+        assert!(text.contains("count: 2"));
+    }
+
+    #[test]
+    fn test_json_include_exclude_and_records() {
+        let options = FlattenOptions {
+            include: vec!["/users".to_string()],
+            exclude: vec!["/users/token".to_string(), "/users/*/tags".to_string()],
+            records_at: None,
+        };
+        let text = flatten_bytes(JSON.as_bytes(), "json", &options).unwrap();
+        assert!(!text.contains("count"));
+        assert!(!text.contains("token"));
+        assert!(!text.contains("tags"));
+        assert!(text.contains("users[1].address.city: Wilmslow"));
+
+        let options = FlattenOptions {
+            exclude: vec!["/token".to_string()],
+            records_at: Some("/users".to_string()),
+            ..Default::default()
+        };
+        let text = flatten_bytes(JSON.as_bytes(), "json", &options).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "name: Ada; address.city: London; tags[0]: math; tags[1]: poetry"
+        );
+    }
+
+    #[test]
+    fn test_jsonl_records() {
+        let jsonl = "{\"id\": 1, \"msg\": \"boot\"}\n\n{\"id\": 2, \"msg\": \"ready\"}\n";
+        let options = FlattenOptions {
+            records_at: Some("/".to_string()),
+            ..Default::default()
+        };
+        let text = flatten_bytes(jsonl.as_bytes(), "jsonl", &options).unwrap();
+        assert_eq!(text, "id: 1; msg: boot\nid: 2; msg: ready");
+
+        let text = flatten_bytes(jsonl.as_bytes(), "jsonl", &FlattenOptions::default()).unwrap();
+        assert!(text.starts_with("[0].id: 1\n[0].msg: boot"));
+    }
+
+    #[test]
+    fn test_xml_depth_limit() {
+        let xml = format!("{}{}", "<a>".repeat(50), "</a>".repeat(50));
+        let limits = ExtractOptions {
+            max_xml_depth: Some(20),
+            ..Default::default()
+        };
+        let result = flatten_bytes_with(xml.as_bytes(), "xml", &FlattenOptions::default(), &limits);
+        assert!(matches!(
+            result,
+            Err(BlitzParseError::LimitExceeded {
+                kind: crate::errors::LimitKind::XmlDepth,
+                ..
+            })
+        ));
+        assert!(flatten_bytes(xml.as_bytes(), "xml", &FlattenOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_flatten_xml_with_records() {
+        let xml = r#"<?xml version="1.0"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Releases</title>
+  <entry id="1"><title>v1.0</title><summary>First &amp; best</summary></entry>
+  <entry id="2"><title>v1.1</title><summary><![CDATA[Fixes <bugs>]]></summary></entry>
+</feed>"#;
+        let text = flatten_bytes(xml.as_bytes(), "xml", &FlattenOptions::default()).unwrap();
+        assert!(text.contains("feed.title: Releases"));
+        assert!(text.contains("feed.entry[0].@id: 1"));
+        assert!(text.contains("feed.entry[1].summary: Fixes <bugs>"));
+
+        let options = FlattenOptions {
+            exclude: vec!["/@id".to_string()],
+            records_at: Some("/feed/entry".to_string()),
+            ..Default::default()
+        };
+        let text = flatten_bytes(xml.as_bytes(), "xml", &options).unwrap();
+        assert_eq!(
+            text,
+            "title: v1.0; summary: First & best\ntitle: v1.1; summary: Fixes <bugs>"
+        );
+
+        // Only names shared by several siblings get an index.
+        let mixed = b"<r><a>1</a><b>2</b><a>3</a></r>";
+        let text = flatten_bytes(mixed, "xml", &FlattenOptions::default()).unwrap();
+        assert_eq!(text, "r.a[0]: 1\nr.b: 2\nr.a[1]: 3");
+    }
+}
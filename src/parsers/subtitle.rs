@@ -0,0 +1,337 @@
+use crate::document::{Warning, WarningKind};
+use crate::errors::{BlitzParseError, ErrorContext};
+use crate::parsers::txt::decode_text;
+use std::path::Path;
+
+/// One timed cue from an SRT or WebVTT file. A WebVTT cue with several voices
+/// is split into one `Cue` per voice, sharing the cue's timing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// The WebVTT voice (`<v Name>`), if any.
+    pub speaker: Option<String>,
+    /// Cue text with markup removed; lines are joined by `\n`.
+    pub text: String,
+}
+
+/// The cues of a subtitle file.
+#[derive(Debug, Clone, Default)]
+pub struct Transcript {
+    pub cues: Vec<Cue>,
+    /// Cues whose timing could not be read and were left out.
+    pub warnings: Vec<Warning>,
+}
+
+/// Render cues one per line, prefixing the speaker where known.
+pub fn cues_text(cues: &[Cue]) -> String {
+    cues.iter()
+        .map(|cue| match &cue.speaker {
+            Some(speaker) => format!("{}: {}", speaker, cue.text.replace('\n', " ")),
+            None => cue.text.replace('\n', " "),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Merge consecutive cues from the same speaker while the merged text stays
+/// within `max_chars`, so transcripts chunk into time-ranged passages.
+pub fn merge_cues(cues: &[Cue], max_chars: usize) -> Vec<Cue> {
+    let mut merged: Vec<Cue> = Vec::new();
+    for cue in cues {
+        if let Some(last) = merged.last_mut() {
+            let fits = last.text.chars().count() + 1 + cue.text.chars().count() <= max_chars;
+            if last.speaker == cue.speaker && fits {
+                last.text.push(' ');
+                last.text.push_str(&cue.text);
+                last.end_ms = last.end_ms.max(cue.end_ms);
+                continue;
+            }
+        }
+        merged.push(cue.clone());
+    }
+    merged
+}
+
+pub fn parse_subtitles(path: &str) -> Result<String, BlitzParseError> {
+    Ok(cues_text(&read_subtitles(path)?.cues))
+}
+
+/// Read a `.srt` or `.vtt` file; the extension picks the dialect.
+pub fn read_subtitles(path: &str) -> Result<Transcript, BlitzParseError> {
+    let bytes = std::fs::read(path)?;
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    read_subtitles_bytes(&bytes, extension)
+}
+
+pub fn parse_subtitles_bytes(bytes: &[u8], extension: &str) -> Result<String, BlitzParseError> {
+    Ok(cues_text(&read_subtitles_bytes(bytes, extension)?.cues))
+}
+
+/// Read the cues of a subtitle file. A cue with unreadable timing is left
+/// out with a warning; the file fails only if no cue can be read.
+pub fn read_subtitles_bytes(bytes: &[u8], extension: &str) -> Result<Transcript, BlitzParseError> {
+    let text = decode_text(bytes, true)?.text;
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let webvtt = extension.eq_ignore_ascii_case("vtt") || text.starts_with("WEBVTT");
+    let format = if webvtt { "vtt" } else { "srt" };
+
+    let mut cues = Vec::new();
+    let mut failures = Vec::new();
+    // The line each block starts on, counting from 1.
+    let mut line = 1;
+    for (n, block) in text.split("\n\n").enumerate() {
+        let block_line = line;
+        line += block.matches('\n').count() + 2;
+        let lines: Vec<&str> = block.lines().filter(|l| !l.trim().is_empty()).collect();
+        if lines.is_empty() {
+            continue;
+        }
+        if webvtt && (n == 0 && lines[0].starts_with("WEBVTT") || is_vtt_metadata_block(lines[0])) {
+            continue;
+        }
+
+        // The timing line follows an optional index (SRT) or identifier (WebVTT).
+        let Some(timing) = lines.iter().position(|l| l.contains("-->")) else {
+            continue;
+        };
+        let Some((start_ms, end_ms)) = parse_timing(lines[timing]) else {
+            let at = block_line + block.lines().position(|l| l.contains("-->")).unwrap_or(0);
+            failures.push(BlitzParseError::corrupt(
+                format,
+                ErrorContext::part(format!("line {}", at)),
+                format!("Invalid cue timing: {}", lines[timing].trim()),
+            ));
+            continue;
+        };
+
+        let body = &lines[timing + 1..];
+        if webvtt {
+            cues.extend(split_voices(body, start_ms, end_ms));
+        } else {
+            let text = body
+                .iter()
+                .map(|l| strip_markup(l))
+                .filter(|l| !l.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+            if !text.is_empty() {
+                cues.push(Cue {
+                    start_ms,
+                    end_ms,
+                    speaker: None,
+                    text,
+                });
+            }
+        }
+    }
+
+    if cues.is_empty() && !failures.is_empty() {
+        return Err(failures.swap_remove(0));
+    }
+    if cues.is_empty() && !text.trim().is_empty() && !text.trim().starts_with("WEBVTT") {
+        return Err(BlitzParseError::MissingPart {
+            format,
+            context: ErrorContext::part("cues"),
+        });
+    }
+    Ok(Transcript {
+        cues,
+        warnings: failures
+            .iter()
+            .map(|e| Warning::from_error(WarningKind::Skipped, e))
+            .collect(),
+    })
+}
+
+fn is_vtt_metadata_block(first_line: &str) -> bool {
+    ["NOTE", "STYLE", "REGION"]
+        .iter()
+        .any(|kw| first_line == *kw || first_line.starts_with(&format!("{} ", kw)))
+}
+
+/// `00:01:02,500 --> 00:01:04,000 align:start` -> (62500, 64000).
+fn parse_timing(line: &str) -> Option<(u64, u64)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start.trim())?, parse_timestamp(end)?))
+}
+
+/// `hh:mm:ss,mmm` (SRT) or `[hh:]mm:ss.mmm` (WebVTT).
+fn parse_timestamp(stamp: &str) -> Option<u64> {
+    let (clock, millis) = match stamp.split_once([',', '.']) {
+        Some((clock, frac)) => {
+            // Normalise fractions such as `.5` or `.5000` to milliseconds.
+            let digits: String = frac.chars().take(3).collect();
+            let value: u64 = digits.parse().ok()?;
+            (clock, value * 10u64.pow(3 - digits.len() as u32))
+        }
+        None => (stamp, 0),
+    };
+    let parts: Vec<u64> = clock
+        .split(':')
+        .map(|p| p.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    // Out-of-range stamps are rejected rather than wrapped.
+    let seconds = match parts.as_slice() {
+        [h, m, s] => h
+            .checked_mul(3600)?
+            .checked_add(m.checked_mul(60)?)?
+            .checked_add(*s)?,
+        [m, s] => m.checked_mul(60)?.checked_add(*s)?,
+        _ => return None,
+    };
+    seconds.checked_mul(1000)?.checked_add(millis)
+}
+
+/// Split WebVTT cue text on voice tags: each line opening with `<v Name>`
+/// starts a new cue for that speaker.
+fn split_voices(lines: &[&str], start_ms: u64, end_ms: u64) -> Vec<Cue> {
+    let mut cues: Vec<Cue> = Vec::new();
+    for line in lines {
+        let voice = voice_of(line);
+        let text = strip_markup(line);
+        match cues.last_mut() {
+            Some(last) if voice.is_none() || voice == last.speaker => {
+                if !text.is_empty() {
+                    if !last.text.is_empty() {
+                        last.text.push('\n');
+                    }
+                    last.text.push_str(&text);
+                }
+            }
+            _ => cues.push(Cue {
+                start_ms,
+                end_ms,
+                speaker: voice,
+                text,
+            }),
+        }
+    }
+    cues.retain(|cue| !cue.text.is_empty());
+    cues
+}
+
+/// The annotation of a `<v>` or `<v.class>` tag, e.g. `Alice` in `<v.loud Alice>`.
+fn voice_of(line: &str) -> Option<String> {
+    let start = line.find("<v")?;
+    let tag = &line[start + 2..];
+    if !tag.starts_with([' ', '.', '\t']) {
+        return None;
+    }
+    let tag = &tag[..tag.find('>')?];
+    let name = tag.split_once([' ', '\t'])?.1.trim();
+    (!name.is_empty()).then(|| decode_entities(name))
+}
+
+/// Remove WebVTT/SRT tags (`<i>`, `<v ..>`, `<00:01.000>`, `<font ..>`),
+/// ASS override blocks such as `{\an8}`, and decode character references.
+fn strip_markup(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '<' => {
+                // A lone `<` in SRT text is kept.
+                match chars.clone().position(|c| c == '>') {
+                    Some(end) => {
+                        for _ in 0..=end {
+                            chars.next();
+                        }
+                    }
+                    None => out.push(c),
+                }
+            }
+            '{' if chars.peek() == Some(&'\\') => {
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    decode_entities(out.trim())
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&lrm;", "\u{200e}")
+        .replace("&rlm;", "\u{200f}")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srt_cues_and_markup() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:04,250\r\n<i>Hello</i> there,\r\n{\\an8}general.\r\n\r\n2\r\n00:01:02,5 --> 01:00:00,000 X1:10\r\nA < B\r\n";
+        let cues = read_subtitles_bytes(srt.as_bytes(), "srt").unwrap().cues;
+        assert_eq!(
+            cues,
+            vec![
+                Cue {
+                    start_ms: 1000,
+                    end_ms: 4250,
+                    speaker: None,
+                    text: "Hello there,\ngeneral.".to_string(),
+                },
+                Cue {
+                    start_ms: 62_500,
+                    end_ms: 3_600_000,
+                    speaker: None,
+                    text: "A < B".to_string(),
+                },
+            ]
+        );
+        assert_eq!(cues_text(&cues), "Hello there, general.\nA < B");
+    }
+
+    #[test]
+    fn test_vtt_voices_notes_and_identifiers() {
+        let vtt = "WEBVTT - Standup\n\nNOTE recorded on Monday\n\nSTYLE\n::cue { color: red }\n\nintro\n00:01.000 --> 00:03.500 align:start\n<v.loud Alice Smith>Morning &amp; welcome.\n<v Bob>Hi <00:02.000><c.yellow>all</c>.\n\n00:04.000 --> 00:06.000\n<v Bob>Second line\ncontinues here.\n";
+        let cues = read_subtitles_bytes(vtt.as_bytes(), "vtt").unwrap().cues;
+        let summary: Vec<_> = cues
+            .iter()
+            .map(|c| (c.start_ms, c.end_ms, c.speaker.as_deref(), c.text.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1000, 3500, Some("Alice Smith"), "Morning & welcome."),
+                (1000, 3500, Some("Bob"), "Hi all."),
+                (4000, 6000, Some("Bob"), "Second line\ncontinues here."),
+            ]
+        );
+
+        let merged = merge_cues(&cues, 200);
+        assert_eq!(merged.len(), 2);
+        assert_eq!((merged[1].start_ms, merged[1].end_ms), (1000, 6000));
+        assert_eq!(merged[1].text, "Hi all. Second line\ncontinues here.");
+    }
+
+    #[test]
+    fn test_rejects_text_without_cues() {
+        assert!(read_subtitles_bytes(b"just some prose\n", "srt").is_err());
+        assert!(read_subtitles_bytes(b"WEBVTT\n", "vtt")
+            .unwrap()
+            .cues
+            .is_empty());
+
+        // A cue whose timing overflows is skipped; the rest of the file is kept.
+        let srt = "1\n99999999999999999:00:00,000 --> 00:00:01,000\nLost\n\n\
+                   2\n00:00:01,000 --> 00:00:02,000\nKept\n";
+        let transcript = read_subtitles_bytes(srt.as_bytes(), "srt").unwrap();
+        assert_eq!(cues_text(&transcript.cues), "Kept");
+        assert_eq!(transcript.warnings.len(), 1);
+        assert!(read_subtitles_bytes(b"1\n99999999999999999:00 --> 00:01\nLost\n", "srt").is_err());
+    }
+}
@@ -1,5 +1,368 @@
-use crate::errors::BlitzParseError;
+use crate::errors::{BlitzParseError, ErrorContext};
+use crate::options::ExtractOptions;
+use encoding_rs::{DecoderResult, Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use std::io::{self, Read};
 
+/// Bytes inspected when looking for BOM-less UTF-16.
+const UTF16_SAMPLE: usize = 8 * 1024;
+
+/// Bytes decoded between cancellation checks and progress reports.
+const DECODE_SLICE: usize = 1024 * 1024;
+
+/// The charset a text file was decoded from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncodingGuess {
+    pub encoding: &'static Encoding,
+    /// 1.0 when a BOM or pure ASCII settles it; lower for statistical guesses.
+    pub confidence: f32,
+    /// Whether the encoding was read from a byte order mark.
+    pub bom: bool,
+}
+
+impl EncodingGuess {
+    /// WHATWG name of the encoding, e.g. `UTF-8`, `windows-1252`, `Shift_JIS`.
+    /// ISO-8859-1 is reported as `windows-1252`, its superset.
+    pub fn name(&self) -> &'static str {
+        self.encoding.name()
+    }
+}
+
+/// A text file decoded to UTF-8.
+#[derive(Debug, Clone)]
+pub struct DecodedText {
+    pub text: String,
+    pub encoding: EncodingGuess,
+    /// True when malformed sequences were replaced with U+FFFD (lossy mode only).
+    pub had_errors: bool,
+}
+
+/// Decode a text file, detecting its encoding. Fails on malformed input.
 pub fn parse_txt(path: &str) -> Result<String, BlitzParseError> {
-    std::fs::read_to_string(path).map_err(BlitzParseError::Io)
+    Ok(read_txt(path, false)?.text)
+}
+
+pub fn parse_txt_bytes(bytes: &[u8]) -> Result<String, BlitzParseError> {
+    parse_txt_bytes_with(bytes, &ExtractOptions::default())
+}
+
+pub fn parse_txt_bytes_with(
+    bytes: &[u8],
+    options: &ExtractOptions,
+) -> Result<String, BlitzParseError> {
+    Ok(decode_text_with(bytes, false, options)?.text)
+}
+
+/// Decode a text file along with the detected encoding. With `lossy` set,
+/// undecodable bytes become U+FFFD instead of failing the whole file.
+pub fn read_txt(path: &str, lossy: bool) -> Result<DecodedText, BlitzParseError> {
+    read_txt_with(path, lossy, &ExtractOptions::default())
+}
+
+/// Like `read_txt`, decoding as `decode_text_with` does.
+pub fn read_txt_with(
+    path: &str,
+    lossy: bool,
+    options: &ExtractOptions,
+) -> Result<DecodedText, BlitzParseError> {
+    crate::input::with_file_bytes_with(path, options, |bytes| {
+        decode_text_with(bytes, lossy, options)
+    })
+}
+
+pub fn decode_text(bytes: &[u8], lossy: bool) -> Result<DecodedText, BlitzParseError> {
+    decode_text_with(bytes, lossy, &ExtractOptions::default())
+}
+
+/// Like `decode_text`, decoding in slices so that a large file can be
+/// cancelled part way and reports its progress in bytes.
+pub fn decode_text_with(
+    bytes: &[u8],
+    lossy: bool,
+    options: &ExtractOptions,
+) -> Result<DecodedText, BlitzParseError> {
+    let mut guess = detect_encoding(bytes);
+    // A mostly-UTF-8 file with a few stray bytes reads better as UTF-8 with
+    // replacements than as a legacy codepage full of mojibake.
+    if lossy && !guess.bom && guess.encoding != UTF_8 {
+        if let Some(confidence) = mostly_utf8(bytes) {
+            guess = EncodingGuess {
+                encoding: UTF_8,
+                confidence,
+                bom: false,
+            };
+        }
+    }
+
+    let bom_len = if guess.bom {
+        Encoding::for_bom(bytes).map_or(0, |(_, len)| len)
+    } else {
+        0
+    };
+    let body = &bytes[bom_len..];
+
+    let mut decoder = guess.encoding.new_decoder_without_bom_handling();
+    let mut text = String::new();
+    let mut had_errors = false;
+    let mut start = 0;
+    loop {
+        options.checkpoint()?;
+        let end = (start + DECODE_SLICE).min(body.len());
+        let last = end == body.len();
+        let slice = &body[start..end];
+        text.reserve(
+            decoder
+                .max_utf8_buffer_length(slice.len())
+                .unwrap_or(slice.len() * 3 + 4),
+        );
+        if lossy {
+            let (_, _, errors) = decoder.decode_to_string(slice, &mut text, last);
+            had_errors |= errors;
+        } else {
+            let (result, read) =
+                decoder.decode_to_string_without_replacement(slice, &mut text, last);
+            if let DecoderResult::Malformed(bad, extra) = result {
+                // The malformed sequence ends `extra` bytes before what was read.
+                let offset = bom_len + start + read - extra as usize - bad as usize;
+                return Err(BlitzParseError::Encoding {
+                    encoding: guess.name(),
+                    context: ErrorContext::default().at(offset as u64),
+                });
+            }
+        }
+        options.report("txt", "bytes", end as u64, Some(body.len() as u64));
+        if last {
+            break;
+        }
+        start = end;
+    }
+
+    Ok(DecodedText {
+        text,
+        encoding: guess,
+        had_errors,
+    })
+}
+
+/// Work out which encoding `bytes` are in: a BOM wins, then BOM-less UTF-16
+/// (whose NULs would otherwise pass as UTF-8), then valid UTF-8, then a
+/// statistical guess over legacy codepages.
+pub fn detect_encoding(bytes: &[u8]) -> EncodingGuess {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return EncodingGuess {
+            encoding,
+            confidence: 1.0,
+            bom: true,
+        };
+    }
+    if let Some(guess) = detect_utf16(bytes) {
+        return guess;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        // Legacy multi-byte text is very unlikely to form valid UTF-8 by accident.
+        let confidence = if bytes.is_ascii() { 1.0 } else { 0.99 };
+        return EncodingGuess {
+            encoding: UTF_8,
+            confidence,
+            bom: false,
+        };
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    let (encoding, assessed) = detector.guess_assess(None, true);
+    let base = if assessed { 0.9 } else { 0.6 };
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    EncodingGuess {
+        encoding,
+        confidence: base * plausibility(&text),
+        bom: false,
+    }
+}
+
+/// ASCII-range text in UTF-16 has a NUL in every other byte.
+/// Like `detect_encoding` on everything `reader` yields, reading it in
+/// blocks of `block` bytes rather than all at once.
+pub(crate) fn detect_encoding_from(
+    mut reader: impl Read,
+    block: usize,
+) -> io::Result<EncodingGuess> {
+    let mut head = Vec::with_capacity(block);
+    (&mut reader).take(block as u64).read_to_end(&mut head)?;
+    if head.len() < block {
+        return Ok(detect_encoding(&head));
+    }
+    if let Some((encoding, _)) = Encoding::for_bom(&head) {
+        return Ok(EncodingGuess {
+            encoding,
+            confidence: 1.0,
+            bom: true,
+        });
+    }
+    if let Some(guess) = detect_utf16(&head) {
+        return Ok(guess);
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    let mut utf8 = true;
+    let mut ascii = true;
+    // The start of a UTF-8 sequence cut at the end of the last block.
+    let mut carry = Vec::new();
+    let mut bytes = head;
+    loop {
+        let last = bytes.is_empty();
+        detector.feed(&bytes, last);
+        ascii &= bytes.is_ascii();
+        if utf8 {
+            carry.extend_from_slice(&bytes);
+            match std::str::from_utf8(&carry) {
+                Ok(_) => carry.clear(),
+                Err(e) if e.error_len().is_none() && !last => {
+                    carry.drain(..e.valid_up_to());
+                }
+                Err(_) => utf8 = false,
+            }
+        }
+        if last {
+            break;
+        }
+        bytes.clear();
+        (&mut reader).take(block as u64).read_to_end(&mut bytes)?;
+    }
+    if utf8 {
+        let confidence = if ascii { 1.0 } else { 0.99 };
+        return Ok(EncodingGuess {
+            encoding: UTF_8,
+            confidence,
+            bom: false,
+        });
+    }
+    let (encoding, assessed) = detector.guess_assess(None, true);
+    Ok(EncodingGuess {
+        encoding,
+        confidence: if assessed { 0.9 } else { 0.6 },
+        bom: false,
+    })
+}
+
+fn detect_utf16(bytes: &[u8]) -> Option<EncodingGuess> {
+    let sample = &bytes[..bytes.len().min(UTF16_SAMPLE) & !1];
+    let units = sample.len() / 2;
+    if units < 2 {
+        return None;
+    }
+    let zeros_at = |parity: usize| {
+        sample
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+
+    // Require NULs to be common in one lane and rare in the other.
+    let (encoding, hits, misses) = if odd > even {
+        (UTF_16LE, odd, even)
+    } else {
+        (UTF_16BE, even, odd)
+    };
+    if hits * 10 < units * 3 || misses * 20 > units {
+        return None;
+    }
+    let confidence = 0.5 + 0.45 * (hits - misses) as f32 / units as f32;
+    Some(EncodingGuess {
+        encoding,
+        confidence,
+        bom: false,
+    })
+}
+
+/// If `bytes` are UTF-8 apart from a handful of invalid sequences, the
+/// confidence that UTF-8 is still the right reading.
+fn mostly_utf8(bytes: &[u8]) -> Option<f32> {
+    let (mut valid, mut invalid) = (0usize, 0usize);
+    for chunk in bytes.utf8_chunks() {
+        valid += chunk.valid().chars().filter(|c| !c.is_ascii()).count();
+        invalid += chunk.invalid().len();
+    }
+    if valid == 0 || invalid * 4 > valid {
+        return None;
+    }
+    Some(0.9 * valid as f32 / (valid + invalid) as f32)
+}
+
+/// Fraction of non-ASCII characters that look like real text rather than
+/// C1 controls, replacement characters or private-use code points.
+fn plausibility(text: &str) -> f32 {
+    let (mut total, mut plausible) = (0usize, 0usize);
+    for c in text.chars().filter(|c| !c.is_ascii()) {
+        total += 1;
+        let implausible = matches!(c, '\u{80}'..='\u{9f}' | '\u{fffd}' | '\u{e000}'..='\u{f8ff}');
+        if !implausible {
+            plausible += 1;
+        }
+    }
+    if total == 0 {
+        1.0
+    } else {
+        plausible as f32 / total as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+
+    #[test]
+    fn test_bom_and_bomless_utf16() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend("Grüße".encode_utf16().flat_map(u16::to_le_bytes));
+        let decoded = decode_text(&bytes, false).unwrap();
+        assert_eq!(decoded.text, "Grüße");
+        assert_eq!(decoded.encoding.name(), "UTF-16LE");
+        assert!(decoded.encoding.bom);
+
+        let bytes: Vec<u8> = "Plain ASCII report, no byte order mark."
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect();
+        let decoded = decode_text(&bytes, false).unwrap();
+        assert_eq!(decoded.text, "Plain ASCII report, no byte order mark.");
+        assert_eq!(decoded.encoding.name(), "UTF-16BE");
+        assert!(!decoded.encoding.bom);
+        assert!(decoded.encoding.confidence > 0.9);
+    }
+
+    #[test]
+    fn test_detects_legacy_codepages() {
+        let text = "Le café de la crème brûlée était très apprécié à Noël, déjà célèbre.";
+        let (bytes, _, _) = WINDOWS_1252.encode(text);
+        let decoded = decode_text(&bytes, false).unwrap();
+        assert_eq!(decoded.encoding.name(), "windows-1252");
+        assert_eq!(decoded.text, text);
+        assert!(decoded.encoding.confidence > 0.5);
+
+        let text = "日本語のテキストファイルです。文字コードを自動的に判定します。";
+        let (bytes, _, _) = SHIFT_JIS.encode(text);
+        let decoded = decode_text(&bytes, false).unwrap();
+        assert_eq!(decoded.encoding.name(), "Shift_JIS");
+        assert_eq!(decoded.text, text);
+    }
+
+    #[test]
+    fn test_lossy_mode_keeps_mostly_utf8_text() {
+        let mut bytes = "Ünïcödé text that is almost entirely valid "
+            .as_bytes()
+            .to_vec();
+        bytes.push(0xFF);
+        bytes.extend("UTF-8 — except one byte.".as_bytes());
+
+        let decoded = decode_text(&bytes, true).unwrap();
+        assert_eq!(decoded.encoding.name(), "UTF-8");
+        assert!(decoded.had_errors);
+        assert!(decoded.text.contains("valid \u{fffd}UTF-8"));
+
+        assert_eq!(detect_encoding(b"just ascii").confidence, 1.0);
+    }
 }
@@ -0,0 +1,719 @@
+use crate::document::ParsedDocument;
+use crate::errors::{zip_error, BlitzParseError, ErrorContext};
+use crate::options::ExtractOptions;
+use crate::provenance::{bytes_to_chars, Locator, Provenance};
+use crate::tree::{locate_blocks, Block, BlockKind, DocumentTree, Span};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek};
+
+const WORKBOOK_XML: &str = "xl/workbook.xml";
+const STYLES_XML: &str = "xl/styles.xml";
+
+/// Days from 1970-01-01 to the day before serial 1 of each date system, as
+/// Excel counts it: 1899-12-30 (making up for the 29 February 1900 it
+/// wrongly includes) and 1904-01-01.
+const EPOCH_1900: i64 = -25_569;
+const EPOCH_1904: i64 = -24_107;
+
+/// A row number with its `(column, value)` cells.
+pub type Row = (u32, Vec<(u32, String)>);
+
+/// A worksheet's non-empty cells.
+#[derive(Debug, Clone, Default)]
+pub struct Sheet {
+    pub name: String,
+    /// Rows holding at least one value, in order, as `(row, cells)` with each
+    /// cell as `(column, value)`. Rows and columns count from 0.
+    pub rows: Vec<Row>,
+}
+
+impl Sheet {
+    /// The range of cells holding values, such as `A1:D20`.
+    pub fn range(&self) -> Option<String> {
+        let (first_row, last_row) = (self.rows.first()?.0, self.rows.last()?.0);
+        let columns = self
+            .rows
+            .iter()
+            .flat_map(|(_, cells)| cells.iter().map(|c| c.0));
+        let first_column = columns.clone().min()?;
+        let last_column = columns.max()?;
+        Some(range_name(
+            (first_column, first_row),
+            (last_column, last_row),
+        ))
+    }
+}
+
+pub fn parse_xlsx(path: &str) -> Result<String, BlitzParseError> {
+    crate::input::with_file_bytes(path, |bytes| {
+        read_xlsx_bytes_with(bytes, &ExtractOptions::default()).map(|document| document.text)
+    })
+}
+
+/// Read a workbook's text: each sheet's name, then one line per row with its
+/// cells from the first to the last value separated by ` | `. Sheets are
+/// separated by a blank line. Each line's provenance is its sheet and cells.
+///
+/// Cells hold what Excel last computed: formulas give their cached value.
+/// Numbers whose format in `styles.xml` is a date or time are written as ISO
+/// 8601 dates (`2024-03-01`), times (`13:45:00`) or both.
+pub fn read_xlsx_bytes_with(
+    bytes: &[u8],
+    options: &ExtractOptions,
+) -> Result<ParsedDocument, BlitzParseError> {
+    read_sheets(bytes, options).map(|sheets| sheets_document(&sheets))
+}
+
+/// The text of `sheets`, with each sheet name and row located.
+fn sheets_document(sheets: &[Sheet]) -> ParsedDocument {
+    let mut text = String::new();
+    let mut provenance = Vec::new();
+    let mut record = |text: &mut String, line: &str, sheet: &str, range: Option<String>| {
+        let start = text.len();
+        text.push_str(line);
+        provenance.push(Provenance {
+            start,
+            end: text.len(),
+            locator: Locator::Xlsx {
+                sheet: sheet.to_string(),
+                range,
+            },
+        });
+    };
+    for sheet in sheets {
+        if sheet.rows.is_empty() {
+            continue;
+        }
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+        record(&mut text, &sheet.name, &sheet.name, None);
+        for (row, cells) in &sheet.rows {
+            let (first, last) = (cells[0].0, cells[cells.len() - 1].0);
+            let mut values = vec![""; (last - first + 1) as usize];
+            for (column, value) in cells {
+                values[(column - first) as usize] = value;
+            }
+            text.push('\n');
+            let range = range_name((first, *row), (last, *row));
+            record(&mut text, &values.join(" | "), &sheet.name, Some(range));
+        }
+    }
+    bytes_to_chars(&text, &mut provenance);
+    ParsedDocument {
+        text,
+        provenance,
+        ..Default::default()
+    }
+}
+
+/// Read a workbook into a heading and a table per sheet. The table spans the
+/// sheet's values; its first row is the header.
+pub fn xlsx_to_tree(
+    bytes: &[u8],
+    options: &ExtractOptions,
+) -> Result<DocumentTree, BlitzParseError> {
+    let sheets = read_sheets(bytes, options)?;
+    let mut blocks = Vec::new();
+    for sheet in &sheets {
+        let Some(range) = sheet.range() else {
+            continue;
+        };
+        let first = sheet
+            .rows
+            .iter()
+            .filter_map(|(_, cells)| cells.first())
+            .map(|c| c.0)
+            .min()
+            .unwrap_or(0);
+        let rows = sheet
+            .rows
+            .iter()
+            .map(|(_, cells)| {
+                let mut row = Vec::new();
+                for (column, value) in cells {
+                    row.resize((column - first) as usize, Vec::new());
+                    row.push(vec![Span::plain(value)]);
+                }
+                row
+            })
+            .collect();
+        let locator = |range| Locator::Xlsx {
+            sheet: sheet.name.clone(),
+            range,
+        };
+        blocks.push(Block {
+            source: Some(locator(None)),
+            ..Block::from(BlockKind::Heading {
+                level: 1,
+                spans: vec![Span::plain(&sheet.name)],
+            })
+        });
+        blocks.push(Block {
+            source: Some(locator(Some(range))),
+            ..Block::from(BlockKind::Table { rows })
+        });
+    }
+    locate_blocks(&mut blocks, &sheets_document(&sheets).provenance);
+    Ok(DocumentTree::new(blocks))
+}
+
+/// The workbook's worksheets in tab order, checking the deadline and
+/// cancellation between sheets and reporting each sheet done.
+pub fn read_sheets(bytes: &[u8], options: &ExtractOptions) -> Result<Vec<Sheet>, BlitzParseError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| {
+        if crate::parsers::docx::is_encrypted_package(bytes) {
+            BlitzParseError::Encrypted {
+                format: "xlsx",
+                context: ErrorContext::default(),
+                source: Some(Box::new(e)),
+            }
+        } else {
+            zip_error("xlsx", None, e)
+        }
+    })?;
+    let Some(workbook) = read_part(&mut archive, WORKBOOK_XML, options)? else {
+        return Err(BlitzParseError::MissingPart {
+            format: "xlsx",
+            context: ErrorContext::part(WORKBOOK_XML),
+        });
+    };
+
+    // (name, relationship ID) of each sheet, in tab order.
+    let mut entries = Vec::new();
+    let mut date1904 = false;
+    walk_xml(&workbook, WORKBOOK_XML, options, |event| {
+        if let Event::Start(e) | Event::Empty(e) = event {
+            match e.local_name().as_ref() {
+                b"sheet" => {
+                    if let (Some(name), Some(id)) = (attribute(e, b"name"), attribute(e, b"id")) {
+                        entries.push((name, id));
+                    }
+                }
+                b"workbookPr" => {
+                    date1904 = attribute(e, b"date1904").is_some_and(|v| v == "1" || v == "true")
+                }
+                _ => {}
+            }
+        }
+    })?;
+
+    let mut targets = HashMap::new();
+    if let Some(xml) = read_part(&mut archive, "xl/_rels/workbook.xml.rels", options)? {
+        walk_xml(&xml, "xl/_rels/workbook.xml.rels", options, |event| {
+            if let Event::Start(e) | Event::Empty(e) = event {
+                if let (Some(id), Some(target)) = (attribute(e, b"Id"), attribute(e, b"Target")) {
+                    let target = match target.strip_prefix('/') {
+                        Some(absolute) => absolute.to_string(),
+                        None => format!("xl/{}", target),
+                    };
+                    targets.insert(id, target);
+                }
+            }
+        })?;
+    }
+
+    let strings = match read_part(&mut archive, "xl/sharedStrings.xml", options)? {
+        Some(xml) => read_shared_strings(&xml, options)?,
+        None => Vec::new(),
+    };
+    let mut styles = match read_part(&mut archive, STYLES_XML, options)? {
+        Some(xml) => read_styles(&xml, options)?,
+        None => Styles::default(),
+    };
+    styles.date1904 = date1904;
+
+    let mut sheets = Vec::with_capacity(entries.len());
+    let total = entries.len() as u64;
+    for (done, (name, id)) in entries.into_iter().enumerate() {
+        options.checkpoint()?;
+        // Chart sheets and dangling relationships have no cells.
+        if let Some(target) = targets.get(&id) {
+            if let Some(xml) = read_part(&mut archive, target, options)? {
+                sheets.push(Sheet {
+                    rows: read_rows(&xml, target, &strings, &styles, options)?,
+                    name,
+                });
+            }
+        }
+        options.report("xlsx", "sheets", done as u64 + 1, Some(total));
+    }
+    Ok(sheets)
+}
+
+/// The shared string table: the text of each `si`, without phonetic runs.
+fn read_shared_strings(
+    xml: &str,
+    options: &ExtractOptions,
+) -> Result<Vec<String>, BlitzParseError> {
+    let mut strings = Vec::new();
+    let mut current = String::new();
+    let (mut in_text, mut in_phonetic) = (false, false);
+    walk_xml(xml, "xl/sharedStrings.xml", options, |event| match event {
+        Event::Start(e) => match e.local_name().as_ref() {
+            b"si" => current.clear(),
+            b"t" => in_text = true,
+            b"rPh" => in_phonetic = true,
+            _ => {}
+        },
+        Event::Empty(e) if e.local_name().as_ref() == b"si" => strings.push(String::new()),
+        Event::End(e) => match e.local_name().as_ref() {
+            b"si" => strings.push(std::mem::take(&mut current)),
+            b"t" => in_text = false,
+            b"rPh" => in_phonetic = false,
+            _ => {}
+        },
+        Event::Text(e) if in_text && !in_phonetic => {
+            current.push_str(&e.unescape().unwrap_or_default())
+        }
+        _ => {}
+    })?;
+    Ok(strings)
+}
+
+/// The non-empty cells of a worksheet part, by row.
+fn read_rows(
+    xml: &str,
+    part: &str,
+    strings: &[String],
+    styles: &Styles,
+    options: &ExtractOptions,
+) -> Result<Vec<Row>, BlitzParseError> {
+    let mut rows: Vec<Row> = Vec::new();
+    // Rows and cells without a reference follow the previous one.
+    let (mut row, mut next_row, mut column) = (0u32, 0u32, 0u32);
+    let mut cells = Vec::new();
+    let mut kind = None;
+    let mut style = None;
+    let cell = |kind: Option<String>, style: Option<usize>| Cell {
+        kind,
+        date: styles.date_format(style),
+        date1904: styles.date1904,
+    };
+    let mut value = String::new();
+    let mut in_value = false;
+    walk_xml(xml, part, options, |event| {
+        let (e, end) = match event {
+            Event::Start(e) => (e, false),
+            Event::Empty(e) => (e, true),
+            Event::End(e) => {
+                match e.local_name().as_ref() {
+                    b"v" | b"t" => in_value = false,
+                    b"c" => end_cell(
+                        &mut cells,
+                        column,
+                        cell(kind.take(), style.take()),
+                        &mut value,
+                        strings,
+                    ),
+                    b"row" => end_row(&mut rows, row, &mut cells),
+                    _ => {}
+                }
+                return;
+            }
+            Event::Text(e) if in_value => {
+                value.push_str(&e.unescape().unwrap_or_default());
+                return;
+            }
+            _ => return,
+        };
+        match e.local_name().as_ref() {
+            b"row" => {
+                row = attribute(e, b"r")
+                    .and_then(|r| r.parse::<u32>().ok())
+                    .map_or(next_row, |r| r.saturating_sub(1));
+                next_row = row.saturating_add(1);
+                column = u32::MAX;
+                if end {
+                    end_row(&mut rows, row, &mut cells);
+                }
+            }
+            b"c" => {
+                column = attribute(e, b"r")
+                    .and_then(|r| parse_cell(&r))
+                    .map_or(column.wrapping_add(1), |(c, _)| c);
+                kind = attribute(e, b"t");
+                style = attribute(e, b"s").and_then(|s| s.parse::<usize>().ok());
+                value.clear();
+                if end {
+                    end_cell(
+                        &mut cells,
+                        column,
+                        cell(kind.take(), style.take()),
+                        &mut value,
+                        strings,
+                    );
+                }
+            }
+            b"v" | b"t" => in_value = !end,
+            _ => {}
+        }
+    })?;
+    Ok(rows)
+}
+
+/// How to read a cell's value: its `t` type and the date format of its style.
+struct Cell {
+    kind: Option<String>,
+    date: Option<DateFormat>,
+    date1904: bool,
+}
+
+fn end_cell(
+    cells: &mut Vec<(u32, String)>,
+    column: u32,
+    cell: Cell,
+    value: &mut String,
+    strings: &[String],
+) {
+    let raw = std::mem::take(value);
+    let text = match cell.kind.as_deref() {
+        Some("s") => raw
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| strings.get(i).cloned())
+            .unwrap_or_default(),
+        Some("b") => match raw.trim() {
+            "1" => "TRUE".to_string(),
+            "0" => "FALSE".to_string(),
+            other => other.to_string(),
+        },
+        None | Some("n") => cell
+            .date
+            .zip(raw.trim().parse::<f64>().ok())
+            .and_then(|(date, serial)| date.render(serial, cell.date1904))
+            .unwrap_or(raw),
+        _ => raw,
+    };
+    let text = text.trim();
+    if !text.is_empty() {
+        cells.push((column, text.to_string()));
+    }
+}
+
+fn end_row(rows: &mut Vec<Row>, row: u32, cells: &mut Vec<(u32, String)>) {
+    if !cells.is_empty() {
+        let mut cells = std::mem::take(cells);
+        cells.sort_by_key(|c| c.0);
+        rows.push((row, cells));
+    }
+}
+
+/// A cell reference such as `B3` as zero-based `(column, row)`.
+pub(crate) fn parse_cell(reference: &str) -> Option<(u32, u32)> {
+    let reference = reference.trim().replace('$', "");
+    let digits = reference.find(|c: char| c.is_ascii_digit())?;
+    let (letters, number) = reference.split_at(digits);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let column = letters.chars().try_fold(0u32, |column, c| {
+        column
+            .checked_mul(26)?
+            .checked_add(c.to_ascii_uppercase() as u32 - 'A' as u32 + 1)
+    })?;
+    let row = number.parse::<u32>().ok()?;
+    Some((column.checked_sub(1)?, row.checked_sub(1)?))
+}
+
+/// A range such as `A1:C3`, or a single cell, as its first and last cells.
+pub(crate) fn parse_range(range: &str) -> Option<((u32, u32), (u32, u32))> {
+    match range.split_once(':') {
+        Some((first, last)) => Some((parse_cell(first)?, parse_cell(last)?)),
+        None => parse_cell(range).map(|cell| (cell, cell)),
+    }
+}
+
+/// The name of the range from `first` to `last`, each `(column, row)`.
+pub(crate) fn range_name(first: (u32, u32), last: (u32, u32)) -> String {
+    if first == last {
+        cell_name(first)
+    } else {
+        format!("{}:{}", cell_name(first), cell_name(last))
+    }
+}
+
+fn cell_name((column, row): (u32, u32)) -> String {
+    let mut letters = Vec::new();
+    let mut n = column as u64 + 1;
+    while n > 0 {
+        letters.push((b'A' + ((n - 1) % 26) as u8) as char);
+        n = (n - 1) / 26;
+    }
+    letters.iter().rev().collect::<String>() + &(row as u64 + 1).to_string()
+}
+
+/// What a number format shows of a date serial number.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateFormat {
+    Date,
+    Time,
+    DateTime,
+}
+
+impl DateFormat {
+    /// The date format of built-in format `id`, if it is one. Ids 27-36 and
+    /// 50-58 are the East Asian locales' dates.
+    fn builtin(id: u32) -> Option<DateFormat> {
+        match id {
+            14..=17 | 27..=36 | 50..=58 => Some(DateFormat::Date),
+            18..=21 | 45..=47 => Some(DateFormat::Time),
+            22 => Some(DateFormat::DateTime),
+            _ => None,
+        }
+    }
+
+    /// Classify a custom format code such as `yyyy-mm-dd h:mm` by the date
+    /// and time tokens left once literals, escapes and `[Red]`-style tags are
+    /// removed. Only the first section, for positive numbers, is read.
+    fn from_code(code: &str) -> Option<DateFormat> {
+        let (mut date, mut time) = (false, false);
+        let mut chars = code.chars();
+        while let Some(c) = chars.next() {
+            match c.to_ascii_lowercase() {
+                ';' => break,
+                '"' => {
+                    chars.by_ref().take_while(|&c| c != '"').for_each(drop);
+                }
+                '\\' | '_' | '*' => {
+                    chars.next();
+                }
+                // Elapsed time such as `[h]:mm` is the only tag that matters.
+                '[' => {
+                    let tag: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                    let tag = tag.to_ascii_lowercase();
+                    if !tag.is_empty() && tag.chars().all(|c| matches!(c, 'h' | 'm' | 's')) {
+                        time = true;
+                    }
+                }
+                'y' | 'd' => date = true,
+                'h' | 's' => time = true,
+                _ => {}
+            }
+        }
+        match (date, time) {
+            (true, true) => Some(DateFormat::DateTime),
+            (true, false) => Some(DateFormat::Date),
+            (false, true) => Some(DateFormat::Time),
+            (false, false) => None,
+        }
+    }
+
+    /// Write `serial` in ISO 8601, or `None` if it is no valid date.
+    fn render(self, serial: f64, date1904: bool) -> Option<String> {
+        // Excel's dates end with 9999-12-31.
+        if !(0.0..2_958_466.0).contains(&serial) {
+            return None;
+        }
+        let mut days = serial.floor() as i64;
+        let mut seconds = ((serial - serial.floor()) * 86_400.0).round() as i64;
+        if seconds == 86_400 {
+            days += 1;
+            seconds = 0;
+        }
+        let epoch = if date1904 {
+            EPOCH_1904
+        } else {
+            match days {
+                // 29 February 1900 never happened.
+                60 => return None,
+                // Before it, serials are one day ahead of the real calendar.
+                ..=59 => EPOCH_1900 + 1,
+                _ => EPOCH_1900,
+            }
+        };
+        let (year, month, day) = civil_from_days(epoch + days);
+        let date = format!("{:04}-{:02}-{:02}", year, month, day);
+        let time = format!(
+            "{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+        Some(match self {
+            DateFormat::Date => date,
+            DateFormat::Time => time,
+            DateFormat::DateTime => format!("{} {}", date, time),
+        })
+    }
+}
+
+/// The year, month and day of a count of days since 1970-01-01, in the
+/// proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// How the workbook's cell styles show numbers: the date format, if any, of
+/// each entry of `cellXfs`, which cells refer to by their `s` index.
+#[derive(Default)]
+struct Styles {
+    dates: Vec<Option<DateFormat>>,
+    date1904: bool,
+}
+
+impl Styles {
+    fn date_format(&self, style: Option<usize>) -> Option<DateFormat> {
+        self.dates.get(style?).copied().flatten()
+    }
+}
+
+fn read_styles(xml: &str, options: &ExtractOptions) -> Result<Styles, BlitzParseError> {
+    let mut custom = HashMap::new();
+    let mut formats = Vec::new();
+    let mut in_cell_xfs = false;
+    walk_xml(xml, STYLES_XML, options, |event| match event {
+        Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+            b"numFmt" => {
+                let id = attribute(e, b"numFmtId").and_then(|id| id.parse::<u32>().ok());
+                if let (Some(id), Some(code)) = (id, attribute(e, b"formatCode")) {
+                    custom.insert(id, code);
+                }
+            }
+            b"cellXfs" => in_cell_xfs = matches!(event, Event::Start(_)),
+            b"xf" if in_cell_xfs => formats.push(
+                attribute(e, b"numFmtId")
+                    .and_then(|id| id.parse::<u32>().ok())
+                    .unwrap_or(0),
+            ),
+            _ => {}
+        },
+        Event::End(e) if e.local_name().as_ref() == b"cellXfs" => in_cell_xfs = false,
+        _ => {}
+    })?;
+    // A custom format may reuse a built-in id; its code wins.
+    let dates = formats
+        .into_iter()
+        .map(|id| match custom.get(&id) {
+            Some(code) => DateFormat::from_code(code),
+            None => DateFormat::builtin(id),
+        })
+        .collect();
+    Ok(Styles {
+        dates,
+        date1904: false,
+    })
+}
+
+/// Read a part of the package as UTF-8, or `None` if it isn't there.
+fn read_part<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+    options: &ExtractOptions,
+) -> Result<Option<String>, BlitzParseError> {
+    let entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(zip_error("xlsx", Some(name), e)),
+    };
+    let bytes = options.read_entry(entry)?;
+    String::from_utf8(bytes)
+        .map(Some)
+        .map_err(|e| BlitzParseError::Encoding {
+            encoding: "UTF-8",
+            context: ErrorContext::part(name).at(e.utf8_error().valid_up_to() as u64),
+        })
+}
+
+/// Feed every event of `xml` to `handle`, within the XML limits.
+fn walk_xml(
+    xml: &str,
+    part: &str,
+    options: &ExtractOptions,
+    mut handle: impl FnMut(&Event),
+) -> Result<(), BlitzParseError> {
+    let mut budget = options.xml_budget();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| BlitzParseError::CorruptContainer {
+                format: "xlsx",
+                context: ErrorContext::part(part).at(reader.error_position()),
+                source: Some(Box::new(e)),
+            })?;
+        match &event {
+            Event::Start(_) => budget.open()?,
+            Event::Empty(_) => {
+                budget.open()?;
+                budget.close();
+            }
+            Event::End(_) => budget.close(),
+            Event::Eof => return Ok(()),
+            _ => {}
+        }
+        handle(&event);
+    }
+}
+
+/// An attribute by local name, ignoring its namespace prefix.
+fn attribute(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_references() {
+        assert_eq!(parse_cell("A1"), Some((0, 0)));
+        assert_eq!(parse_cell("$AB$12"), Some((27, 11)));
+        assert_eq!(parse_cell("A0"), None);
+        assert_eq!(range_name((27, 11), (27, 11)), "AB12");
+        assert_eq!(range_name((0, 0), (25, 2)), "A1:Z3");
+        assert_eq!(parse_range("B2:AA10"), Some(((1, 1), (26, 9))));
+    }
+
+    #[test]
+    fn test_date_formats_and_serials() {
+        assert_eq!(DateFormat::builtin(14), Some(DateFormat::Date));
+        assert_eq!(DateFormat::builtin(2), None);
+        assert_eq!(
+            DateFormat::from_code("yyyy-mm-dd h:mm"),
+            Some(DateFormat::DateTime)
+        );
+        assert_eq!(DateFormat::from_code("[h]:mm"), Some(DateFormat::Time));
+        assert_eq!(DateFormat::from_code("[Red]0.00"), None);
+        assert_eq!(DateFormat::from_code("0.0 \"days\""), None);
+        assert_eq!(DateFormat::from_code("#,##0;[Red]dd"), None);
+        assert_eq!(DateFormat::from_code("0.00E+00"), None);
+
+        let date = |serial| DateFormat::Date.render(serial, false);
+        assert_eq!(date(45352.0).as_deref(), Some("2024-03-01"));
+        assert_eq!(date(1.0).as_deref(), Some("1900-01-01"));
+        assert_eq!(date(59.0).as_deref(), Some("1900-02-28"));
+        assert_eq!(date(60.0), None);
+        assert_eq!(date(61.0).as_deref(), Some("1900-03-01"));
+        assert_eq!(
+            DateFormat::Date.render(0.0, true).as_deref(),
+            Some("1904-01-01")
+        );
+        assert_eq!(
+            DateFormat::DateTime
+                .render(45352.5729166667, false)
+                .as_deref(),
+            Some("2024-03-01 13:45:00")
+        );
+        assert_eq!(
+            DateFormat::Time.render(0.999999, false).as_deref(),
+            Some("00:00:00")
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use crate::errors::Cause;
+use pyo3::prelude::*;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A shared flag for stopping an extraction from another thread. Clones share
+/// the flag; parsers check it between pages, XML elements and text blocks and
+/// fail with `BlitzParseError::Cancelled` once it is set.
+#[pyclass]
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+#[pymethods]
+impl CancellationToken {
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// One progress update: `done` of `total` units through the current stage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    /// What is being processed: `pdf`, `docx`, `txt` or `graph`.
+    pub stage: &'static str,
+    /// `pages`, `bytes` or `chunks`.
+    pub unit: &'static str,
+    pub done: u64,
+    /// Known for pages and bytes; `None` while building a graph.
+    pub total: Option<u64>,
+}
+
+type Callback = dyn Fn(&Progress) -> Result<(), Cause> + Send + Sync;
+
+/// A callback receiving `Progress` updates, possibly from worker threads.
+#[derive(Clone)]
+pub struct ProgressCallback {
+    callback: Arc<Callback>,
+    /// The first error the callback returned, until a checkpoint raises it.
+    failure: Arc<Mutex<Option<Cause>>>,
+}
+
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+        ProgressCallback::fallible(move |progress| {
+            callback(progress);
+            Ok(())
+        })
+    }
+
+    /// A callback that can stop the extraction by failing. The error fails
+    /// the extraction at its next checkpoint as `BlitzParseError::Callback`.
+    pub fn fallible(
+        callback: impl Fn(&Progress) -> Result<(), Cause> + Send + Sync + 'static,
+    ) -> Self {
+        ProgressCallback {
+            callback: Arc::new(callback),
+            failure: Arc::default(),
+        }
+    }
+
+    /// Wrap a Python callable, called with a dict of the `Progress` fields.
+    /// An exception it raises stops the extraction and is re-raised.
+    pub fn from_py(callback: Py<PyAny>) -> Self {
+        ProgressCallback::fallible(move |progress| {
+            Python::attach(|py| {
+                let dict = pyo3::types::PyDict::new(py);
+                dict.set_item("stage", progress.stage)
+                    .and_then(|_| dict.set_item("unit", progress.unit))
+                    .and_then(|_| dict.set_item("done", progress.done))
+                    .and_then(|_| dict.set_item("total", progress.total))
+                    .and_then(|_| callback.call1(py, (dict,)))
+                    .map(drop)
+                    .map_err(|e| Box::new(e) as Cause)
+            })
+        })
+    }
+
+    /// Pass on an update, unless the callback has already failed.
+    pub fn call(&self, progress: &Progress) {
+        let mut failure = self.failure.lock().unwrap();
+        if failure.is_none() {
+            if let Err(e) = (self.callback)(progress) {
+                *failure = Some(e);
+            }
+        }
+    }
+
+    /// The error the callback failed with, if any, clearing it.
+    pub(crate) fn take_failure(&self) -> Option<Cause> {
+        self.failure.lock().unwrap().take()
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::BlitzParseError;
+    use crate::options::ExtractOptions;
+    use crate::parsers::txt::decode_text_with;
+
+    #[test]
+    fn test_text_decoding_reports_progress_and_cancels() {
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        let sink = updates.clone();
+        let options = ExtractOptions {
+            progress: Some(ProgressCallback::new(move |p| {
+                sink.lock().unwrap().push(p.clone())
+            })),
+            cancel: Some(CancellationToken::new()),
+            ..Default::default()
+        };
+
+        let bytes = "línea de texto\n".repeat(200_000);
+        let decoded = decode_text_with(bytes.as_bytes(), false, &options).unwrap();
+        assert_eq!(decoded.text, bytes);
+        let updates = updates.lock().unwrap();
+        assert!(updates.len() > 1);
+        assert_eq!(
+            updates.last(),
+            Some(&Progress {
+                stage: "txt",
+                unit: "bytes",
+                done: bytes.len() as u64,
+                total: Some(bytes.len() as u64),
+            })
+        );
+
+        // Clones share the flag, so a handle kept by the caller stops the parse.
+        options.cancel.clone().unwrap().cancel();
+        assert!(matches!(
+            decode_text_with(bytes.as_bytes(), false, &options),
+            Err(BlitzParseError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn test_a_failing_callback_stops_the_parse_once() {
+        let calls = Arc::new(Mutex::new(0));
+        let counter = calls.clone();
+        let options = ExtractOptions {
+            progress: Some(ProgressCallback::fallible(move |_| {
+                *counter.lock().unwrap() += 1;
+                Err("stop here".into())
+            })),
+            ..Default::default()
+        };
+
+        let bytes = "line of text\n".repeat(200_000);
+        let error = decode_text_with(bytes.as_bytes(), false, &options).unwrap_err();
+        assert!(matches!(error, BlitzParseError::Callback { .. }));
+        assert_eq!(error.to_string(), "Progress callback failed: stop here");
+        // Later updates are dropped once the callback has failed.
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        // The failure is raised once, so the options can be used again.
+        *calls.lock().unwrap() = 0;
+        assert!(options.checkpoint().is_ok());
+    }
+}
@@ -0,0 +1,405 @@
+use crate::normalize::{normalize, NormalizeOptions};
+use crate::parsers::xlsx::{parse_range, range_name};
+use serde::{Deserialize, Serialize};
+
+/// Where a range of extracted text came from.
+///
+/// As JSON the locator's fields sit beside the offsets, e.g.
+/// `{"start": 0, "end": 42, "kind": "pdf", "page": 1, "bbox": [72.0, 700.2, 540.0, 712.2]}`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Provenance {
+    /// Offsets in characters (Unicode scalar values) into the extracted
+    /// text; `end` is exclusive, so they slice it as Python strings do.
+    pub start: usize,
+    pub end: usize,
+    #[serde(flatten)]
+    pub locator: Locator,
+}
+
+/// Where text or a block is in its source file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Locator {
+    /// A paragraph of a DOCX part, counting every `w:p` from 0, including
+    /// those in tables. `last_paragraph` is set when more than one is covered.
+    Docx {
+        part: String,
+        paragraph: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        last_paragraph: Option<usize>,
+    },
+    /// A PDF page, counting from 1, and the box around the text on it as
+    /// `[x0, y0, x1, y1]` in PDF user space, i.e. points up and right from
+    /// the bottom left of the page.
+    Pdf {
+        page: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bbox: Option<[f64; 4]>,
+    },
+    /// A range of cells of an XLSX worksheet such as `A2:D2`, or without one,
+    /// the sheet's name.
+    Xlsx {
+        sheet: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        range: Option<String>,
+    },
+    /// A notebook cell, counting from 0.
+    Cell { cell: usize },
+}
+
+impl Locator {
+    /// Whether `other` lies within this locator: a paragraph in its range,
+    /// a box overlapping its box on the same page, or cells overlapping its
+    /// range of the same sheet.
+    pub fn contains(&self, other: &Locator) -> bool {
+        match (self, other) {
+            (
+                Locator::Docx {
+                    part,
+                    paragraph,
+                    last_paragraph,
+                },
+                Locator::Docx {
+                    part: other_part,
+                    paragraph: other_paragraph,
+                    ..
+                },
+            ) => {
+                part == other_part
+                    && (*paragraph..=last_paragraph.unwrap_or(*paragraph)).contains(other_paragraph)
+            }
+            (
+                Locator::Pdf { page, bbox },
+                Locator::Pdf {
+                    page: other_page,
+                    bbox: other_bbox,
+                },
+            ) => {
+                page == other_page
+                    && match (bbox, other_bbox) {
+                        (Some(a), Some(b)) => {
+                            a[0] < b[2] && b[0] < a[2] && a[1] < b[3] && b[1] < a[3]
+                        }
+                        _ => true,
+                    }
+            }
+            (
+                Locator::Xlsx { sheet, range },
+                Locator::Xlsx {
+                    sheet: other_sheet,
+                    range: other_range,
+                },
+            ) => {
+                sheet == other_sheet
+                    && match (range, other_range) {
+                        (Some(a), Some(b)) => match (parse_range(a), parse_range(b)) {
+                            (Some((a1, a2)), Some((b1, b2))) => {
+                                a1.0 <= b2.0 && b1.0 <= a2.0 && a1.1 <= b2.1 && b1.1 <= a2.1
+                            }
+                            _ => false,
+                        },
+                        (None, None) => true,
+                        _ => false,
+                    }
+            }
+            (Locator::Cell { cell }, Locator::Cell { cell: other_cell }) => cell == other_cell,
+            _ => false,
+        }
+    }
+
+    /// Extend this locator to cover `other` as well, if both are in the same
+    /// part, page, sheet or cell. Returns whether it was extended.
+    pub fn merge(&mut self, other: &Locator) -> bool {
+        match (self, other) {
+            (
+                Locator::Docx {
+                    part,
+                    paragraph,
+                    last_paragraph,
+                },
+                Locator::Docx {
+                    part: other_part,
+                    paragraph: other_paragraph,
+                    last_paragraph: other_last,
+                },
+            ) if part == other_part => {
+                let last = last_paragraph
+                    .unwrap_or(*paragraph)
+                    .max(other_last.unwrap_or(*other_paragraph));
+                *paragraph = (*paragraph).min(*other_paragraph);
+                *last_paragraph = (last > *paragraph).then_some(last);
+                true
+            }
+            (
+                Locator::Pdf { page, bbox },
+                Locator::Pdf {
+                    page: other_page,
+                    bbox: other_bbox,
+                },
+            ) if page == other_page => {
+                *bbox = match (*bbox, other_bbox) {
+                    (Some(a), Some(b)) => Some([
+                        a[0].min(b[0]),
+                        a[1].min(b[1]),
+                        a[2].max(b[2]),
+                        a[3].max(b[3]),
+                    ]),
+                    (a, b) => a.or(*b),
+                };
+                true
+            }
+            (
+                Locator::Xlsx { sheet, range },
+                Locator::Xlsx {
+                    sheet: other_sheet,
+                    range: other_range,
+                },
+            ) if sheet == other_sheet => {
+                let ranges = (
+                    range.as_deref().and_then(parse_range),
+                    other_range.as_deref().and_then(parse_range),
+                );
+                if let (Some((a1, a2)), Some((b1, b2))) = ranges {
+                    *range = Some(range_name(
+                        (a1.0.min(b1.0), a1.1.min(b1.1)),
+                        (a2.0.max(b2.0), a2.1.max(b2.1)),
+                    ));
+                } else if range.is_none() {
+                    range.clone_from(other_range);
+                }
+                true
+            }
+            (Locator::Cell { cell }, Locator::Cell { cell: other_cell }) => cell == other_cell,
+            _ => false,
+        }
+    }
+}
+
+/// The locators of the entries of `provenance` overlapping the characters
+/// `start..end`, with neighbours in the same part, page, sheet or cell
+/// merged. `provenance` must be in text order without overlaps, as parsers
+/// produce it.
+pub fn locate(provenance: &[Provenance], start: usize, end: usize) -> Vec<Locator> {
+    let first = provenance.partition_point(|p| p.end <= start);
+    let last = provenance.partition_point(|p| p.start < end);
+    let mut locators: Vec<Locator> = Vec::new();
+    for entry in provenance.get(first..last).unwrap_or_default() {
+        let merged = locators
+            .last_mut()
+            .is_some_and(|locator| locator.merge(&entry.locator));
+        if !merged {
+            locators.push(entry.locator.clone());
+        }
+    }
+    locators
+}
+
+/// Converts between byte and character offsets into a text. Offsets are
+/// counted on from the previous one, so converting them in ascending order
+/// takes one pass over the text.
+pub(crate) struct Offsets<'a> {
+    text: &'a str,
+    byte: usize,
+    char: usize,
+}
+
+impl<'a> Offsets<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        Offsets {
+            text,
+            byte: 0,
+            char: 0,
+        }
+    }
+
+    /// The character offset of the byte offset `byte`, a char boundary.
+    pub(crate) fn char(&mut self, byte: usize) -> usize {
+        if byte < self.byte {
+            (self.byte, self.char) = (0, 0);
+        }
+        self.char += self.text[self.byte..byte].chars().count();
+        self.byte = byte;
+        self.char
+    }
+
+    /// The byte offset of the character offset `char`, clamped to the text.
+    pub(crate) fn byte(&mut self, char: usize) -> usize {
+        if char < self.char {
+            (self.byte, self.char) = (0, 0);
+        }
+        let rest = &self.text[self.byte..];
+        let skip = rest
+            .char_indices()
+            .nth(char - self.char)
+            .map_or(rest.len(), |(i, _)| i);
+        self.byte += skip;
+        self.char += rest[..skip].chars().count();
+        self.byte
+    }
+}
+
+/// Turn provenance recorded as byte offsets into `text` into character
+/// offsets.
+pub(crate) fn bytes_to_chars(text: &str, provenance: &mut [Provenance]) {
+    let mut offsets = Offsets::new(text);
+    for entry in provenance {
+        entry.start = offsets.char(entry.start);
+        entry.end = offsets.char(entry.end);
+    }
+}
+
+/// Normalize `text` one provenance range at a time, so the offsets can be
+/// moved to the normalized text exactly. Ranges that normalize to nothing are
+/// dropped; the text between ranges is only whitespace-collapsed, or
+/// normalized like a range if it isn't whitespace.
+pub(crate) fn normalize_ranges(
+    text: &str,
+    provenance: &[Provenance],
+    options: &NormalizeOptions,
+) -> (String, Vec<Provenance>) {
+    let mut offsets = Offsets::new(text);
+    let mut out = String::with_capacity(text.len());
+    let mut out_chars = 0;
+    let mut entries = Vec::with_capacity(provenance.len());
+    let mut gap_start = 0;
+    for entry in provenance {
+        let start = offsets.byte(entry.start).max(gap_start);
+        let end = offsets.byte(entry.end).max(start);
+        let normalized = normalize(&text[start..end], options);
+        if normalized.is_empty() {
+            continue;
+        }
+        let gap = normalize_gap(&text[gap_start..start], options, out.is_empty());
+        out_chars += gap.chars().count();
+        out.push_str(&gap);
+        let chars = normalized.chars().count();
+        entries.push(Provenance {
+            start: out_chars,
+            end: out_chars + chars,
+            locator: entry.locator.clone(),
+        });
+        out_chars += chars;
+        out.push_str(&normalized);
+        gap_start = end;
+    }
+    let tail = normalize_gap(&text[gap_start..], options, out.is_empty());
+    if !(options.collapse_whitespace && tail.trim().is_empty()) {
+        out.push_str(&tail);
+    }
+    (out, entries)
+}
+
+/// The text between two ranges. Collapsing whitespace keeps one blank line
+/// at most, as `normalize` does, and none at the start.
+fn normalize_gap(gap: &str, options: &NormalizeOptions, first: bool) -> String {
+    let text = normalize(gap, options);
+    if !options.collapse_whitespace {
+        return text;
+    }
+    let newlines = gap.matches('\n').count();
+    let separator = match newlines {
+        _ if first => "",
+        0 if gap.is_empty() => "",
+        0 => " ",
+        1 => "\n",
+        _ => "\n\n",
+    };
+    // Text no range covers goes on a line of its own.
+    match (text.is_empty(), first) {
+        (true, _) => separator.to_string(),
+        (false, true) => format!("{}\n", text),
+        (false, false) => format!("\n{}\n", text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pdf(start: usize, end: usize, page: u32, bbox: [f64; 4]) -> Provenance {
+        Provenance {
+            start,
+            end,
+            locator: Locator::Pdf {
+                page,
+                bbox: Some(bbox),
+            },
+        }
+    }
+
+    #[test]
+    fn test_normalizes_ranges_keeping_offsets_exact() {
+        let text = "  caf\u{e9} \u{fb01}ne \n\n\n exam-\nple\u{200b}\n  next ";
+        let (start, end) = (text.find("exam").unwrap(), text.find('\u{200b}').unwrap());
+        let mut provenance = vec![
+            pdf(2, text.find(" \n").unwrap(), 1, [0.0, 90.0, 50.0, 100.0]),
+            pdf(start, end + 3, 1, [0.0, 60.0, 40.0, 80.0]),
+            pdf(
+                text.find("next").unwrap(),
+                text.len() - 1,
+                2,
+                [0.0, 0.0, 10.0, 10.0],
+            ),
+        ];
+        bytes_to_chars(text, &mut provenance);
+        assert_eq!(
+            text.chars()
+                .skip(provenance[1].start)
+                .take(4)
+                .collect::<String>(),
+            "exam"
+        );
+
+        let (normalized, entries) =
+            normalize_ranges(text, &provenance, &NormalizeOptions::default());
+        assert_eq!(normalized, "caf\u{e9} fine\n\nexample\nnext");
+        let slices: Vec<String> = entries
+            .iter()
+            .map(|p| {
+                normalized
+                    .chars()
+                    .skip(p.start)
+                    .take(p.end - p.start)
+                    .collect()
+            })
+            .collect();
+        assert_eq!(slices, ["caf\u{e9} fine", "example", "next"]);
+
+        // The chunk spanning the first two paragraphs is on one page.
+        assert_eq!(
+            locate(&entries, 5, 12),
+            vec![Locator::Pdf {
+                page: 1,
+                bbox: Some([0.0, 60.0, 50.0, 100.0]),
+            }]
+        );
+        assert_eq!(locate(&entries, 18, 20).len(), 1);
+        assert!(locate(&entries, 9, 10).is_empty());
+    }
+
+    #[test]
+    fn test_merges_docx_and_xlsx_locators() {
+        let docx = |paragraph| Locator::Docx {
+            part: "word/document.xml".to_string(),
+            paragraph,
+            last_paragraph: None,
+        };
+        let mut locator = docx(4);
+        assert!(locator.merge(&docx(7)));
+        assert!(locator.contains(&docx(6)) && !locator.contains(&docx(8)));
+        assert_eq!(
+            serde_json::to_string(&locator).unwrap(),
+            r#"{"kind":"docx","part":"word/document.xml","paragraph":4,"last_paragraph":7}"#
+        );
+
+        let xlsx = |range: &str| Locator::Xlsx {
+            sheet: "Data".to_string(),
+            range: Some(range.to_string()),
+        };
+        let mut locator = xlsx("B2:C2");
+        assert!(locator.merge(&xlsx("A5")));
+        assert_eq!(locator, xlsx("A2:C5"));
+        assert!(locator.contains(&xlsx("C4:D9")) && !locator.contains(&xlsx("D1")));
+        assert!(!locator.merge(&docx(0)));
+    }
+}
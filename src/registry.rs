@@ -0,0 +1,472 @@
+use crate::document::ParsedDocument;
+use crate::errors::BlitzParseError;
+use crate::options::ExtractOptions;
+use crate::parsers;
+use crate::parsers::sniff::sniff_format;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// The input handed to a `Parser`: the file's bytes and, when known, where
+/// they came from.
+#[derive(Debug, Clone, Copy)]
+pub struct Source<'a> {
+    pub bytes: &'a [u8],
+    /// Path of the file on disk, or the member path inside an archive.
+    pub path: Option<&'a str>,
+    /// Lower-cased extension without the dot, if any.
+    pub extension: Option<&'a str>,
+    /// Limits the parser should respect where its format allows.
+    pub options: &'a ExtractOptions,
+}
+
+/// A file format handler. Implement this to teach `extract_text` and
+/// `build_graph` a new format, then add it with `ParserRegistry::register`.
+pub trait Parser: Send + Sync {
+    /// A short identifier, e.g. `pdf`.
+    fn name(&self) -> String;
+
+    /// Extensions handled, lower-case and without the dot.
+    fn extensions(&self) -> Vec<String>;
+
+    /// MIME types handled, e.g. `application/pdf`.
+    fn mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Whether `bytes` look like this format. Used for files without an extension.
+    fn sniff(&self, _bytes: &[u8]) -> bool {
+        false
+    }
+
+    fn parse(&self, source: &Source) -> Result<ParsedDocument, BlitzParseError>;
+}
+
+struct Entry {
+    parser: Arc<dyn Parser>,
+    builtin: bool,
+}
+
+/// The set of parsers consulted by `extract_text`, `extract_text_from_bytes`
+/// and `build_graph`. Parsers registered later take precedence, so a custom
+/// parser can replace a built-in one for the same extension.
+pub struct ParserRegistry {
+    entries: Vec<Entry>,
+}
+
+impl Default for ParserRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+impl ParserRegistry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        ParserRegistry {
+            entries: Vec::new(),
+        }
+    }
+
+    /// A registry holding every built-in format.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        for builtin in BUILTINS {
+            registry.entries.push(Entry {
+                parser: Arc::new(*builtin),
+                builtin: true,
+            });
+        }
+        registry
+    }
+
+    /// The process-wide registry used by the top-level extraction functions.
+    pub fn global() -> &'static RwLock<ParserRegistry> {
+        static GLOBAL: OnceLock<RwLock<ParserRegistry>> = OnceLock::new();
+        GLOBAL.get_or_init(|| RwLock::new(ParserRegistry::with_builtins()))
+    }
+
+    pub fn register(&mut self, parser: Arc<dyn Parser>) {
+        self.entries.push(Entry {
+            parser,
+            builtin: false,
+        });
+    }
+
+    /// Names of the registered parsers, most recently registered first.
+    pub fn names(&self) -> Vec<String> {
+        self.entries.iter().rev().map(|e| e.parser.name()).collect()
+    }
+
+    pub fn for_extension(&self, extension: &str) -> Option<Arc<dyn Parser>> {
+        self.entry_for_extension(extension)
+            .map(|e| e.parser.clone())
+    }
+
+    pub fn for_mime_type(&self, mime: &str) -> Option<Arc<dyn Parser>> {
+        let mime = mime.split(';').next().unwrap_or(mime).trim();
+        self.entries
+            .iter()
+            .rev()
+            .find(|e| {
+                e.parser
+                    .mime_types()
+                    .iter()
+                    .any(|m| m.eq_ignore_ascii_case(mime))
+            })
+            .map(|e| e.parser.clone())
+    }
+
+    /// Every registered parser, most recently registered first.
+    pub fn parsers(&self) -> Vec<Arc<dyn Parser>> {
+        self.entries
+            .iter()
+            .rev()
+            .map(|e| e.parser.clone())
+            .collect()
+    }
+
+    /// True if `extension` is handled by a built-in parser that has not been overridden.
+    pub fn is_builtin(&self, extension: &str) -> bool {
+        self.entry_for_extension(extension)
+            .is_some_and(|e| e.builtin)
+    }
+
+    fn entry_for_extension(&self, extension: &str) -> Option<&Entry> {
+        self.entries.iter().rev().find(|e| {
+            e.parser
+                .extensions()
+                .iter()
+                .any(|ext| ext.eq_ignore_ascii_case(extension))
+        })
+    }
+}
+
+/// Pick the parser for a file: by extension when it has one, otherwise by
+/// sniffing its content, taking the most recently registered parser whose
+/// sniffer accepts it.
+///
+/// Sniffers run on a snapshot of the parsers with the lock released, since a
+/// Python sniffer needs the GIL, which a thread registering a parser may hold
+/// while it waits for the lock.
+pub fn resolve(
+    registry: &RwLock<ParserRegistry>,
+    path: &str,
+    bytes: &[u8],
+) -> Result<Arc<dyn Parser>, BlitzParseError> {
+    if let Some(extension) = Path::new(path).extension().and_then(|e| e.to_str()) {
+        return registry
+            .read()
+            .unwrap()
+            .for_extension(extension)
+            .ok_or_else(|| BlitzParseError::unsupported(extension));
+    }
+    let parsers = registry.read().unwrap().parsers();
+    parsers
+        .into_iter()
+        .find(|parser| parser.sniff(bytes))
+        .ok_or_else(|| BlitzParseError::unsupported("No extension"))
+}
+
+type ParseFn = fn(&Source) -> Result<ParsedDocument, BlitzParseError>;
+
+/// A built-in format backed by the functions in `crate::parsers`.
+#[derive(Clone, Copy)]
+struct Builtin {
+    name: &'static str,
+    extensions: &'static [&'static str],
+    mime_types: &'static [&'static str],
+    sniff: fn(&[u8]) -> bool,
+    parse: ParseFn,
+}
+
+impl Parser for Builtin {
+    fn name(&self) -> String {
+        self.name.to_string()
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        self.extensions.iter().map(|e| e.to_string()).collect()
+    }
+
+    fn mime_types(&self) -> Vec<String> {
+        self.mime_types.iter().map(|m| m.to_string()).collect()
+    }
+
+    fn sniff(&self, bytes: &[u8]) -> bool {
+        (self.sniff)(bytes)
+    }
+
+    fn parse(&self, source: &Source) -> Result<ParsedDocument, BlitzParseError> {
+        (self.parse)(source)
+    }
+}
+
+fn never(_: &[u8]) -> bool {
+    false
+}
+
+fn text(result: Result<String, BlitzParseError>) -> Result<ParsedDocument, BlitzParseError> {
+    result.map(ParsedDocument::from_text)
+}
+
+/// Built-in parsers in ascending priority: when sniffing, later entries are
+/// tried first, so plain text comes first as the catch-all.
+const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "txt",
+        extensions: &["txt"],
+        mime_types: &["text/plain"],
+        sniff: |b| sniff_format(b) == Some("txt"),
+        parse: |s| text(parsers::txt::parse_txt_bytes_with(s.bytes, s.options)),
+    },
+    Builtin {
+        name: "code",
+        extensions: parsers::code::EXTENSIONS,
+        mime_types: &[
+            "text/x-rust",
+            "text/x-python",
+            "text/javascript",
+            "application/javascript",
+            "application/typescript",
+            "text/x-go",
+            "text/x-c",
+            "text/x-c++",
+            "text/x-java-source",
+            "application/x-sh",
+        ],
+        sniff: |b| {
+            b.starts_with(b"#!")
+                && parsers::code::Language::from_shebang(&String::from_utf8_lossy(
+                    &b[..b.len().min(256)],
+                ))
+                .is_some()
+        },
+        parse: |s| text(parsers::code::parse_code_bytes(s.bytes)),
+    },
+    Builtin {
+        name: "pdf",
+        extensions: &["pdf"],
+        mime_types: &["application/pdf"],
+        sniff: |b| sniff_format(b) == Some("pdf"),
+        parse: |s| parsers::pdf::read_pdf_bytes_with(s.bytes, s.options),
+    },
+    Builtin {
+        name: "csv",
+        extensions: &["csv", "tsv"],
+        mime_types: &["text/csv", "text/tab-separated-values"],
+        sniff: never,
+        parse: |s| text(parsers::csv::parse_csv_bytes(s.bytes).map(|table| table.text())),
+    },
+    Builtin {
+        name: "doc",
+        extensions: &["doc"],
+        mime_types: &["application/msword"],
+        sniff: |b| sniff_format(b) == Some("doc"),
+        parse: |s| text(parsers::doc::parse_doc_bytes(s.bytes)),
+    },
+    Builtin {
+        name: "docx",
+        extensions: &["docx"],
+        mime_types: &["application/vnd.openxmlformats-officedocument.wordprocessingml.document"],
+        sniff: |b| sniff_format(b) == Some("docx"),
+        parse: |s| parsers::docx::read_docx_bytes_with(s.bytes, s.options),
+    },
+    Builtin {
+        name: "xlsx",
+        extensions: &["xlsx"],
+        mime_types: &["application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"],
+        sniff: |b| sniff_format(b) == Some("xlsx"),
+        parse: |s| parsers::xlsx::read_xlsx_bytes_with(s.bytes, s.options),
+    },
+    Builtin {
+        name: "eml",
+        extensions: &["eml"],
+        mime_types: &["message/rfc822"],
+        sniff: never,
+        parse: |s| {
+            text(parsers::email::parse_email_bytes_with(s.bytes, s.options).map(|m| m.text()))
+        },
+    },
+    Builtin {
+        name: "mbox",
+        extensions: &["mbox"],
+        mime_types: &["application/mbox"],
+        sniff: never,
+        parse: |s| {
+            let mbox = parsers::email::parse_mbox_bytes_with(s.bytes, s.options)?;
+            Ok(ParsedDocument {
+                text: mbox.text(),
+                warnings: mbox.warnings,
+                ..Default::default()
+            })
+        },
+    },
+    Builtin {
+        name: "epub",
+        extensions: &["epub"],
+        mime_types: &["application/epub+zip"],
+        sniff: |b| sniff_format(b) == Some("epub"),
+        parse: |s| {
+            let book =
+                parsers::epub::read_epub_from_with(std::io::Cursor::new(s.bytes), s.options)?;
+            Ok(ParsedDocument {
+                text: book.text(),
+                metadata: book.metadata.to_map(),
+                sections: book.sections(),
+                warnings: book.warnings,
+                ..Default::default()
+            })
+        },
+    },
+    Builtin {
+        name: "ipynb",
+        extensions: &["ipynb"],
+        mime_types: &["application/x-ipynb+json"],
+        sniff: never,
+        parse: |s| {
+            let notebook = parsers::notebook::read_ipynb_bytes(s.bytes)?;
+            Ok(ParsedDocument {
+                text: notebook.text(false),
+                metadata: notebook.metadata.to_map(),
+                ..Default::default()
+            })
+        },
+    },
+    Builtin {
+        name: "structured",
+        extensions: &["json", "jsonl", "ndjson", "xml"],
+        mime_types: &[
+            "application/json",
+            "application/x-ndjson",
+            "application/xml",
+            "text/xml",
+        ],
+        sniff: never,
+        parse: |s| {
+            text(parsers::structured::flatten_bytes_with(
+                s.bytes,
+                s.extension.unwrap_or("json"),
+                &parsers::structured::FlattenOptions::default(),
+                s.options,
+            ))
+        },
+    },
+    Builtin {
+        name: "rtf",
+        extensions: &["rtf"],
+        mime_types: &["application/rtf", "text/rtf"],
+        sniff: |b| sniff_format(b) == Some("rtf"),
+        parse: |s| text(parsers::rtf::rtf_to_text(s.bytes)),
+    },
+    Builtin {
+        name: "subtitle",
+        extensions: &["srt", "vtt"],
+        mime_types: &["application/x-subrip", "text/vtt"],
+        sniff: |b| b.starts_with(b"WEBVTT"),
+        parse: |s| {
+            let transcript =
+                parsers::subtitle::read_subtitles_bytes(s.bytes, s.extension.unwrap_or("vtt"))?;
+            Ok(ParsedDocument {
+                text: parsers::subtitle::cues_text(&transcript.cues),
+                warnings: transcript.warnings,
+                ..Default::default()
+            })
+        },
+    },
+    Builtin {
+        name: "archive",
+        extensions: &["zip", "tar", "gz", "tgz"],
+        mime_types: &["application/zip", "application/x-tar", "application/gzip"],
+        sniff: |b| matches!(sniff_format(b), Some("zip" | "tar" | "gz")),
+        parse: |s| {
+            // The archive walker picks the container type from the name.
+            let name = match (s.path, s.extension) {
+                (Some(path), _) => path.to_string(),
+                (None, Some(ext)) => format!("archive.{}", ext),
+                (None, None) => "archive".to_string(),
+            };
+            text(
+                parsers::archive::extract_archive_bytes_with(
+                    s.bytes,
+                    &name,
+                    &parsers::archive::ArchiveLimits::default(),
+                    s.options,
+                )
+                .map(|members| parsers::archive::join_members(&members)),
+            )
+        },
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Shouty;
+
+    impl Parser for Shouty {
+        fn name(&self) -> String {
+            "shouty".to_string()
+        }
+
+        fn extensions(&self) -> Vec<String> {
+            vec!["txt".to_string(), "shout".to_string()]
+        }
+
+        fn sniff(&self, bytes: &[u8]) -> bool {
+            bytes.starts_with(b"SHOUT:")
+        }
+
+        fn parse(&self, source: &Source) -> Result<ParsedDocument, BlitzParseError> {
+            Ok(ParsedDocument::from_text(
+                String::from_utf8_lossy(source.bytes).to_uppercase(),
+            ))
+        }
+    }
+
+    #[test]
+    fn test_custom_parser_overrides_builtin() {
+        let mut registry = ParserRegistry::with_builtins();
+        assert!(registry.is_builtin("txt"));
+        assert_eq!(registry.for_extension("PDF").unwrap().name(), "pdf");
+        assert_eq!(
+            registry
+                .for_mime_type("text/csv; charset=utf-8")
+                .unwrap()
+                .name(),
+            "csv"
+        );
+
+        registry.register(Arc::new(Shouty));
+        assert!(!registry.is_builtin("txt"));
+        assert_eq!(registry.names()[0], "shouty");
+
+        let registry = RwLock::new(registry);
+        let parser = resolve(&registry, "notes.txt", b"hi").unwrap();
+        let source = Source {
+            bytes: b"hi",
+            path: Some("notes.txt"),
+            extension: Some("txt"),
+            options: &ExtractOptions::default(),
+        };
+        assert_eq!(parser.parse(&source).unwrap().text, "HI");
+        assert_eq!(
+            resolve(&registry, "README", b"SHOUT: x").unwrap().name(),
+            "shouty"
+        );
+    }
+
+    #[test]
+    fn test_sniffing_without_extension() {
+        let registry = RwLock::new(ParserRegistry::with_builtins());
+        let sniffed = |bytes: &[u8]| resolve(&registry, "upload", bytes).map(|p| p.name());
+        assert_eq!(sniffed(b"%PDF-1.7\n").unwrap(), "pdf");
+        assert_eq!(
+            sniffed(b"#!/usr/bin/env python3\nprint(1)\n").unwrap(),
+            "code"
+        );
+        assert_eq!(sniffed(b"plain words").unwrap(), "txt");
+        assert!(sniffed(b"\x89PNG\r\n\x1a\n\0").is_err());
+        assert!(resolve(&registry, "image.png", b"").is_err());
+    }
+}
@@ -0,0 +1,468 @@
+use crate::errors::{BlitzParseError, ErrorContext};
+use crate::graph_builder::chunker::{ChunkingStrategy, StreamingChunker};
+use crate::input::FileBytes;
+use crate::normalize::{normalize, NormalizeOptions};
+use crate::options::ExtractOptions;
+use crate::parsers::pdf::PageTexts;
+use crate::parsers::txt::{detect_encoding_from, EncodingGuess};
+use crate::registry::ParserRegistry;
+use encoding_rs::{Decoder, DecoderResult};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes read from a text file per block.
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// Text of a file as a sequence of blocks that concatenate to the full text.
+pub type Blocks = Box<dyn Iterator<Item = Result<String, BlitzParseError>> + Send>;
+
+/// Extract a file incrementally. Plain text is decoded in fixed-size blocks,
+/// after a first pass over the file to detect its encoding, and PDFs page
+/// by page, so the text waiting to be consumed is at most one
+/// block or page; other formats are extracted whole and yielded as a single
+/// block.
+///
+/// With `options.normalize`, text is held back to the next blank line, up
+/// to one more block's worth. A PDF's object structure is loaded whole
+/// before its first page is yielded.
+///
+/// The limits in `options` apply as they do to `extract_text_with`, with the
+/// deadline and cancellation checked before each block.
+pub fn iter_blocks(path: &str, options: &ExtractOptions) -> Result<Blocks, BlitzParseError> {
+    let options = options.started();
+    options
+        .check_input(std::fs::metadata(path)?.len())
+        .map_err(|e| e.with_path(path))?;
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase());
+    let parser = extension.as_deref().and_then(|ext| {
+        let registry = ParserRegistry::global().read().unwrap();
+        registry
+            .for_extension(ext)
+            .filter(|_| registry.is_builtin(ext))
+    });
+
+    let normalize = options.normalize.clone();
+    let blocks = match parser.as_ref().map(|p| p.name()).as_deref() {
+        Some("txt") => checked(TextBlocks::open(path, &options)?, options),
+        Some("pdf") => checked(pdf_pages(path, &options)?, options),
+        // Normalized as a whole by `extract_text_with`.
+        _ => {
+            return Ok(Box::new(std::iter::once(crate::extract_text_with(
+                path, &options,
+            ))))
+        }
+    };
+    Ok(match normalize {
+        Some(normalize) => normalized(blocks, normalize),
+        None => blocks,
+    })
+}
+
+/// Normalize blocks so that they concatenate to the whole text normalized.
+/// Normalizing joins the lines of a paragraph, so text is held back to the
+/// last blank line, and each run of paragraphs is normalized on its own.
+/// At most `MAX_HELD` bytes are held back: past that, a run is cut at the
+/// last line break or space, so a word hyphenated across exactly that line
+/// break is not rejoined.
+fn normalized(mut blocks: Blocks, options: NormalizeOptions) -> Blocks {
+    let mut pending = String::new();
+    // Whitespace between the last text yielded and what follows it, which
+    // collapsing turns into a space, a line break or a blank line.
+    let mut gap = Gap::default();
+    let mut started = false;
+    let mut done = false;
+    Box::new(std::iter::from_fn(move || loop {
+        if done {
+            return None;
+        }
+        let run = match blocks.next() {
+            Some(Ok(block)) => {
+                pending.push_str(&block);
+                let Some(cut) = cut_point(&pending) else {
+                    continue;
+                };
+                let rest = pending.split_off(cut);
+                std::mem::replace(&mut pending, rest)
+            }
+            Some(Err(e)) => {
+                done = true;
+                return Some(Err(e));
+            }
+            None => {
+                done = true;
+                std::mem::take(&mut pending)
+            }
+        };
+        let mut text = normalize(&run, &options);
+        if !options.collapse_whitespace {
+            if !text.is_empty() {
+                return Some(Ok(text));
+            }
+            continue;
+        }
+        if text.is_empty() {
+            gap.add(&run);
+            continue;
+        }
+        gap.add(&run[..run.len() - run.trim_start().len()]);
+        if started {
+            text.insert_str(0, gap.separator());
+        }
+        started = true;
+        gap = Gap::default();
+        gap.add(&run[run.trim_end().len()..]);
+        return Some(Ok(text));
+    }))
+}
+
+/// Text held back for normalization before it is cut short of a blank line.
+const MAX_HELD: usize = BLOCK_SIZE;
+
+/// Where to cut `pending` so that what follows is at most `MAX_HELD` bytes:
+/// after the last blank line if that does, otherwise after the last line
+/// break or space in the final `MAX_HELD` bytes, otherwise at the end.
+fn cut_point(pending: &str) -> Option<usize> {
+    let window = pending.floor_char_boundary(pending.len().saturating_sub(MAX_HELD));
+    match pending.rfind("\n\n") {
+        Some(i) if i + 2 >= window => return Some(i + 2),
+        _ if pending.len() <= MAX_HELD => return None,
+        _ => {}
+    }
+    let tail = &pending[window..];
+    let cut = tail
+        .rfind('\n')
+        .or_else(|| tail.rfind(char::is_whitespace))
+        .map_or(tail.len(), |i| {
+            i + tail[i..].chars().next().map_or(0, char::len_utf8)
+        });
+    Some(window + cut)
+}
+
+/// A run of whitespace, as much of it as collapsing looks at.
+#[derive(Default)]
+struct Gap {
+    newlines: usize,
+    any: bool,
+}
+
+impl Gap {
+    fn add(&mut self, whitespace: &str) {
+        self.newlines += whitespace.matches('\n').count();
+        self.any |= !whitespace.is_empty();
+    }
+
+    /// What `normalize` collapses the gap to.
+    fn separator(&self) -> &'static str {
+        match (self.newlines, self.any) {
+            (2.., _) => "\n\n",
+            (1, _) => "\n",
+            (0, true) => " ",
+            (0, false) => "",
+        }
+    }
+}
+
+/// Check the deadline and cancellation before each block and once after the
+/// last, ending the stream at the first error.
+fn checked(
+    mut blocks: impl Iterator<Item = Result<String, BlitzParseError>> + Send + 'static,
+    options: ExtractOptions,
+) -> Blocks {
+    let mut failed = false;
+    Box::new(std::iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        let block = match options.checkpoint() {
+            Ok(()) => match blocks.next() {
+                Some(block) => block,
+                None => {
+                    failed = true;
+                    return options.checkpoint().err().map(Err);
+                }
+            },
+            Err(e) => Err(e),
+        };
+        failed = block.is_err();
+        Some(block)
+    }))
+}
+
+/// Chunks of a file, produced as it is extracted.
+pub struct ChunkIter {
+    blocks: Blocks,
+    chunker: Option<StreamingChunker>,
+    ready: VecDeque<String>,
+}
+
+/// Stream the chunks of a file as its blocks are extracted, with the same
+/// buffering as `iter_blocks`. The chunks are the same as chunking the output
+/// of `extract_text_with`.
+pub fn iter_chunks(
+    path: &str,
+    strategy: &ChunkingStrategy,
+    options: &ExtractOptions,
+) -> Result<ChunkIter, BlitzParseError> {
+    Ok(ChunkIter {
+        blocks: iter_blocks(path, options)?,
+        chunker: Some(StreamingChunker::new(*strategy)),
+        ready: VecDeque::new(),
+    })
+}
+
+impl Iterator for ChunkIter {
+    type Item = Result<String, BlitzParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(chunk) = self.ready.pop_front() {
+                return Some(Ok(chunk));
+            }
+            let chunker = self.chunker.as_mut()?;
+            match self.blocks.next() {
+                Some(Ok(block)) => self.ready.extend(chunker.push(&block)),
+                Some(Err(e)) => {
+                    // An extraction error ends the stream.
+                    self.chunker = None;
+                    return Some(Err(e));
+                }
+                None => {
+                    let chunker = self.chunker.take()?;
+                    self.ready.extend(chunker.finish());
+                }
+            }
+        }
+    }
+}
+
+/// A text file decoded block by block from the encoding detected in its head.
+struct TextBlocks {
+    path: String,
+    file: File,
+    len: u64,
+    encoding: EncodingGuess,
+    decoder: Decoder,
+    /// Bytes of the file decoded so far.
+    offset: u64,
+    options: ExtractOptions,
+    done: bool,
+}
+
+impl TextBlocks {
+    /// Detect the encoding over the whole file, as `extract_text` does, then
+    /// decode it from the start.
+    fn open(path: &str, options: &ExtractOptions) -> Result<TextBlocks, BlitzParseError> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let encoding = detect_encoding_from(&mut file, BLOCK_SIZE)?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok(TextBlocks {
+            path: path.to_string(),
+            file,
+            len,
+            encoding,
+            decoder: encoding.encoding.new_decoder_with_bom_removal(),
+            offset: 0,
+            options: options.clone(),
+            done: false,
+        })
+    }
+}
+
+impl Iterator for TextBlocks {
+    type Item = Result<String, BlitzParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut bytes = Vec::with_capacity(BLOCK_SIZE);
+        if let Err(e) = (&mut self.file)
+            .take(BLOCK_SIZE as u64)
+            .read_to_end(&mut bytes)
+        {
+            self.done = true;
+            return Some(Err(e.into()));
+        }
+
+        // A short read means the end of the file; flush the decoder.
+        let last = bytes.len() < BLOCK_SIZE;
+        self.done = last;
+        let capacity = self
+            .decoder
+            .max_utf8_buffer_length(bytes.len())
+            .unwrap_or(bytes.len() * 3 + 4);
+        let mut text = String::with_capacity(capacity);
+        let (result, read) = self
+            .decoder
+            .decode_to_string_without_replacement(&bytes, &mut text, last);
+        if let DecoderResult::Malformed(bad, extra) = result {
+            self.done = true;
+            // The malformed sequence ends `extra` bytes before what was read.
+            let offset = self.offset + (read - extra as usize - bad as usize) as u64;
+            return Some(Err(BlitzParseError::Encoding {
+                encoding: self.encoding.name(),
+                context: ErrorContext::default().at(offset),
+            }
+            .with_path(&self.path)));
+        }
+        self.offset += bytes.len() as u64;
+        self.options
+            .report("txt", "bytes", self.offset, Some(self.len));
+        Some(Ok(text))
+    }
+}
+
+/// A PDF's text, one page at a time.
+fn pdf_pages(path: &str, options: &ExtractOptions) -> Result<PageTexts, BlitzParseError> {
+    let bytes = FileBytes::open_with(path, options)?;
+    let doc = crate::parsers::pdf::load_document(&bytes).map_err(|e| e.with_path(path))?;
+    PageTexts::new(doc, options).map_err(|e| e.with_path(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_builder::chunker::chunk_text;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_streamed_text_chunks_match_extracted_text() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("log.txt");
+        let line = "2024-01-01 12:00:00 INFO café served request\n";
+        std::fs::write(&path, line.repeat(BLOCK_SIZE / line.len() * 3)).unwrap();
+        let path = path.to_str().unwrap();
+
+        let options = ExtractOptions::default();
+        let blocks: Vec<String> = iter_blocks(path, &options)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(blocks.len() > 1);
+        let text = crate::extract_text(path).unwrap();
+        assert_eq!(blocks.concat(), text);
+
+        let strategy = ChunkingStrategy::RecursiveCharacter {
+            chunk_size: 100,
+            overlap: 10,
+        };
+        let chunks: Vec<String> = iter_chunks(path, &strategy, &options)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(chunks, chunk_text(&text, &strategy));
+
+        let cancel = crate::progress::CancellationToken::default();
+        let options = ExtractOptions {
+            cancel: Some(cancel.clone()),
+            ..Default::default()
+        };
+        let mut blocks = iter_blocks(path, &options).unwrap();
+        assert!(blocks.next().unwrap().is_ok());
+        cancel.cancel();
+        assert!(matches!(
+            blocks.next(),
+            Some(Err(BlitzParseError::Cancelled))
+        ));
+        assert!(blocks.next().is_none());
+    }
+
+    #[test]
+    fn test_streamed_text_decodes_like_extracted_text() {
+        let dir = tempdir().unwrap();
+        let collect = |path: &str| {
+            iter_blocks(path, &ExtractOptions::default())
+                .unwrap()
+                .collect::<Result<Vec<String>, _>>()
+        };
+
+        // A lone surrogate well past the first block.
+        let path = dir.path().join("utf16.txt");
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "naïve café\n".repeat(BLOCK_SIZE / 10).encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        let offset = bytes.len() as u64;
+        bytes.extend_from_slice(&[0x00, 0xDC, b'x', 0x00]);
+        std::fs::write(&path, &bytes).unwrap();
+        let path = path.to_str().unwrap();
+        match (collect(path), crate::extract_text(path)) {
+            (
+                Err(BlitzParseError::Encoding { encoding, context }),
+                Err(BlitzParseError::Encoding {
+                    context: expected, ..
+                }),
+            ) => {
+                assert_eq!(encoding, "UTF-16LE");
+                assert_eq!(context.offset, Some(offset));
+                assert_eq!(context.offset, expected.offset);
+                assert_eq!(context.path.as_deref(), Some(path));
+            }
+            other => panic!("expected encoding errors, got {:?}", other),
+        }
+
+        // A stray byte after the first block is seen when detecting too.
+        let path = dir.path().join("latin1.txt");
+        let mut bytes = "café au lait\n".repeat(BLOCK_SIZE / 10).into_bytes();
+        bytes.extend_from_slice(b"na\xEFve\n");
+        std::fs::write(&path, &bytes).unwrap();
+        let path = path.to_str().unwrap();
+        assert_eq!(
+            collect(path).unwrap().concat(),
+            crate::extract_text(path).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_streamed_text_is_normalized_like_extracted_text() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        let paragraph = "The \u{fb01}rst   line is hy-\nphenated \u{201c}here\u{201d}.\n\n\n";
+        std::fs::write(&path, paragraph.repeat(BLOCK_SIZE / paragraph.len() * 3)).unwrap();
+        let path = path.to_str().unwrap();
+
+        let options = ExtractOptions {
+            normalize: Some(NormalizeOptions::default()),
+            ..Default::default()
+        };
+        let blocks: Vec<String> = iter_blocks(path, &options)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(blocks.len() > 1);
+        let text = crate::extract_text_with(path, &options).unwrap();
+        assert!(text.starts_with("The first line is hyphenated\n\"here\".\n\nThe"));
+        assert_eq!(blocks.concat(), text);
+    }
+
+    #[test]
+    fn test_normalized_blocks_stay_bounded_without_blank_lines() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("app.txt");
+        let line = "2024-01-01  12:00:00 \u{201c}INFO\u{201d}   served   request\n";
+        std::fs::write(&path, line.repeat(BLOCK_SIZE / line.len() * 8)).unwrap();
+        let path = path.to_str().unwrap();
+
+        let options = ExtractOptions {
+            normalize: Some(NormalizeOptions::default()),
+            ..Default::default()
+        };
+        let blocks: Vec<String> = iter_blocks(path, &options)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(blocks.len() > 4);
+        let longest = blocks.iter().map(String::len).max().unwrap();
+        assert!(longest <= MAX_HELD + BLOCK_SIZE);
+        assert_eq!(
+            blocks.concat(),
+            crate::extract_text_with(path, &options).unwrap()
+        );
+    }
+}
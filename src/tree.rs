@@ -0,0 +1,369 @@
+use crate::document::Warning;
+use crate::errors::BlitzParseError;
+use crate::options::ExtractOptions;
+use crate::parsers;
+use crate::provenance::Provenance;
+use crate::registry::ParserRegistry;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub use crate::provenance::Locator;
+
+/// Version of the JSON schema of `DocumentTree`. It changes when a field is
+/// removed, renamed or changes meaning; adding an optional field or a new
+/// block or locator type doesn't change it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A document as typed blocks, the form rendered as Markdown, HTML and JSON.
+///
+/// As JSON:
+///
+/// ```json
+/// {
+///   "schema_version": 1,
+///   "format": "docx",
+///   "blocks": [
+///     {"type": "heading", "level": 1, "spans": [{"text": "Summary"}],
+///      "style": "Heading1",
+///      "source": {"kind": "docx", "part": "word/document.xml", "paragraph": 0}},
+///     {"type": "paragraph", "spans": [{"text": "Plain "}, {"text": "bold", "bold": true}]},
+///     {"type": "list_item", "depth": 0, "ordered": false, "spans": [...]},
+///     {"type": "table", "rows": [[[{"text": "Key"}], [{"text": "Value"}]], ...]},
+///     {"type": "code", "language": "rust", "text": "fn main() {}"}
+///   ],
+///   "footnotes": [{"label": "1", "spans": [{"text": "A note."}]}],
+///   "warnings": [{"kind": "skipped", "message": "...", "context": {"part": "page 3"}}]
+/// }
+/// ```
+///
+/// Fields that are false, empty or unknown are left out, and are read back
+/// as such.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DocumentTree {
+    pub schema_version: u32,
+    /// The file's extension, lowercased.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    pub blocks: Vec<Block>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub footnotes: Vec<Footnote>,
+    /// Problems that were recovered from, such as an unreadable page.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
+}
+
+impl DocumentTree {
+    pub fn new(blocks: Vec<Block>) -> Self {
+        DocumentTree {
+            schema_version: SCHEMA_VERSION,
+            format: None,
+            blocks,
+            footnotes: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// A block-level element, with where it came from when that is known.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Block {
+    #[serde(flatten)]
+    pub kind: BlockKind,
+    /// The source format's name for the block's style, e.g. a DOCX style ID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Locator>,
+    /// Character offsets of the block's text in what `extract_text` returns
+    /// with the same options, for formats whose text records provenance.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<usize>,
+}
+
+impl From<BlockKind> for Block {
+    fn from(kind: BlockKind) -> Self {
+        Block {
+            kind,
+            style: None,
+            source: None,
+            start: None,
+            end: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlockKind {
+    Heading {
+        /// 1 for a top-level heading.
+        level: usize,
+        spans: Vec<Span>,
+    },
+    Paragraph {
+        spans: Vec<Span>,
+    },
+    /// An item of a list. Nested lists are items with a greater depth
+    /// following their parent item.
+    ListItem {
+        /// Nesting depth, 0 for a top-level item.
+        depth: usize,
+        ordered: bool,
+        spans: Vec<Span>,
+    },
+    /// Rows of cells; the first row is the header.
+    Table {
+        rows: Vec<Vec<Vec<Span>>>,
+    },
+    Code {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        language: Option<String>,
+        text: String,
+    },
+}
+
+/// A run of text sharing one set of inline formatting.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Span {
+    pub text: String,
+    #[serde(skip_serializing_if = "is_false")]
+    pub bold: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    pub italic: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    pub code: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    /// A reference to the footnote with this label; `text` is ignored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub footnote: Option<String>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl Span {
+    pub fn plain(text: impl Into<String>) -> Self {
+        Span {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn same_format(&self, other: &Span) -> bool {
+        self.bold == other.bold
+            && self.italic == other.italic
+            && self.code == other.code
+            && self.footnote.is_none()
+            && other.footnote.is_none()
+    }
+}
+
+/// A footnote, rendered after the body.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Footnote {
+    pub label: String,
+    pub spans: Vec<Span>,
+}
+
+/// Append `span`, extending the last span instead if it has the same
+/// formatting and link.
+pub fn push_span(spans: &mut Vec<Span>, span: Span) {
+    match spans.last_mut() {
+        Some(last) if last.same_format(&span) && last.link == span.link => {
+            last.text.push_str(&span.text)
+        }
+        _ => spans.push(span),
+    }
+}
+
+/// Trim whitespace from the start of the first span and the end of the last.
+pub fn trim_spans(spans: &mut [Span]) {
+    if let Some(last) = spans.last_mut() {
+        last.text.truncate(last.text.trim_end().len());
+    }
+    if let Some(first) = spans.first_mut() {
+        first.text = first.text.trim_start().to_string();
+    }
+}
+
+/// Read a file into blocks. DOCX, PDF and XLSX blocks also carry their
+/// `start` and `end` in the text `extract_text` returns, recorded as the file
+/// is read.
+pub fn file_to_tree(path: &str, options: &ExtractOptions) -> Result<DocumentTree, BlitzParseError> {
+    read_tree(path, options)
+}
+
+/// Give each located block the span of the text ranges its locator contains.
+pub(crate) fn locate_blocks(blocks: &mut [Block], provenance: &[Provenance]) {
+    for block in blocks {
+        let Some(source) = &block.source else {
+            continue;
+        };
+        let mut covered = provenance.iter().filter(|p| source.contains(&p.locator));
+        if let Some(first) = covered.next() {
+            block.start = Some(first.start);
+            block.end = Some(covered.next_back().unwrap_or(first).end);
+        }
+    }
+}
+
+/// Read a file into blocks, choosing the richest structure its format has:
+/// DOCX, HTML and EPUB structure, PDF outline headings, sheets of XLSX,
+/// tables for CSV, cells for notebooks, code for source files, and sections
+/// or paragraphs of text for everything else. Extensions a caller's parser
+/// has taken over are read through that parser.
+pub(crate) fn read_tree(
+    path: &str,
+    options: &ExtractOptions,
+) -> Result<DocumentTree, BlitzParseError> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase());
+    let options = options.started();
+    options
+        .check_input(std::fs::metadata(path)?.len())
+        .map_err(|e| e.with_path(path))?;
+
+    // A parser registered by the caller replaces the built-in structure for
+    // its extensions; the tree is then built from its `ParsedDocument`.
+    let custom = extension.as_deref().is_some_and(|ext| {
+        let registry = ParserRegistry::global().read().unwrap();
+        registry.for_extension(ext).is_some() && !registry.is_builtin(ext)
+    });
+
+    let tree = match extension.as_deref() {
+        _ if custom => document_tree(path, &options),
+        Some("docx") => crate::input::with_file_bytes_with(path, &options, |bytes| {
+            parsers::docx::docx_to_tree(bytes, &options)
+        }),
+        Some("pdf") => crate::input::with_file_bytes_with(path, &options, |bytes| {
+            parsers::pdf::pdf_to_tree(bytes, &options)
+        }),
+        Some("xlsx") => crate::input::with_file_bytes_with(path, &options, |bytes| {
+            parsers::xlsx::xlsx_to_tree(bytes, &options)
+        }),
+        Some("epub") => crate::input::with_file_bytes_with(path, &options, |bytes| {
+            parsers::epub::epub_to_tree(bytes, &options)
+        }),
+        Some("html" | "htm" | "xhtml") => parsers::txt::read_txt(path, true)
+            .map(|html| DocumentTree::new(parsers::html::html_to_blocks(&html.text))),
+        Some("csv" | "tsv") => parsers::csv::read_csv(path).map(|table| {
+            let mut rows = Vec::with_capacity(table.rows.len() + 1);
+            rows.push(table.headers.iter().map(|h| vec![Span::plain(h)]).collect());
+            for row in &table.rows {
+                rows.push(row.iter().map(|cell| vec![Span::plain(cell)]).collect());
+            }
+            DocumentTree::new(vec![BlockKind::Table { rows }.into()])
+        }),
+        Some("ipynb") => parsers::notebook::read_ipynb(path).map(|notebook| {
+            let mut blocks = Vec::new();
+            for (i, cell) in notebook.cells.iter().enumerate() {
+                let kinds = match cell.cell_type.as_str() {
+                    "markdown" => markdown_cell(&cell.source),
+                    "code" => vec![BlockKind::Code {
+                        language: notebook.metadata.language.clone(),
+                        text: cell.source.clone(),
+                    }],
+                    _ => vec![BlockKind::Code {
+                        language: None,
+                        text: cell.source.clone(),
+                    }],
+                };
+                blocks.extend(kinds.into_iter().map(|kind| Block {
+                    style: Some(cell.cell_type.clone()),
+                    source: Some(Locator::Cell { cell: i }),
+                    ..Block::from(kind)
+                }));
+            }
+            blocks.retain(|block| match &block.kind {
+                BlockKind::Code { text, .. } => !text.trim().is_empty(),
+                _ => true,
+            });
+            DocumentTree::new(blocks)
+        }),
+        Some(ext) if parsers::code::Language::from_extension(ext).is_some() => {
+            parsers::code::read_code(path).map(|file| {
+                DocumentTree::new(vec![BlockKind::Code {
+                    language: Some(file.language.name().to_string()),
+                    text: file.source,
+                }
+                .into()])
+            })
+        }
+        _ => document_tree(path, &options),
+    };
+    tree.and_then(|tree| options.checkpoint().map(|()| tree))
+        .map(|mut tree| {
+            tree.format = extension;
+            for warning in &mut tree.warnings {
+                warning.context.path.get_or_insert_with(|| path.to_string());
+            }
+            tree
+        })
+        .map_err(|e| e.with_path(path))
+}
+
+/// The sections or paragraphs of the text `extract_document_with` returns.
+fn document_tree(path: &str, options: &ExtractOptions) -> Result<DocumentTree, BlitzParseError> {
+    let document = crate::extract_document_with(path, options)?;
+    let mut blocks = Vec::new();
+    if document.sections.is_empty() {
+        blocks.extend(paragraphs(&document.text));
+    }
+    for section in &document.sections {
+        blocks.push(
+            BlockKind::Heading {
+                level: section.level + 1,
+                spans: vec![Span::plain(&section.title)],
+            }
+            .into(),
+        );
+        blocks.extend(paragraphs(&section.content));
+    }
+    let mut tree = DocumentTree::new(blocks);
+    tree.warnings = document.warnings;
+    Ok(tree)
+}
+
+/// Plain text as paragraphs separated by blank lines.
+fn paragraphs(text: &str) -> impl Iterator<Item = Block> + '_ {
+    text.split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            BlockKind::Paragraph {
+                spans: vec![Span::plain(p)],
+            }
+            .into()
+        })
+}
+
+/// The headings and paragraphs of a notebook's Markdown cell. Inline
+/// Markdown is kept as text.
+fn markdown_cell(source: &str) -> Vec<BlockKind> {
+    source
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let level = p.len() - p.trim_start_matches('#').len();
+            match p[level..].strip_prefix(' ') {
+                Some(title) if (1..=6).contains(&level) && !title.contains('\n') => {
+                    BlockKind::Heading {
+                        level,
+                        spans: vec![Span::plain(title)],
+                    }
+                }
+                _ => BlockKind::Paragraph {
+                    spans: vec![Span::plain(p)],
+                },
+            }
+        })
+        .collect()
+}
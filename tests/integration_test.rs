@@ -43,3 +43,589 @@ fn test_unsupported_file_type() {
         "Should return an error for unsupported file types"
     );
 }
+
+#[test]
+fn test_pdf_streams_page_by_page() {
+    let blocks: Vec<String> =
+        blitz_parse::stream::iter_blocks("tests/assets/sample.pdf", &Default::default())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+    assert!(blocks.len() > 1, "sample.pdf has several pages");
+    assert_eq!(
+        blocks.concat(),
+        extract_text("tests/assets/sample.pdf").unwrap()
+    );
+
+    let strategy = blitz_parse::graph_builder::chunker::ChunkingStrategy::ByLine();
+    let chunks: Vec<String> =
+        blitz_parse::stream::iter_chunks("tests/assets/sample.pdf", &strategy, &Default::default())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+    assert!(chunks
+        .iter()
+        .any(|c| c.contains("scelerisque sit amet ligula eu")));
+}
+
+/// A PDF with one line of Helvetica text per page, at the given position.
+fn write_pdf(path: &std::path::Path, pages: &[(&str, f64, f64)]) {
+    use pdf_extract::content::{Content, Operation};
+    use pdf_extract::{dictionary, Document, Object, Stream};
+
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+    let resources_id = doc.add_object(dictionary! {
+        "Font" => dictionary! { "F1" => font_id },
+    });
+    let kids: Vec<Object> = pages
+        .iter()
+        .map(|&(text, x, y)| {
+            let content = Content {
+                operations: vec![
+                    Operation::new("BT", vec![]),
+                    Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                    Operation::new("Td", vec![x.into(), y.into()]),
+                    Operation::new("Tj", vec![Object::string_literal(text)]),
+                    Operation::new("ET", vec![]),
+                ],
+            };
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+            doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+            })
+            .into()
+        })
+        .collect();
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => kids.len() as i64,
+            "Kids" => kids,
+            "Resources" => resources_id,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).unwrap();
+}
+
+#[test]
+fn test_pdf_pages_stream_with_the_extracted_layout() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pages.pdf");
+    // Each page continues the line the one before it ended on.
+    write_pdf(
+        &path,
+        &[
+            ("First", 72., 700.),
+            ("Second", 300., 700.),
+            ("Third", 72., 650.),
+        ],
+    );
+    let path = path.to_str().unwrap();
+
+    let blocks: Vec<String> = blitz_parse::stream::iter_blocks(path, &Default::default())
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(blocks.len(), 3);
+    let text = extract_text(path).unwrap();
+    assert!(text.contains("First Second"), "{:?}", text);
+    assert_eq!(blocks.concat(), text);
+}
+
+#[test]
+fn test_limits_stop_untrusted_input() {
+    use blitz_parse::errors::{BlitzParseError, LimitKind};
+    use blitz_parse::options::ExtractOptions;
+
+    let kind = |path: &str, options: ExtractOptions| match blitz_parse::extract_text_with(
+        path, &options,
+    ) {
+        Err(BlitzParseError::LimitExceeded { kind, .. }) => Some(kind),
+        _ => None,
+    };
+    let options = || ExtractOptions::default();
+
+    let mut pages = options();
+    pages.max_pages = Some(0);
+    assert_eq!(
+        kind("tests/assets/sample.pdf", pages),
+        Some(LimitKind::Pages)
+    );
+
+    let mut entry = options();
+    entry.max_entry_bytes = Some(1024);
+    assert_eq!(
+        kind("tests/assets/sample.docx", entry),
+        Some(LimitKind::EntryBytes)
+    );
+
+    let mut elements = options();
+    elements.max_xml_elements = Some(10);
+    assert_eq!(
+        kind("tests/assets/sample.docx", elements),
+        Some(LimitKind::XmlElements)
+    );
+
+    let mut input = options();
+    input.max_input_bytes = Some(10);
+    assert_eq!(
+        kind("tests/assets/sample.pdf", input),
+        Some(LimitKind::InputBytes)
+    );
+
+    let mut generous = options();
+    generous.max_pages = Some(100);
+    generous.max_entry_bytes = Some(64 << 20);
+    assert!(blitz_parse::extract_text_with("tests/assets/sample.docx", &generous).is_ok());
+    assert!(blitz_parse::extract_text_with("tests/assets/sample.pdf", &generous).is_ok());
+}
+
+#[test]
+fn test_progress_and_cancellation() {
+    use blitz_parse::errors::BlitzParseError;
+    use blitz_parse::options::ExtractOptions;
+    use blitz_parse::progress::{CancellationToken, ProgressCallback};
+    use std::sync::{Arc, Mutex};
+
+    let updates = Arc::new(Mutex::new(Vec::new()));
+    let sink = updates.clone();
+    let options = ExtractOptions {
+        progress: Some(ProgressCallback::new(move |p| {
+            sink.lock()
+                .unwrap()
+                .push((p.stage, p.unit, p.done, p.total))
+        })),
+        ..Default::default()
+    };
+    blitz_parse::extract_text_with("tests/assets/sample.pdf", &options).unwrap();
+    blitz_parse::extract_text_with("tests/assets/sample.docx", &options).unwrap();
+    let updates = updates.lock().unwrap();
+    let pages: Vec<_> = updates.iter().filter(|u| u.0 == "pdf").collect();
+    assert!(!pages.is_empty());
+    assert!(pages.iter().all(|u| u.1 == "pages" && u.2 <= u.3.unwrap()));
+    assert!(updates
+        .iter()
+        .any(|u| u.0 == "docx" && u.1 == "bytes" && Some(u.2) == u.3));
+
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let options = ExtractOptions {
+        cancel: Some(cancel),
+        ..Default::default()
+    };
+    for path in ["tests/assets/sample.pdf", "tests/assets/sample.docx"] {
+        assert!(matches!(
+            blitz_parse::extract_text_with(path, &options),
+            Err(BlitzParseError::Cancelled)
+        ));
+    }
+    let strategy = blitz_parse::graph_builder::chunker::ChunkingStrategy::ByLine();
+    assert!(matches!(
+        blitz_parse::build_graph_with(
+            "tests/assets/sample.pdf",
+            &Default::default(),
+            &strategy,
+            &options
+        ),
+        Err(BlitzParseError::Cancelled)
+    ));
+}
+
+#[test]
+fn test_errors_carry_structured_context() {
+    use blitz_parse::errors::BlitzParseError;
+    use std::error::Error;
+    use std::io::Write;
+
+    let dir = tempfile::tempdir().unwrap();
+
+    // A ZIP without word/document.xml, which docx-rs can't read either.
+    let path = dir.path().join("empty.docx");
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    writer
+        .start_file("[Content_Types].xml", zip::write::FileOptions::default())
+        .unwrap();
+    writer.write_all(b"<Types/>").unwrap();
+    writer.finish().unwrap();
+    let path = path.to_str().unwrap();
+    match extract_text(path) {
+        Err(e @ BlitzParseError::MissingPart { .. }) => {
+            assert_eq!(e.category(), "missing_part");
+            let context = e.context().unwrap();
+            assert_eq!(context.path.as_deref(), Some(path));
+            assert_eq!(context.part.as_deref(), Some("word/document.xml"));
+        }
+        other => panic!("expected MissingPart, got {:?}", other),
+    }
+
+    // The docx-rs fallback only sees parts read within the entry limit.
+    let path = dir.path().join("bomb.docx");
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    writer
+        .start_file("word/media/image1.png", zip::write::FileOptions::default())
+        .unwrap();
+    writer.write_all(&vec![0; 1 << 20]).unwrap();
+    writer.finish().unwrap();
+    let options = blitz_parse::options::ExtractOptions {
+        max_entry_bytes: Some(64 * 1024),
+        ..Default::default()
+    };
+    match blitz_parse::extract_text_with(path.to_str().unwrap(), &options) {
+        Err(e @ BlitzParseError::LimitExceeded { .. }) => {
+            let part = e.context().unwrap().part.as_deref();
+            assert_eq!(part, Some("word/media/image1.png"));
+        }
+        other => panic!("expected LimitExceeded, got {:?}", other),
+    }
+
+    let path = dir.path().join("truncated.pdf");
+    std::fs::write(&path, b"%PDF-1.7\n1 0 obj\n<<").unwrap();
+    let error = extract_text(path.to_str().unwrap()).unwrap_err();
+    assert_eq!(error.category(), "corrupt_container");
+    assert!(error.source().is_some());
+
+    // Every parser's errors carry the path and their cause.
+    let path = dir.path().join("broken.ipynb");
+    std::fs::write(&path, b"{\"cells\": [").unwrap();
+    let path = path.to_str().unwrap();
+    let error = extract_text(path).unwrap_err();
+    assert_eq!(error.format(), Some("ipynb"));
+    assert_eq!(error.context().unwrap().path.as_deref(), Some(path));
+    assert!(error.source().is_some());
+
+    let path = dir.path().join("broken.xml");
+    std::fs::write(&path, b"<root><a></b></root>").unwrap();
+    let path = path.to_str().unwrap();
+    let error = extract_text(path).unwrap_err();
+    assert_eq!(error.category(), "corrupt_container");
+    let context = error.context().unwrap();
+    assert_eq!(context.path.as_deref(), Some(path));
+    assert!(context.offset.is_some());
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn test_extraction_recovers_past_broken_parts() {
+    use blitz_parse::document::{Strategy, WarningKind};
+    use std::io::Write;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("broken.docx");
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    writer
+        .start_file("word/document.xml", zip::write::FileOptions::default())
+        .unwrap();
+    writer
+        .write_all(
+            b"<w:document><w:body><w:p><w:r><w:t>Kept paragraph</w:t></w:r></w:p>\
+              <w:p><w:r><w:t>Broken</w:x></w:r></w:p></w:body></w:document>",
+        )
+        .unwrap();
+    writer.finish().unwrap();
+    let path = path.to_str().unwrap();
+
+    let result = blitz_parse::extract(path).unwrap();
+    assert!(result.content.contains("Kept paragraph"));
+    assert_eq!(result.strategy, Strategy::FastPath);
+    assert_eq!(result.warnings.len(), 1);
+    let warning = &result.warnings[0];
+    assert_eq!(warning.kind, WarningKind::Skipped);
+    assert_eq!(warning.context.path.as_deref(), Some(path));
+    assert_eq!(warning.context.part.as_deref(), Some("word/document.xml"));
+    assert!(warning.context.offset.is_some());
+
+    // The partial document keeps the provenance of what was read.
+    assert_eq!(result.provenance.len(), result.content.lines().count());
+    assert_eq!(result.provenance[0].end, "Kept paragraph".chars().count());
+
+    // The text-only parser API still reports the failure.
+    let bytes = std::fs::read(path).unwrap();
+    assert!(matches!(
+        blitz_parse::parsers::docx::parse_docx_bytes(&bytes),
+        Err(blitz_parse::errors::BlitzParseError::PartialSuccess { .. })
+    ));
+
+    assert_eq!(extract_text(path).unwrap(), result.content);
+    assert!(blitz_parse::extract("tests/assets/sample.docx")
+        .unwrap()
+        .warnings
+        .is_empty());
+}
+
+/// A DOCX with headings, emphasis, a link, a footnote, a nested list, a
+/// table and code paragraphs.
+fn write_report_docx(dir: &std::path::Path) -> String {
+    use std::io::Write;
+
+    let path = dir.join("report.docx");
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    let parts: [(&str, &str); 5] = [
+        (
+            "word/document.xml",
+            r#"<w:document xmlns:w="w" xmlns:r="r"><w:body>
+            <w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Summary</w:t></w:r></w:p>
+            <w:p><w:r><w:t xml:space="preserve">Plain </w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>bold</w:t></w:r>
+              <w:r><w:t xml:space="preserve"> and </w:t></w:r><w:r><w:rPr><w:i/></w:rPr><w:t>italic</w:t></w:r>
+              <w:r><w:rPr><w:b w:val="0"/></w:rPr><w:t xml:space="preserve"> see </w:t></w:r>
+              <w:hyperlink r:id="rId9"><w:r><w:t xml:space="preserve">the site </w:t></w:r></w:hyperlink>
+              <w:r><w:footnoteReference w:id="1"/></w:r></w:p>
+            <w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>First</w:t></w:r></w:p>
+            <w:p><w:pPr><w:numPr><w:ilvl w:val="1"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>Nested</w:t></w:r></w:p>
+            <w:tbl><w:tr><w:tc><w:p><w:r><w:t>Key</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>Value</w:t></w:r></w:p></w:tc></w:tr>
+              <w:tr><w:tc><w:p><w:r><w:t>a|b</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>1</w:t></w:r></w:p><w:p><w:r><w:t>2</w:t></w:r></w:p></w:tc></w:tr></w:tbl>
+            <w:p><w:pPr><w:pStyle w:val="SourceCode"/></w:pPr><w:r><w:t>let x = 1;</w:t></w:r></w:p>
+            <w:p><w:pPr><w:pStyle w:val="SourceCode"/></w:pPr><w:r><w:t xml:space="preserve">  x * 2</w:t></w:r></w:p>
+            </w:body></w:document>"#,
+        ),
+        (
+            "word/styles.xml",
+            r#"<w:styles xmlns:w="w"><w:style w:type="paragraph" w:styleId="SourceCode"><w:name w:val="Source Code"/></w:style></w:styles>"#,
+        ),
+        (
+            "word/numbering.xml",
+            r#"<w:numbering xmlns:w="w"><w:abstractNum w:abstractNumId="3">
+              <w:lvl w:ilvl="0"><w:numFmt w:val="bullet"/></w:lvl><w:lvl w:ilvl="1"><w:numFmt w:val="decimal"/></w:lvl>
+            </w:abstractNum><w:num w:numId="1"><w:abstractNumId w:val="3"/></w:num></w:numbering>"#,
+        ),
+        (
+            "word/_rels/document.xml.rels",
+            r#"<Relationships><Relationship Id="rId9" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="https://example.com" TargetMode="External"/></Relationships>"#,
+        ),
+        (
+            "word/footnotes.xml",
+            r#"<w:footnotes xmlns:w="w"><w:footnote w:type="separator" w:id="-1"><w:p><w:r><w:separator/></w:r></w:p></w:footnote>
+            <w:footnote w:id="1"><w:p><w:r><w:footnoteRef/></w:r><w:r><w:t xml:space="preserve"> Source note.</w:t></w:r></w:p></w:footnote></w:footnotes>"#,
+        ),
+    ];
+    for (name, xml) in parts {
+        writer
+            .start_file(name, zip::write::FileOptions::default())
+            .unwrap();
+        writer.write_all(xml.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_docx_renders_as_markdown() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = &write_report_docx(dir.path());
+
+    assert_eq!(
+        blitz_parse::extract_markdown(path).unwrap(),
+        "# Summary\n\n\
+         Plain **bold** and *italic* see [the site](https://example.com) [^1]\n\n\
+         - First\n   1. Nested\n\n\
+         | Key | Value |\n| --- | --- |\n| a\\|b | 1<br>2 |\n\n\
+         ```\nlet x = 1;\n  x * 2\n```\n\n\
+         [^1]: Source note."
+    );
+    // Plain text keeps table rows on their own lines.
+    assert!(extract_text(path).unwrap().contains("| a|b | 1 2 |"));
+
+    let sample = blitz_parse::extract_markdown("tests/assets/sample.docx").unwrap();
+    assert!(sample.contains("| --- |"));
+    assert!(blitz_parse::extract_markdown("tests/assets/sample.pdf")
+        .unwrap()
+        .contains("Vestibulum neque massa"));
+}
+
+#[test]
+fn test_document_tree_renders_as_html_and_json() {
+    use blitz_parse::tree::{BlockKind, DocumentTree, Locator, SCHEMA_VERSION};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = &write_report_docx(dir.path());
+
+    let tree = blitz_parse::extract_tree(path).unwrap();
+    assert_eq!(tree.format.as_deref(), Some("docx"));
+    assert_eq!(tree.blocks.len(), 6);
+    assert!(matches!(
+        tree.blocks[0].kind,
+        BlockKind::Heading { level: 1, .. }
+    ));
+    assert_eq!(tree.blocks[0].style.as_deref(), Some("Heading1"));
+    let paragraph = |block: usize| match &tree.blocks[block].source {
+        Some(Locator::Docx {
+            part, paragraph, ..
+        }) => {
+            assert_eq!(part, "word/document.xml");
+            *paragraph
+        }
+        other => panic!("unexpected source {:?}", other),
+    };
+    // The table's four cells hold five paragraphs.
+    assert_eq!((paragraph(4), paragraph(5)), (4, 9));
+    // Blocks point into the plain text extraction.
+    let text = extract_text(path).unwrap();
+    let span = |block: usize| {
+        let chars: Vec<char> = text.chars().collect();
+        let (start, end) = (
+            tree.blocks[block].start.unwrap(),
+            tree.blocks[block].end.unwrap(),
+        );
+        chars[start..end].iter().collect::<String>()
+    };
+    assert_eq!(span(0), "Summary");
+    assert_eq!(span(5), "let x = 1;\nx * 2");
+
+    let json = blitz_parse::extract_json(path).unwrap();
+    assert!(json.starts_with("{\n  \"schema_version\": 1,"));
+    let read: DocumentTree = serde_json::from_str(&json).unwrap();
+    assert_eq!(read, tree);
+    assert_eq!(read.schema_version, SCHEMA_VERSION);
+
+    let html = blitz_parse::extract_html(path).unwrap();
+    assert!(
+        html.starts_with("<h1>Summary</h1>\n<p>Plain <strong>bold</strong> and <em>italic</em>")
+    );
+    assert!(html.contains("<a href=\"https://example.com\">the site </a>"));
+    assert!(html.contains("<ul>\n<li>First<ol>\n<li>Nested</li>\n</ol>\n</li>\n</ul>"));
+    assert!(html.contains("<td>a|b</td><td>1<br>2</td>"));
+    assert!(html.contains("<pre><code>let x = 1;\n  x * 2</code></pre>"));
+    assert!(html.contains("<p id=\"fn-1\"><sup>1</sup> Source note.</p>"));
+}
+
+#[test]
+fn test_spans_carry_offsets_and_locators() {
+    use blitz_parse::provenance::Locator;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = &write_report_docx(dir.path());
+
+    let result = blitz_parse::extract(path).unwrap();
+    let chars: Vec<char> = result.content.chars().collect();
+    let spans: Vec<(String, &Locator)> = result
+        .provenance
+        .iter()
+        .map(|p| (chars[p.start..p.end].iter().collect(), &p.locator))
+        .collect();
+    assert_eq!(spans[0].0, "Summary");
+    assert!(matches!(spans[0].1, Locator::Docx { paragraph: 0, .. }));
+    let row = spans
+        .iter()
+        .find(|(text, _)| text == "| a|b | 1 2 |")
+        .unwrap();
+    assert!(matches!(row.1, Locator::Docx { paragraph: 6, .. }));
+
+    // Chunks record where they came from.
+    let graph = blitz_parse::build_graph(
+        path,
+        &std::collections::HashMap::new(),
+        &blitz_parse::graph_builder::chunker::ChunkingStrategy::ByLine(),
+    )
+    .unwrap();
+    let chunks: Vec<_> = graph
+        .nodes
+        .iter()
+        .filter(|node| node.label == "Chunk")
+        .collect();
+    assert!(!chunks.is_empty());
+    for chunk in chunks {
+        let start = chunk.properties["start"].parse::<usize>().unwrap();
+        let end = chunk.properties["end"].parse::<usize>().unwrap();
+        assert!(start < end && end <= chars.len());
+        assert!(chunk.properties["source"].contains("\"kind\":\"docx\""));
+    }
+}
+
+#[test]
+fn test_xlsx_sheets_and_cells() {
+    use blitz_parse::provenance::Locator;
+    use blitz_parse::tree::BlockKind;
+    use std::io::Write;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("book.xlsx");
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    let parts: [(&str, &str); 5] = [
+        (
+            "xl/workbook.xml",
+            r#"<workbook xmlns:r="r"><sheets><sheet name="Totals" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+        ),
+        (
+            "xl/_rels/workbook.xml.rels",
+            r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/></Relationships>"#,
+        ),
+        (
+            "xl/sharedStrings.xml",
+            r#"<sst><si><t>Item</t></si><si><t>Cost</t></si><si><r><t>Ink</t></r></si></sst>"#,
+        ),
+        (
+            "xl/worksheets/sheet1.xml",
+            r#"<worksheet><sheetData>
+            <row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="s"><v>1</v></c></row>
+            <row r="2"><c r="A2" t="s"><v>2</v></c><c r="C2"><v>4.5</v></c></row>
+            <row r="3"><c r="A3" s="1"><v>45352</v></c><c r="B3" s="2"><v>45352.5</v></c></row>
+            </sheetData></worksheet>"#,
+        ),
+        (
+            "xl/styles.xml",
+            r#"<styleSheet><numFmts count="1"><numFmt numFmtId="164" formatCode="yyyy\-mm\-dd h:mm"/></numFmts>
+            <cellStyleXfs count="1"><xf numFmtId="0"/></cellStyleXfs>
+            <cellXfs count="3"><xf numFmtId="0"/><xf numFmtId="14"/><xf numFmtId="164"/></cellXfs></styleSheet>"#,
+        ),
+    ];
+    for (name, xml) in parts {
+        writer
+            .start_file(name, zip::write::FileOptions::default())
+            .unwrap();
+        writer.write_all(xml.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap();
+    let path = path.to_str().unwrap();
+
+    let result = blitz_parse::extract(path).unwrap();
+    assert_eq!(
+        result.content,
+        "Totals\nItem | Cost\nInk |  | 4.5\n2024-03-01 | 2024-03-01 12:00:00"
+    );
+    let located: Vec<_> = result
+        .provenance
+        .iter()
+        .map(|p| (p.start, p.end, &p.locator))
+        .collect();
+    assert_eq!(located.len(), 4);
+    assert_eq!(
+        located[2],
+        (
+            19,
+            31,
+            &Locator::Xlsx {
+                sheet: "Totals".to_string(),
+                range: Some("A2:C2".to_string()),
+            }
+        )
+    );
+
+    let tree = blitz_parse::extract_tree(path).unwrap();
+    assert_eq!(tree.format.as_deref(), Some("xlsx"));
+    assert!(matches!(tree.blocks[1].kind, BlockKind::Table { .. }));
+    assert_eq!(
+        tree.blocks[1].source,
+        Some(Locator::Xlsx {
+            sheet: "Totals".to_string(),
+            range: Some("A1:C3".to_string()),
+        })
+    );
+    assert_eq!(
+        (tree.blocks[1].start, tree.blocks[1].end),
+        (Some(7), Some(64))
+    );
+}